@@ -0,0 +1,98 @@
+//! Reads blocks, events and runtime API results from a node over its HTTP RPC server.
+
+use codec::Decode;
+use frame_system::EventRecord;
+use jsonrpc_core_client::{transports::http, RpcChannel};
+use nexus_runtime::{
+	nexus_api_final::PaymentTransition, AccountId, Block, BlockNumber, Event, Hash, Header,
+	SignedBlock,
+};
+use sc_rpc_api::{chain::ChainClient, state::StateClient};
+use sp_core::{
+	storage::{StorageData, StorageKey},
+	twox_128, Bytes,
+};
+use sp_rpc::{list::ListOrValue, number::NumberOrHex};
+use sp_runtime::traits::Header as HeaderT;
+
+use crate::error::{Error, Result};
+
+/// A connection to the RPC server of a node.
+pub struct NodeClient {
+	chain: ChainClient<BlockNumber, Hash, Header, SignedBlock>,
+	state: StateClient<Hash>,
+}
+
+impl NodeClient {
+	/// Connect to the node serving HTTP RPC at `url`.
+	pub async fn connect(url: &str) -> Result<Self> {
+		let channel: RpcChannel = http::connect(url).await?;
+
+		Ok(NodeClient {
+			chain: channel.clone().into(),
+			state: channel.into(),
+		})
+	}
+
+	/// The hash of block `number` of the best chain.
+	pub async fn block_hash(&self, number: BlockNumber) -> Result<Hash> {
+		let hash = self
+			.chain
+			.block_hash(Some(ListOrValue::Value(NumberOrHex::Number(number.into()))))
+			.await?;
+
+		match hash {
+			ListOrValue::Value(Some(hash)) => Ok(hash),
+			_ => Err(Error::Chain(format!("The node has no block {}.", number))),
+		}
+	}
+
+	/// The number of the last finalized block.
+	pub async fn finalized_number(&self) -> Result<BlockNumber> {
+		let hash = self.chain.finalized_head().await?;
+		let header = self.chain.header(Some(hash)).await?.ok_or_else(|| {
+			Error::Chain(format!(
+				"The node has no header for finalized block {}.",
+				hash
+			))
+		})?;
+
+		Ok(*header.number())
+	}
+
+	/// The block with the given hash.
+	pub async fn block(&self, hash: Hash) -> Result<Block> {
+		self.chain
+			.block(Some(hash))
+			.await?
+			.map(|signed| signed.block)
+			.ok_or_else(|| Error::Chain(format!("The node has no block {}.", hash)))
+	}
+
+	/// The events deposited by the block with the given hash.
+	pub async fn events(&self, hash: Hash) -> Result<Vec<EventRecord<Event, Hash>>> {
+		let key = [twox_128(b"System"), twox_128(b"Events")].concat();
+
+		match self.state.storage(StorageKey(key), Some(hash)).await? {
+			Some(StorageData(data)) => Ok(Decode::decode(&mut &data[..])?),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	/// The payments whose status the block with the given hash changed.
+	pub async fn payment_transitions(
+		&self,
+		hash: Hash,
+	) -> Result<Vec<PaymentTransition<AccountId, BlockNumber>>> {
+		let result = self
+			.state
+			.call(
+				"NexusPaymentTransitionApi_payment_transitions".into(),
+				Bytes(Vec::new()),
+				Some(hash),
+			)
+			.await?;
+
+		Ok(Decode::decode(&mut &result[..])?)
+	}
+}
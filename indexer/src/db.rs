@@ -0,0 +1,187 @@
+//! The SQLite database the events are projected into.
+
+use std::path::Path;
+
+use nexus_runtime::{nexus_api_final::PaymentTransition, AccountId, BlockNumber, Event, Hash};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+	error::{Error, Result},
+	projection::{self, Extrinsic},
+};
+
+/// The tables, created when a database is first opened.
+///
+/// Accounts are stored as SS58 addresses and amounts as decimal text, since they do not fit
+/// into an SQLite integer.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+	key TEXT PRIMARY KEY,
+	value BLOB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS blocks (
+	number INTEGER PRIMARY KEY,
+	hash BLOB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS quotes (
+	fxp TEXT NOT NULL,
+	quote_uuid BLOB NOT NULL,
+	fxp_uuid BLOB,
+	source_currency BLOB NOT NULL,
+	destination_currency BLOB NOT NULL,
+	rate BLOB NOT NULL,
+	public INTEGER,
+	source_lp BLOB,
+	destination_lp BLOB,
+	source_bank_id BLOB,
+	provided_at INTEGER NOT NULL,
+	locked_by TEXT,
+	expires_at INTEGER,
+	deleted_at INTEGER,
+	PRIMARY KEY (fxp, quote_uuid)
+);
+
+CREATE TABLE IF NOT EXISTS sld_versions (
+	ips TEXT NOT NULL,
+	country_id BLOB NOT NULL,
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	iban INTEGER NOT NULL,
+	local_bank_number BLOB NOT NULL,
+	local_bank_id BLOB NOT NULL,
+	alias_conversion INTEGER NOT NULL,
+	alias_name BLOB NOT NULL,
+	alias_format BLOB NOT NULL,
+	alias_desc BLOB NOT NULL,
+	max_destination_value BLOB NOT NULL,
+	account_validation_available INTEGER NOT NULL,
+	payee_type INTEGER NOT NULL,
+	ips_timeout BLOB NOT NULL,
+	PRIMARY KEY (ips, country_id, block_number, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS payee_lookups (
+	psp TEXT NOT NULL,
+	lookup_id BLOB NOT NULL,
+	destination_country_id BLOB,
+	destination_bank_identifier BLOB,
+	destination_bank_account_number BLOB,
+	holder_name BLOB,
+	display_name BLOB,
+	confirmed INTEGER NOT NULL DEFAULT 0,
+	updated_at INTEGER NOT NULL,
+	erased_at INTEGER,
+	tombstone BLOB,
+	PRIMARY KEY (psp, lookup_id)
+);
+
+CREATE TABLE IF NOT EXISTS payments (
+	source_psp TEXT NOT NULL,
+	payment_uuid BLOB NOT NULL,
+	dest_psp TEXT,
+	state TEXT NOT NULL,
+	destination_amount TEXT,
+	created_at INTEGER NOT NULL,
+	updated_at INTEGER NOT NULL,
+	PRIMARY KEY (source_psp, payment_uuid)
+);
+
+CREATE TABLE IF NOT EXISTS payment_status_history (
+	source_psp TEXT NOT NULL,
+	payment_uuid BLOB NOT NULL,
+	block_number INTEGER NOT NULL,
+	state TEXT NOT NULL,
+	PRIMARY KEY (source_psp, payment_uuid, block_number)
+);
+";
+
+/// An index of the finalized blocks of one chain.
+pub struct Database {
+	connection: Connection,
+}
+
+impl Database {
+	/// Open the database at `path`, creating it if needed.
+	pub fn open(path: &Path) -> Result<Self> {
+		Self::new(Connection::open(path)?)
+	}
+
+	/// Open a database that only lives in memory.
+	pub fn open_in_memory() -> Result<Self> {
+		Self::new(Connection::open_in_memory()?)
+	}
+
+	fn new(connection: Connection) -> Result<Self> {
+		connection.execute_batch(SCHEMA)?;
+		Ok(Database { connection })
+	}
+
+	/// The connection, to query the tables.
+	pub fn connection(&self) -> &Connection {
+		&self.connection
+	}
+
+	/// Record the genesis hash of the chain on first use, and refuse a chain with another one.
+	pub fn check_genesis(&self, genesis: Hash) -> Result<()> {
+		let stored: Option<Vec<u8>> = self
+			.connection
+			.query_row(
+				"SELECT value FROM meta WHERE key = 'genesis_hash'",
+				params![],
+				|row| row.get(0),
+			)
+			.optional()?;
+
+		match stored {
+			Some(stored) if stored != genesis.as_bytes() => Err(Error::Chain(format!(
+				"The database indexes the chain with genesis 0x{}, not {}.",
+				hex::encode(stored),
+				genesis
+			))),
+			Some(_) => Ok(()),
+			None => {
+				self.connection.execute(
+					"INSERT INTO meta (key, value) VALUES ('genesis_hash', ?1)",
+					params![genesis.as_bytes()],
+				)?;
+				Ok(())
+			}
+		}
+	}
+
+	/// The last block indexed, if any.
+	pub fn last_indexed(&self) -> Result<Option<(BlockNumber, Hash)>> {
+		Ok(self
+			.connection
+			.query_row(
+				"SELECT number, hash FROM blocks ORDER BY number DESC LIMIT 1",
+				params![],
+				|row| Ok((row.get(0)?, row.get::<_, Vec<u8>>(1)?)),
+			)
+			.optional()?
+			.map(|(number, hash)| (number, Hash::from_slice(&hash))))
+	}
+
+	/// Project a finalized block, all or nothing.
+	pub fn index_block(
+		&mut self,
+		number: BlockNumber,
+		hash: Hash,
+		extrinsics: &[Extrinsic],
+		events: &[frame_system::EventRecord<Event, Hash>],
+		transitions: &[PaymentTransition<AccountId, BlockNumber>],
+	) -> Result<()> {
+		let transaction = self.connection.transaction()?;
+
+		projection::project(&transaction, number, extrinsics, events, transitions)?;
+		transaction.execute(
+			"INSERT INTO blocks (number, hash) VALUES (?1, ?2)",
+			params![number, hash.as_bytes()],
+		)?;
+
+		transaction.commit()?;
+		Ok(())
+	}
+}
@@ -0,0 +1,52 @@
+//! Why indexing stopped.
+
+use std::fmt;
+
+/// An error of the indexer.
+#[derive(Debug)]
+pub enum Error {
+	/// The node did not answer or refused a request.
+	Rpc(jsonrpc_core_client::RpcError),
+	/// Reading or writing the SQLite database failed.
+	Database(rusqlite::Error),
+	/// Something the node returned could not be decoded with the runtime this indexer was built
+	/// with.
+	Codec(codec::Error),
+	/// The node does not follow the chain the database was built from, or is missing a block, or
+	/// the database was built with another schema.
+	Chain(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Rpc(e) => write!(f, "RPC error: {}", e),
+			Error::Database(e) => write!(f, "database error: {}", e),
+			Error::Codec(e) => write!(f, "cannot decode: {}", e),
+			Error::Chain(message) => write!(f, "{}", message),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<jsonrpc_core_client::RpcError> for Error {
+	fn from(e: jsonrpc_core_client::RpcError) -> Self {
+		Error::Rpc(e)
+	}
+}
+
+impl From<rusqlite::Error> for Error {
+	fn from(e: rusqlite::Error) -> Self {
+		Error::Database(e)
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Error::Codec(e)
+	}
+}
+
+/// The result of an indexer operation.
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,119 @@
+//! Follows a Nexus node and projects the events of its finalized blocks into SQLite tables of
+//! quotes, SLD versions, payee lookups and payments with their status history.
+//!
+//! Only finalized blocks are indexed, so a reorg never has to be undone. Events and calls are
+//! decoded with the runtime this binary is built with; rebuild it with the node after a runtime
+//! upgrade. To try it out, start `nexus-node --dev` and run
+//! `nexus-indexer --url http://127.0.0.1:9933 --database dev.sqlite`.
+
+mod client;
+mod db;
+mod error;
+mod projection;
+
+#[cfg(test)]
+mod tests;
+
+use std::{path::PathBuf, time::Duration};
+
+use futures_timer::Delay;
+use nexus_runtime::BlockNumber;
+use structopt::StructOpt;
+
+use client::NodeClient;
+use db::Database;
+use error::{Error, Result};
+use projection::Extrinsic;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+	name = "nexus-indexer",
+	about = "Projects the finalized Nexus events of a node into SQLite."
+)]
+struct Cli {
+	/// HTTP RPC endpoint of the node to follow.
+	#[structopt(long, default_value = "http://127.0.0.1:9933")]
+	url: String,
+
+	/// SQLite database to write, created if it does not exist.
+	#[structopt(long, parse(from_os_str), default_value = "nexus-index.sqlite")]
+	database: PathBuf,
+
+	/// Seconds to wait before looking for newly finalized blocks.
+	#[structopt(long, default_value = "6")]
+	interval: u64,
+
+	/// Index up to the last finalized block and exit.
+	#[structopt(long)]
+	once: bool,
+}
+
+fn main() {
+	sp_tracing::try_init_simple();
+
+	let cli = Cli::from_args();
+	if let Err(e) = futures::executor::block_on(run(cli)) {
+		log::error!("{}", e);
+		std::process::exit(1);
+	}
+}
+
+async fn run(cli: Cli) -> Result<()> {
+	let client = NodeClient::connect(&cli.url).await?;
+	let mut database = Database::open(&cli.database)?;
+	database.check_genesis(client.block_hash(0).await?)?;
+
+	loop {
+		let finalized = client.finalized_number().await?;
+		index_to(&client, &mut database, finalized).await?;
+
+		if cli.once {
+			return Ok(());
+		}
+		Delay::new(Duration::from_secs(cli.interval)).await;
+	}
+}
+
+/// Index every block after the last one indexed up to block `finalized`.
+async fn index_to(
+	client: &NodeClient,
+	database: &mut Database,
+	finalized: BlockNumber,
+) -> Result<()> {
+	let mut number = match database.last_indexed()? {
+		Some((last, hash)) => {
+			if client.block_hash(last).await? != hash {
+				return Err(Error::Chain(format!(
+					"Block #{} of the node is not the one indexed ({}).",
+					last, hash
+				)));
+			}
+			last + 1
+		}
+		None => 1,
+	};
+
+	while number <= finalized {
+		let hash = client.block_hash(number).await?;
+		let block = client.block(hash).await?;
+		let events = client.events(hash).await?;
+		let transitions = if projection::touches_payments(&events) {
+			client.payment_transitions(hash).await?
+		} else {
+			Vec::new()
+		};
+
+		database.index_block(
+			number,
+			hash,
+			&Extrinsic::from_block(&block),
+			&events,
+			&transitions,
+		)?;
+		log::info!("Indexed block #{} ({})", number, hash);
+
+		number += 1;
+	}
+
+	Ok(())
+}
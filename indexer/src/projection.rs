@@ -0,0 +1,346 @@
+//! Turns the events and extrinsics of a block into rows.
+
+use frame_system::{EventRecord, Phase};
+use nexus_runtime::{
+	nexus_api_final::{self, PaymentTransition},
+	nexus_api_payee, nexus_api_quote, nexus_api_sld, AccountId, Address, Block, BlockNumber, Call,
+	Event, Hash,
+};
+use rusqlite::{params, Transaction};
+use sp_core::crypto::Ss58Codec;
+
+use crate::error::Result;
+
+/// The signer and call of an extrinsic.
+pub struct Extrinsic {
+	pub signer: Option<AccountId>,
+	pub call: Call,
+}
+
+impl Extrinsic {
+	/// The extrinsics of `block`, in order.
+	pub fn from_block(block: &Block) -> Vec<Self> {
+		block
+			.extrinsics
+			.iter()
+			.map(|extrinsic| Extrinsic {
+				signer: extrinsic
+					.signature
+					.as_ref()
+					.and_then(|(address, _, _)| match address {
+						Address::Id(who) => Some(who.clone()),
+						_ => None,
+					}),
+				call: extrinsic.function.clone(),
+			})
+			.collect()
+	}
+}
+
+/// Whether any of `events` may have changed the status of a payment, so the payment
+/// transitions of the block are needed.
+pub fn touches_payments(events: &[EventRecord<Event, Hash>]) -> bool {
+	events
+		.iter()
+		.any(|record| matches!(record.event, Event::nexus_api_final(_)))
+}
+
+/// Write what block `number` did to the quotes, SLDs, payee lookups and payments.
+pub fn project(
+	transaction: &Transaction,
+	number: BlockNumber,
+	extrinsics: &[Extrinsic],
+	events: &[EventRecord<Event, Hash>],
+	transitions: &[PaymentTransition<AccountId, BlockNumber>],
+) -> Result<()> {
+	for transition in transitions {
+		project_transition(transaction, number, transition)?;
+	}
+
+	for (index, record) in events.iter().enumerate() {
+		let extrinsic = match record.phase {
+			Phase::ApplyExtrinsic(i) => extrinsics.get(i as usize),
+			_ => None,
+		};
+
+		match &record.event {
+			Event::nexus_api_quote(event) => project_quote(transaction, number, extrinsic, event)?,
+			Event::nexus_api_sld(event) => project_sld(transaction, number, index, event)?,
+			Event::nexus_api_payee(event) => project_payee(transaction, number, extrinsic, event)?,
+			Event::nexus_api_final(nexus_api_final::Event::PaymentSettled(
+				source_psp,
+				payment_uuid,
+				_,
+				destination_amount,
+			)) => {
+				transaction.execute(
+					"UPDATE payments SET destination_amount = ?3
+					WHERE source_psp = ?1 AND payment_uuid = ?2",
+					params![
+						source_psp.to_ss58check(),
+						payment_uuid,
+						destination_amount.to_string()
+					],
+				)?;
+			}
+			_ => {}
+		}
+	}
+
+	Ok(())
+}
+
+fn project_quote(
+	transaction: &Transaction,
+	number: BlockNumber,
+	extrinsic: Option<&Extrinsic>,
+	event: &nexus_api_quote::Event<nexus_runtime::Runtime>,
+) -> Result<()> {
+	match event {
+		nexus_api_quote::Event::RatesProvided(
+			source_currency,
+			destination_currency,
+			fxp,
+			quote_uuid,
+			rate,
+		) => {
+			transaction.execute(
+				"INSERT INTO quotes
+					(fxp, quote_uuid, source_currency, destination_currency, rate, provided_at)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+				ON CONFLICT (fxp, quote_uuid) DO UPDATE SET
+					source_currency = ?3, destination_currency = ?4, rate = ?5,
+					provided_at = ?6, locked_by = NULL, expires_at = NULL, deleted_at = NULL",
+				params![
+					fxp.to_ss58check(),
+					quote_uuid,
+					source_currency,
+					destination_currency,
+					rate,
+					number
+				],
+			)?;
+
+			// The rest of the quote is only in the call that provided it.
+			if let Some(Extrinsic {
+				signer: Some(signer),
+				call:
+					Call::NexusApiQuote(nexus_api_quote::Call::provide_rate(
+						_,
+						_,
+						call_quote_uuid,
+						fxp_uuid,
+						source_lp,
+						destination_lp,
+						_,
+						public,
+						_,
+						source_bank_id,
+					)),
+			}) = extrinsic
+			{
+				if signer == fxp && call_quote_uuid == quote_uuid {
+					transaction.execute(
+						"UPDATE quotes SET fxp_uuid = ?3, public = ?4, source_lp = ?5,
+							destination_lp = ?6, source_bank_id = ?7
+						WHERE fxp = ?1 AND quote_uuid = ?2",
+						params![
+							fxp.to_ss58check(),
+							quote_uuid,
+							fxp_uuid,
+							public,
+							source_lp,
+							destination_lp,
+							source_bank_id
+						],
+					)?;
+				}
+			}
+		}
+		nexus_api_quote::Event::QuoteLocked(fxp, quote_uuid, source_psp, expires_at) => {
+			transaction.execute(
+				"UPDATE quotes SET locked_by = ?3, expires_at = ?4
+				WHERE fxp = ?1 AND quote_uuid = ?2",
+				params![
+					fxp.to_ss58check(),
+					quote_uuid,
+					source_psp.to_ss58check(),
+					expires_at
+				],
+			)?;
+		}
+		nexus_api_quote::Event::RatesDeleted(_, _, fxp, quote_uuid) => {
+			transaction.execute(
+				"UPDATE quotes SET deleted_at = ?3 WHERE fxp = ?1 AND quote_uuid = ?2",
+				params![fxp.to_ss58check(), quote_uuid, number],
+			)?;
+		}
+		_ => {}
+	}
+
+	Ok(())
+}
+
+fn project_sld(
+	transaction: &Transaction,
+	number: BlockNumber,
+	index: usize,
+	event: &nexus_api_sld::Event<nexus_runtime::Runtime>,
+) -> Result<()> {
+	if let nexus_api_sld::Event::InputSet(
+		ips,
+		iban,
+		country_id,
+		local_bank_number,
+		local_bank_id,
+		alias_conversion,
+		alias_name,
+		alias_format,
+		alias_desc,
+		max_destination_value,
+		account_validation_available,
+		payee_type,
+		ips_timeout,
+	) = event
+	{
+		transaction.execute(
+			"INSERT INTO sld_versions VALUES
+				(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+			params![
+				ips.to_ss58check(),
+				country_id,
+				number,
+				index as u32,
+				iban,
+				local_bank_number,
+				local_bank_id,
+				alias_conversion,
+				alias_name,
+				alias_format,
+				alias_desc,
+				max_destination_value,
+				account_validation_available,
+				payee_type,
+				ips_timeout
+			],
+		)?;
+	}
+
+	Ok(())
+}
+
+fn project_payee(
+	transaction: &Transaction,
+	number: BlockNumber,
+	extrinsic: Option<&Extrinsic>,
+	event: &nexus_api_payee::Event<nexus_runtime::Runtime>,
+) -> Result<()> {
+	match event {
+		nexus_api_payee::Event::PaymentConfirm(psp, lookup_id, destination_bank_identifier) => {
+			transaction.execute(
+				"INSERT INTO payee_lookups
+					(psp, lookup_id, destination_bank_identifier, confirmed, updated_at)
+				VALUES (?1, ?2, ?3, 0, ?4)
+				ON CONFLICT (psp, lookup_id) DO UPDATE SET
+					destination_country_id = NULL, destination_bank_identifier = ?3,
+					destination_bank_account_number = NULL, holder_name = NULL,
+					display_name = NULL, confirmed = 0, updated_at = ?4, erased_at = NULL,
+					tombstone = NULL",
+				params![
+					psp.to_ss58check(),
+					lookup_id,
+					destination_bank_identifier,
+					number
+				],
+			)?;
+
+			// The rest of the payee looked up is only in the call that looked it up.
+			if let Some(Extrinsic {
+				signer: Some(signer),
+				call:
+					Call::NexusApiPayee(nexus_api_payee::Call::confirmation_of_payee(
+						call_lookup_id,
+						destination_country_id,
+						_,
+						destination_bank_account_number,
+					)),
+			}) = extrinsic
+			{
+				if signer != psp || call_lookup_id != lookup_id {
+					return Ok(());
+				}
+				transaction.execute(
+					"UPDATE payee_lookups SET destination_country_id = ?3,
+						destination_bank_account_number = ?4
+					WHERE psp = ?1 AND lookup_id = ?2",
+					params![
+						psp.to_ss58check(),
+						lookup_id,
+						destination_country_id,
+						destination_bank_account_number
+					],
+				)?;
+			}
+		}
+		nexus_api_payee::Event::SubProcessDone(psp, lookup_id, holder_name, display_name) => {
+			transaction.execute(
+				"UPDATE payee_lookups SET holder_name = ?3, display_name = ?4, confirmed = ?5,
+					updated_at = ?6
+				WHERE psp = ?1 AND lookup_id = ?2",
+				params![
+					psp.to_ss58check(),
+					lookup_id,
+					holder_name,
+					display_name,
+					!holder_name.is_empty(),
+					number
+				],
+			)?;
+		}
+		nexus_api_payee::Event::PayeeDataErased(psp, lookup_id, tombstone) => {
+			transaction.execute(
+				"UPDATE payee_lookups SET destination_country_id = NULL,
+					destination_bank_identifier = NULL, destination_bank_account_number = NULL,
+					holder_name = NULL, display_name = NULL, erased_at = ?3, tombstone = ?4
+				WHERE psp = ?1 AND lookup_id = ?2",
+				params![psp.to_ss58check(), lookup_id, number, tombstone.as_bytes()],
+			)?;
+		}
+		_ => {}
+	}
+
+	Ok(())
+}
+
+fn project_transition(
+	transaction: &Transaction,
+	number: BlockNumber,
+	transition: &PaymentTransition<AccountId, BlockNumber>,
+) -> Result<()> {
+	let source_psp = transition.source_psp.to_ss58check();
+	let dest_psp = transition.dest_psp.as_ref().map(|psp| psp.to_ss58check());
+	let state = format!("{:?}", transition.status.state);
+
+	transaction.execute(
+		"INSERT INTO payments (source_psp, payment_uuid, dest_psp, state, created_at, updated_at)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+		ON CONFLICT (source_psp, payment_uuid) DO UPDATE SET
+			dest_psp = ?3, state = ?4, updated_at = ?6",
+		params![
+			source_psp,
+			transition.payment_uuid,
+			dest_psp,
+			state,
+			transition.status.created_at,
+			transition.status.updated_at
+		],
+	)?;
+	transaction.execute(
+		"INSERT OR REPLACE INTO payment_status_history
+			(source_psp, payment_uuid, block_number, state)
+		VALUES (?1, ?2, ?3, ?4)",
+		params![source_psp, transition.payment_uuid, number, state],
+	)?;
+
+	Ok(())
+}
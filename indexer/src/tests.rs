@@ -0,0 +1,393 @@
+use frame_system::{EventRecord, Phase};
+use nexus_runtime::{
+	nexus_api_final::{self, PaymentState, PaymentStatusInfo, PaymentTransition},
+	nexus_api_payee, nexus_api_quote, nexus_api_sld, AccountId, BlockNumber, Call, Event, Hash,
+};
+use rusqlite::{params, types::Value};
+use sp_core::crypto::Ss58Codec;
+
+use crate::{
+	db::Database,
+	projection::{self, Extrinsic},
+};
+
+const FXP: [u8; 32] = [1; 32];
+const SOURCE_PSP: [u8; 32] = [2; 32];
+const DEST_PSP: [u8; 32] = [3; 32];
+const IPS: [u8; 32] = [4; 32];
+
+fn account(who: [u8; 32]) -> AccountId {
+	AccountId::from(who)
+}
+
+fn address(who: [u8; 32]) -> String {
+	account(who).to_ss58check()
+}
+
+fn record(extrinsic: u32, event: Event) -> EventRecord<Event, Hash> {
+	EventRecord {
+		phase: Phase::ApplyExtrinsic(extrinsic),
+		event,
+		topics: vec![],
+	}
+}
+
+fn index(
+	database: &mut Database,
+	number: BlockNumber,
+	extrinsics: &[Extrinsic],
+	events: &[EventRecord<Event, Hash>],
+	transitions: &[PaymentTransition<AccountId, BlockNumber>],
+) {
+	database
+		.index_block(
+			number,
+			Hash::repeat_byte(number as u8),
+			extrinsics,
+			events,
+			transitions,
+		)
+		.unwrap();
+}
+
+fn query(database: &Database, sql: &str) -> Vec<Vec<Value>> {
+	let mut statement = database.connection().prepare(sql).unwrap();
+	let columns = statement.column_count();
+	statement
+		.query_map(params![], |row| {
+			(0..columns).map(|i| row.get(i)).collect::<Result<_, _>>()
+		})
+		.unwrap()
+		.collect::<Result<_, _>>()
+		.unwrap()
+}
+
+fn text(value: &str) -> Value {
+	Value::Text(value.into())
+}
+
+fn blob(value: &[u8]) -> Value {
+	Value::Blob(value.to_vec())
+}
+
+#[test]
+fn blocks_are_indexed_once_for_one_chain() {
+	let mut database = Database::open_in_memory().unwrap();
+	assert_eq!(database.last_indexed().unwrap(), None);
+
+	database.check_genesis(Hash::repeat_byte(0)).unwrap();
+	database.check_genesis(Hash::repeat_byte(0)).unwrap();
+	assert!(database.check_genesis(Hash::repeat_byte(9)).is_err());
+
+	index(&mut database, 1, &[], &[], &[]);
+	index(&mut database, 2, &[], &[], &[]);
+	assert_eq!(
+		database.last_indexed().unwrap(),
+		Some((2, Hash::repeat_byte(2)))
+	);
+	assert!(database
+		.index_block(2, Hash::repeat_byte(2), &[], &[], &[])
+		.is_err());
+}
+
+#[test]
+fn quotes_are_completed_from_their_call() {
+	let mut database = Database::open_in_memory().unwrap();
+	let provide = Extrinsic {
+		signer: Some(account(FXP)),
+		call: Call::NexusApiQuote(nexus_api_quote::Call::provide_rate(
+			b"SGD".to_vec(),
+			b"INR".to_vec(),
+			b"q-1".to_vec(),
+			b"fxp-1".to_vec(),
+			b"lp-sgd".to_vec(),
+			b"lp-inr".to_vec(),
+			b"61.5".to_vec(),
+			true,
+			b"1614556800".to_vec(),
+			b"NEXSSGSGXXX".to_vec(),
+		)),
+	};
+	let provided = record(
+		0,
+		Event::nexus_api_quote(nexus_api_quote::Event::RatesProvided(
+			b"SGD".to_vec(),
+			b"INR".to_vec(),
+			account(FXP),
+			b"q-1".to_vec(),
+			b"61.5".to_vec(),
+		)),
+	);
+	index(&mut database, 1, &[provide], &[provided], &[]);
+
+	let locked = record(
+		0,
+		Event::nexus_api_quote(nexus_api_quote::Event::QuoteLocked(
+			account(FXP),
+			b"q-1".to_vec(),
+			account(SOURCE_PSP),
+			11,
+		)),
+	);
+	let deleted = record(
+		1,
+		Event::nexus_api_quote(nexus_api_quote::Event::RatesDeleted(
+			b"SGD".to_vec(),
+			b"INR".to_vec(),
+			account(FXP),
+			b"q-1".to_vec(),
+		)),
+	);
+	index(&mut database, 2, &[], &[locked, deleted], &[]);
+
+	assert_eq!(
+		query(
+			&database,
+			"SELECT fxp, rate, public, source_lp, provided_at, locked_by, expires_at, deleted_at
+			FROM quotes"
+		),
+		vec![vec![
+			text(&address(FXP)),
+			blob(b"61.5"),
+			Value::Integer(1),
+			blob(b"lp-sgd"),
+			Value::Integer(1),
+			text(&address(SOURCE_PSP)),
+			Value::Integer(11),
+			Value::Integer(2),
+		]]
+	);
+}
+
+#[test]
+fn every_sld_version_is_kept() {
+	let mut database = Database::open_in_memory().unwrap();
+	let input = |timeout: &[u8]| {
+		record(
+			0,
+			Event::nexus_api_sld(nexus_api_sld::Event::InputSet(
+				account(IPS),
+				true,
+				b"SG".to_vec(),
+				b"12".to_vec(),
+				b"NEXSSGSG".to_vec(),
+				false,
+				b"".to_vec(),
+				b"".to_vec(),
+				b"".to_vec(),
+				b"200000".to_vec(),
+				true,
+				false,
+				timeout.to_vec(),
+			)),
+		)
+	};
+	index(&mut database, 1, &[], &[input(b"30")], &[]);
+	index(&mut database, 2, &[], &[input(b"60")], &[]);
+
+	assert_eq!(
+		query(
+			&database,
+			"SELECT ips, country_id, block_number, ips_timeout FROM sld_versions
+			ORDER BY block_number"
+		),
+		vec![
+			vec![
+				text(&address(IPS)),
+				blob(b"SG"),
+				Value::Integer(1),
+				blob(b"30")
+			],
+			vec![
+				text(&address(IPS)),
+				blob(b"SG"),
+				Value::Integer(2),
+				blob(b"60")
+			],
+		]
+	);
+}
+
+#[test]
+fn erased_payee_lookups_keep_no_personal_data() {
+	let mut database = Database::open_in_memory().unwrap();
+	let look_up = Extrinsic {
+		signer: Some(account(SOURCE_PSP)),
+		call: Call::NexusApiPayee(nexus_api_payee::Call::confirmation_of_payee(
+			b"p-1".to_vec(),
+			b"IN".to_vec(),
+			b"NEXSINBB".to_vec(),
+			b"0123456789".to_vec(),
+		)),
+	};
+	let looked_up = record(
+		0,
+		Event::nexus_api_payee(nexus_api_payee::Event::PaymentConfirm(
+			account(SOURCE_PSP),
+			b"p-1".to_vec(),
+			b"NEXSINBB".to_vec(),
+		)),
+	);
+	index(&mut database, 1, &[look_up], &[looked_up], &[]);
+
+	let confirmed = record(
+		0,
+		Event::nexus_api_payee(nexus_api_payee::Event::SubProcessDone(
+			account(SOURCE_PSP),
+			b"p-1".to_vec(),
+			b"Jane Doe".to_vec(),
+			b"J. Doe".to_vec(),
+		)),
+	);
+	index(&mut database, 2, &[], &[confirmed], &[]);
+
+	assert_eq!(
+		query(
+			&database,
+			"SELECT lookup_id, destination_bank_account_number, holder_name, confirmed
+			FROM payee_lookups"
+		),
+		vec![vec![
+			blob(b"p-1"),
+			blob(b"0123456789"),
+			blob(b"Jane Doe"),
+			Value::Integer(1)
+		]]
+	);
+
+	let erased = record(
+		0,
+		Event::nexus_api_payee(nexus_api_payee::Event::PayeeDataErased(
+			account(SOURCE_PSP),
+			b"p-1".to_vec(),
+			Hash::repeat_byte(7),
+		)),
+	);
+	index(&mut database, 3, &[], &[erased], &[]);
+
+	assert_eq!(
+		query(
+			&database,
+			"SELECT destination_country_id, destination_bank_account_number, holder_name,
+				display_name, erased_at, tombstone
+			FROM payee_lookups"
+		),
+		vec![vec![
+			Value::Null,
+			Value::Null,
+			Value::Null,
+			Value::Null,
+			Value::Integer(3),
+			blob(Hash::repeat_byte(7).as_bytes()),
+		]]
+	);
+}
+
+#[test]
+fn payments_keep_their_status_history() {
+	let mut database = Database::open_in_memory().unwrap();
+	let transition = |state, updated_at| PaymentTransition {
+		source_psp: account(SOURCE_PSP),
+		dest_psp: Some(account(DEST_PSP)),
+		payment_uuid: b"p-1".to_vec(),
+		status: PaymentStatusInfo {
+			state,
+			created_at: 1,
+			updated_at,
+		},
+	};
+	index(
+		&mut database,
+		1,
+		&[],
+		&[],
+		&[transition(PaymentState::Pending, 1)],
+	);
+
+	let settled = record(
+		0,
+		Event::nexus_api_final(nexus_api_final::Event::PaymentSettled(
+			account(SOURCE_PSP),
+			b"p-1".to_vec(),
+			account(DEST_PSP),
+			6_150,
+		)),
+	);
+	index(
+		&mut database,
+		2,
+		&[],
+		&[settled],
+		&[transition(PaymentState::Accepted, 2)],
+	);
+
+	assert_eq!(
+		query(
+			&database,
+			"SELECT dest_psp, state, destination_amount, created_at, updated_at FROM payments"
+		),
+		vec![vec![
+			text(&address(DEST_PSP)),
+			text("Accepted"),
+			text("6150"),
+			Value::Integer(1),
+			Value::Integer(2),
+		]]
+	);
+	assert_eq!(
+		query(
+			&database,
+			"SELECT block_number, state FROM payment_status_history ORDER BY block_number"
+		),
+		vec![
+			vec![Value::Integer(1), text("Pending")],
+			vec![Value::Integer(2), text("Accepted")],
+		]
+	);
+}
+
+#[test]
+fn limit_breaches_are_kept_without_a_destination_psp() {
+	let mut database = Database::open_in_memory().unwrap();
+	let breached = record(
+		0,
+		Event::nexus_api_final(nexus_api_final::Event::DestinationValueExceeded(
+			account(SOURCE_PSP),
+			b"p-1".to_vec(),
+			121_000,
+		)),
+	);
+	let refused = PaymentTransition {
+		source_psp: account(SOURCE_PSP),
+		dest_psp: None,
+		payment_uuid: b"p-1".to_vec(),
+		status: PaymentStatusInfo {
+			state: PaymentState::LimitExceeded,
+			created_at: 1,
+			updated_at: 1,
+		},
+	};
+	assert!(projection::touches_payments(&[breached.clone()]));
+	index(&mut database, 1, &[], &[breached], &[refused]);
+
+	assert_eq!(
+		query(
+			&database,
+			"SELECT dest_psp, state, destination_amount, created_at, updated_at FROM payments"
+		),
+		vec![vec![
+			Value::Null,
+			text("LimitExceeded"),
+			Value::Null,
+			Value::Integer(1),
+			Value::Integer(1),
+		]]
+	);
+	assert_eq!(
+		query(
+			&database,
+			"SELECT block_number, state FROM payment_status_history"
+		),
+		vec![vec![Value::Integer(1), text("LimitExceeded")]]
+	);
+}
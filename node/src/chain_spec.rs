@@ -1,6 +1,9 @@
 use nexus_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	nexus_directory::{Participant, ParticipantStatus},
+	nexus_roles::Role,
+	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, NexusApiFinalConfig,
+	NexusApiPayeeConfig, NexusApiQuoteConfig, NexusApiSLDConfig, NexusDirectoryConfig,
+	NexusRolesConfig, NexusTokensConfig, Signature, SudoConfig, SystemConfig, WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -127,6 +130,14 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+/// The BIC, LEI and country of the PSPs of the Nexus pilot.
+const PILOT_PSPS: [(&[u8], &[u8], &[u8]); 4] = [
+	(b"NEXSSGSG", b"NEXUSSGPSP0000000148", b"SG"),
+	(b"NEXSINBB", b"NEXUSINPSP0000000216", b"IN"),
+	(b"NEXSMYKL", b"NEXUSMYPSP0000000342", b"MY"),
+	(b"NEXSPHMM", b"NEXUSPHPSP0000000450", b"PH"),
+];
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
@@ -160,7 +171,56 @@ fn testnet_genesis(
 		}),
 		pallet_sudo: Some(SudoConfig {
 			// Assign network admin rights.
-			key: root_key,
+			key: root_key.clone(),
+		}),
+		nexus_roles: Some(NexusRolesConfig {
+			// The network admin runs the IPS and oversees it; every endowed account may act as a
+			// PSP and an FXP.
+			roles: vec![
+				(root_key.clone(), Role::IpsOperator),
+				(root_key.clone(), Role::Regulator),
+				(root_key.clone(), Role::NexusGateway),
+			]
+			.into_iter()
+			.chain(endowed_accounts.iter().flat_map(|k| {
+				vec![
+					(k.clone(), Role::SourcePsp),
+					(k.clone(), Role::DestinationPsp),
+					(k.clone(), Role::Fxp),
+				]
+			}))
+			.collect(),
+		}),
+		nexus_directory: Some(NexusDirectoryConfig {
+			// Give the first endowed accounts the identities of the pilot PSPs, all settling in
+			// the IPS of the network admin.
+			participants: endowed_accounts
+				.iter()
+				.zip(PILOT_PSPS.iter())
+				.map(|(k, (bic, lei, country))| {
+					let participant = Participant {
+						bic: bic.to_vec(),
+						lei: lei.to_vec(),
+						country: country.to_vec(),
+						home_ips: root_key.clone(),
+						status: ParticipantStatus::Active,
+					};
+					(k.clone(), participant)
+				})
+				.collect(),
+		}),
+		nexus_tokens: Some(NexusTokensConfig {
+			// Register the CBDCs of the Nexus pilot, all issued by the network admin.
+			assets: vec![
+				(*b"EUR", root_key.clone(), 2),
+				(*b"INR", root_key.clone(), 2),
+				(*b"MYR", root_key.clone(), 2),
+				(*b"SGD", root_key, 2),
+			],
 		}),
+		nexus_api_sld: Some(NexusApiSLDConfig {}),
+		nexus_api_payee: Some(NexusApiPayeeConfig {}),
+		nexus_api_quote: Some(NexusApiQuoteConfig {}),
+		nexus_api_final: Some(NexusApiFinalConfig {}),
 	}
 }
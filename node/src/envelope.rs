@@ -0,0 +1,261 @@
+//! Encryption and decryption of the payment envelopes stored by `nexus-api-final`.
+//!
+//! The payload is prefixed with a random salt and encrypted once with a random content key. The
+//! content key is then wrapped for every recipient with a key derived from an X25519 agreement
+//! between a one-off ephemeral key and the recipient's registered key.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use codec::{Decode, Encode};
+use nexus_runtime::nexus_api_final::{EncryptionKey, Envelope};
+use nexus_runtime::Hash;
+use rand::{rngs::OsRng, RngCore};
+use sp_core::hashing::blake2_256;
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
+use structopt::StructOpt;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// Domain separator for the per-recipient key wrapping key.
+const KEY_WRAP_CONTEXT: &[u8] = b"nexus-envelope-v1";
+
+/// Every wrapping key is used exactly once, so the wrap can use a fixed nonce.
+const KEY_WRAP_NONCE: [u8; 12] = [0; 12];
+
+/// The `envelope` subcommand.
+#[derive(Debug, StructOpt)]
+pub enum EnvelopeCmd {
+	/// Generate an X25519 key pair to register with `set_encryption_key` or `set_regulator_key`.
+	GenerateKey,
+
+	/// Encrypt a payload and print the hex encoded envelope to pass to `set_payment_envelope`.
+	Encrypt {
+		/// File holding the ISO payload.
+		#[structopt(long, parse(from_os_str))]
+		payload: PathBuf,
+
+		/// Hex encoded X25519 public key of a recipient. Repeat for every recipient.
+		#[structopt(long = "recipient", required = true)]
+		recipients: Vec<String>,
+	},
+
+	/// Decrypt an envelope, check it against its on-chain hash commitment and print the payload.
+	Decrypt {
+		/// Hex encoded envelope, as read from `NexusApiFinal::Envelopes`.
+		#[structopt(long)]
+		envelope: String,
+
+		/// Hex encoded payload hash of the `EnvelopeStored` event of the payment.
+		#[structopt(long)]
+		payload_hash: String,
+
+		/// Hex encoded X25519 secret key of one of the recipients.
+		#[structopt(long)]
+		secret: String,
+	},
+}
+
+impl EnvelopeCmd {
+	/// Run the subcommand.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		match self {
+			EnvelopeCmd::GenerateKey => {
+				let secret = StaticSecret::new(OsRng);
+				let public = PublicKey::from(&secret);
+				println!("Secret key: 0x{}", hex::encode(secret.to_bytes()));
+				println!("Public key: 0x{}", hex::encode(public.as_bytes()));
+				Ok(())
+			}
+			EnvelopeCmd::Encrypt {
+				payload,
+				recipients,
+			} => {
+				let payload = std::fs::read(payload)?;
+				let recipients = recipients
+					.iter()
+					.map(|r| decode_key(r))
+					.collect::<Result<Vec<_>, _>>()?;
+				let envelope = seal(&payload, &recipients)?;
+				println!("0x{}", hex::encode(envelope.encode()));
+				Ok(())
+			}
+			EnvelopeCmd::Decrypt {
+				envelope,
+				payload_hash,
+				secret,
+			} => {
+				let envelope = Envelope::<Hash>::decode(&mut &decode_hex(envelope)?[..])
+					.map_err(|e| input_error(format!("Invalid envelope: {}", e)))?;
+				let payload_hash = decode_hash(payload_hash)?;
+				let secret = StaticSecret::from(decode_key(secret)?);
+				let payload = open(&envelope, &payload_hash, &secret)?;
+				std::io::stdout().write_all(&payload)?;
+				Ok(())
+			}
+		}
+	}
+}
+
+/// Encrypt `payload` so that each of `recipients` can read it.
+pub fn seal(payload: &[u8], recipients: &[EncryptionKey]) -> sc_cli::Result<Envelope<Hash>> {
+	let mut salt = [0u8; 16];
+	OsRng.fill_bytes(&mut salt);
+	let plaintext = (salt, payload.to_vec()).encode();
+
+	let mut content_key = [0u8; 32];
+	OsRng.fill_bytes(&mut content_key);
+	let mut nonce = [0u8; 12];
+	OsRng.fill_bytes(&mut nonce);
+
+	let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&content_key))
+		.encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+		.map_err(|_| input_error("Encryption failed"))?;
+
+	let ephemeral = StaticSecret::new(OsRng);
+	let ephemeral_public = *PublicKey::from(&ephemeral).as_bytes();
+
+	let recipients = recipients
+		.iter()
+		.map(|recipient| {
+			let shared = ephemeral.diffie_hellman(&PublicKey::from(*recipient));
+			let wrapping_key = wrapping_key(shared.as_bytes(), &ephemeral_public, recipient);
+			ChaCha20Poly1305::new(Key::from_slice(&wrapping_key))
+				.encrypt(Nonce::from_slice(&KEY_WRAP_NONCE), content_key.as_ref())
+				.map(|wrapped| (*recipient, wrapped))
+				.map_err(|_| input_error("Key wrapping failed"))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(Envelope {
+		ephemeral_public,
+		nonce,
+		ciphertext,
+		recipients,
+		payload_hash: BlakeTwo256::hash(&plaintext),
+	})
+}
+
+/// Decrypt `envelope` with `secret` and check the plaintext against `payload_hash`, the hash
+/// committed to on-chain in `EnvelopeStored`. The `payload_hash` inside the envelope is not
+/// trusted, since whoever handed over the envelope may have changed it along with the payload.
+pub fn open(
+	envelope: &Envelope<Hash>,
+	payload_hash: &Hash,
+	secret: &StaticSecret,
+) -> sc_cli::Result<Vec<u8>> {
+	let public = *PublicKey::from(secret).as_bytes();
+	let (_, wrapped) = envelope
+		.recipients
+		.iter()
+		.find(|(key, _)| *key == public)
+		.ok_or_else(|| input_error("The envelope is not encrypted to this key"))?;
+
+	let shared = secret.diffie_hellman(&PublicKey::from(envelope.ephemeral_public));
+	let wrapping_key = wrapping_key(shared.as_bytes(), &envelope.ephemeral_public, &public);
+	let content_key = ChaCha20Poly1305::new(Key::from_slice(&wrapping_key))
+		.decrypt(Nonce::from_slice(&KEY_WRAP_NONCE), wrapped.as_ref())
+		.map_err(|_| input_error("Could not unwrap the content key"))?;
+
+	let plaintext = ChaCha20Poly1305::new(Key::from_slice(&content_key))
+		.decrypt(Nonce::from_slice(&envelope.nonce), envelope.ciphertext.as_ref())
+		.map_err(|_| input_error("Could not decrypt the payload"))?;
+
+	if BlakeTwo256::hash(&plaintext) != *payload_hash {
+		return Err(input_error("The payload does not match its on-chain commitment"));
+	}
+
+	let (_salt, payload) = <([u8; 16], Vec<u8>)>::decode(&mut &plaintext[..])
+		.map_err(|e| input_error(format!("Invalid payload: {}", e)))?;
+	Ok(payload)
+}
+
+/// The key that wraps the content key for one recipient. The sender agrees `shared` from the
+/// ephemeral secret and the recipient's key, the recipient from its secret and the ephemeral key.
+fn wrapping_key(
+	shared: &[u8; 32],
+	ephemeral_public: &EncryptionKey,
+	recipient: &EncryptionKey,
+) -> [u8; 32] {
+	let parts: [&[u8]; 4] = [KEY_WRAP_CONTEXT, shared, ephemeral_public, recipient];
+	blake2_256(&parts.concat())
+}
+
+fn decode_hex(input: &str) -> sc_cli::Result<Vec<u8>> {
+	hex::decode(input.trim_start_matches("0x"))
+		.map_err(|e| input_error(format!("Invalid hex: {}", e)))
+}
+
+fn decode_hash(input: &str) -> sc_cli::Result<Hash> {
+	let bytes = decode_hex(input)?;
+	if bytes.len() != Hash::len_bytes() {
+		return Err(input_error("Payload hashes must be 32 bytes long"));
+	}
+	Ok(Hash::from_slice(&bytes))
+}
+
+fn decode_key(input: &str) -> sc_cli::Result<EncryptionKey> {
+	let bytes = decode_hex(input)?;
+	let mut key = [0u8; 32];
+	if bytes.len() != key.len() {
+		return Err(input_error("Keys must be 32 bytes long"));
+	}
+	key.copy_from_slice(&bytes);
+	Ok(key)
+}
+
+fn input_error(message: impl ToString) -> sc_cli::Error {
+	sc_cli::Error::Input(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn key_pair() -> (StaticSecret, EncryptionKey) {
+		let secret = StaticSecret::new(OsRng);
+		let public = *PublicKey::from(&secret).as_bytes();
+		(secret, public)
+	}
+
+	#[test]
+	fn every_recipient_can_open_a_sealed_envelope() {
+		let (source, source_public) = key_pair();
+		let (dest, dest_public) = key_pair();
+		let envelope = seal(b"pacs.008", &[source_public, dest_public]).unwrap();
+
+		for secret in [source, dest].iter() {
+			assert_eq!(
+				open(&envelope, &envelope.payload_hash, secret).unwrap(),
+				b"pacs.008".to_vec()
+			);
+		}
+	}
+
+	#[test]
+	fn other_keys_cannot_open_an_envelope() {
+		let (_, recipient) = key_pair();
+		let (outsider, _) = key_pair();
+		let envelope = seal(b"pacs.008", &[recipient]).unwrap();
+
+		assert!(open(&envelope, &envelope.payload_hash, &outsider).is_err());
+	}
+
+	#[test]
+	fn tampered_ciphertexts_are_rejected() {
+		let (secret, public) = key_pair();
+		let mut envelope = seal(b"pacs.008", &[public]).unwrap();
+		envelope.ciphertext[0] ^= 1;
+
+		assert!(open(&envelope, &envelope.payload_hash, &secret).is_err());
+	}
+
+	#[test]
+	fn payloads_must_match_their_on_chain_hash() {
+		let (secret, public) = key_pair();
+		let envelope = seal(b"pacs.008", &[public]).unwrap();
+
+		assert!(open(&envelope, &Hash::repeat_byte(1), &secret).is_err());
+	}
+}
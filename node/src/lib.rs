@@ -1,3 +1,4 @@
 pub mod chain_spec;
+pub mod envelope;
 pub mod service;
 pub mod rpc;
@@ -7,7 +7,9 @@
 
 use std::sync::Arc;
 
-use nexus_runtime::{opaque::Block, AccountId, Balance, Index};
+use nexus_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Index};
+use sc_client_api::BlockchainEvents;
+use sc_rpc::SubscriptionTaskExecutor;
 pub use sc_rpc_api::DenyUnsafe;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
@@ -22,6 +24,8 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor the subscriptions run on.
+	pub subscription_executor: SubscriptionTaskExecutor,
 }
 
 /// Instantiate all full RPC extensions.
@@ -29,12 +33,19 @@ pub fn create_full<C, P>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc
 where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: nexus_api_final_rpc::NexusSldRuntimeApi<Block, AccountId>,
+	C::Api: nexus_api_final_rpc::NexusQuoteRuntimeApi<Block, AccountId, BlockNumber>,
+	C::Api: nexus_api_final_rpc::NexusPayeeRuntimeApi<Block, AccountId>,
+	C::Api: nexus_api_final_rpc::NexusPaymentRuntimeApi<Block, AccountId, Balance, Hash, BlockNumber>,
+	C::Api: nexus_api_final_rpc::NexusPaymentTransitionRuntimeApi<Block, AccountId, BlockNumber>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
 {
+	use nexus_api_final_rpc::{Nexus, NexusApi, NexusPubSub, NexusPubSubApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 
@@ -43,6 +54,7 @@ where
 		client,
 		pool,
 		deny_unsafe,
+		subscription_executor,
 	} = deps;
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(
@@ -55,10 +67,12 @@ where
 		client.clone(),
 	)));
 
-	// Extend this RPC with a custom API by using the following syntax.
-	// `YourRpcStruct` should have a reference to a client, which is needed
-	// to call into the runtime.
-	// `io.extend_with(YourRpcTrait::to_delegate(YourRpcStruct::new(ReferenceToClient, ...)));`
+	io.extend_with(NexusApi::to_delegate(Nexus::new(client.clone())));
+
+	io.extend_with(NexusPubSubApi::to_delegate(NexusPubSub::new(
+		client,
+		subscription_executor,
+	)));
 
 	io
 }
@@ -0,0 +1,221 @@
+//! The `nexus_*` RPC methods, which read the SLDs, quotes, payee lookups and payments of the
+//! Nexus pallets without submitting a transaction or decoding storage.
+
+use std::{convert::TryInto, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use nexus_runtime_api::{
+	ChargeBearer, CostDisclosure, NexusPayeeApi as NexusPayeeRuntimeApi,
+	NexusPaymentApi as NexusPaymentRuntimeApi,
+	NexusPaymentTransitionApi as NexusPaymentTransitionRuntimeApi,
+	NexusQuoteApi as NexusQuoteRuntimeApi, NexusSldApi as NexusSldRuntimeApi, PayeeLookup,
+	PaymentInfo, PaymentTransition, QuoteInfo, SettlementStruct, Sld,
+};
+
+mod pubsub;
+
+pub use pubsub::{BlockStatus, NexusPubSub, NexusPubSubApi, PaymentNotification};
+
+#[rpc]
+pub trait NexusApi<BlockHash, AccountId, Balance, Hash, BlockNumber> {
+	/// Every SLD published for a country, with the IPS that published it.
+	#[rpc(name = "nexus_sld")]
+	fn sld(&self, country_id: Bytes, at: Option<BlockHash>) -> Result<Vec<(AccountId, Sld)>>;
+
+	/// The public quotes between two currencies that may still be locked, with `amount`, in the
+	/// smallest unit of the source currency, converted at each rate.
+	#[rpc(name = "nexus_quotes")]
+	fn quotes(
+		&self,
+		source_currency: Bytes,
+		destination_currency: Bytes,
+		amount: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> Result<Vec<QuoteInfo<AccountId, BlockNumber>>>;
+
+	/// The payee a source PSP looked up under `lookup_id`, and whether it has been confirmed.
+	#[rpc(name = "nexus_payeeLookup")]
+	fn payee_lookup(
+		&self,
+		source_psp: AccountId,
+		lookup_id: Bytes,
+		at: Option<BlockHash>,
+	) -> Result<Option<PayeeLookup>>;
+
+	/// The payment with the given id, its bank commitments, settlement and status.
+	#[rpc(name = "nexus_payment")]
+	fn payment(
+		&self,
+		payment_uuid: Bytes,
+		at: Option<BlockHash>,
+	) -> Result<Option<PaymentInfo<AccountId, Balance, Hash, BlockNumber>>>;
+
+	/// The ids of the payments a PSP has sent or is the destination PSP of.
+	#[rpc(name = "nexus_paymentsByPsp")]
+	fn payments_by_psp(&self, psp: AccountId, at: Option<BlockHash>) -> Result<Vec<Bytes>>;
+
+	/// What a payment would cost, and what its creditor would receive, before it is sent.
+	#[rpc(name = "nexus_costDisclosure")]
+	fn cost_disclosure(
+		&self,
+		source_psp: AccountId,
+		settlement: SettlementStruct<AccountId, Balance>,
+		charge_bearer: ChargeBearer,
+		at: Option<BlockHash>,
+	) -> Result<CostDisclosure<Balance>>;
+}
+
+/// Serves `NexusApi` from the runtime of a client.
+pub struct Nexus<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Nexus<C, B> {
+	/// Create a new `Nexus` reading from `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Nexus {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, B> Nexus<C, B>
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+{
+	/// The block to read from: `at`, or else the best block.
+	fn block_id(&self, at: Option<<B as BlockT>::Hash>) -> BlockId<B> {
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+/// Error codes returned by the Nexus RPC methods.
+pub enum Error {
+	/// The call into the runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, AccountId, Balance, Hash, BlockNumber>
+	NexusApi<<Block as BlockT>::Hash, AccountId, Balance, Hash, BlockNumber> for Nexus<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: NexusSldRuntimeApi<Block, AccountId>,
+	C::Api: NexusQuoteRuntimeApi<Block, AccountId, BlockNumber>,
+	C::Api: NexusPayeeRuntimeApi<Block, AccountId>,
+	C::Api: NexusPaymentRuntimeApi<Block, AccountId, Balance, Hash, BlockNumber>,
+	AccountId: Codec,
+	Balance: Codec,
+	Hash: Codec,
+	BlockNumber: Codec,
+{
+	fn sld(
+		&self,
+		country_id: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(AccountId, Sld)>> {
+		self.client
+			.runtime_api()
+			.slds(&self.block_id(at), country_id.to_vec())
+			.map_err(|e| runtime_error("Unable to query the SLDs.", e))
+	}
+
+	fn quotes(
+		&self,
+		source_currency: Bytes,
+		destination_currency: Bytes,
+		amount: NumberOrHex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<QuoteInfo<AccountId, BlockNumber>>> {
+		let amount: u128 = amount.try_into().map_err(|_| RpcError {
+			code: ErrorCode::InvalidParams,
+			message: "The amount does not fit into 128 bits.".into(),
+			data: None,
+		})?;
+
+		self.client
+			.runtime_api()
+			.quotes(
+				&self.block_id(at),
+				source_currency.to_vec(),
+				destination_currency.to_vec(),
+				amount,
+			)
+			.map_err(|e| runtime_error("Unable to query the quotes.", e))
+	}
+
+	fn payee_lookup(
+		&self,
+		source_psp: AccountId,
+		lookup_id: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<PayeeLookup>> {
+		self.client
+			.runtime_api()
+			.payee_lookup(&self.block_id(at), source_psp, lookup_id.to_vec())
+			.map_err(|e| runtime_error("Unable to query the payee lookup.", e))
+	}
+
+	fn payment(
+		&self,
+		payment_uuid: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<PaymentInfo<AccountId, Balance, Hash, BlockNumber>>> {
+		self.client
+			.runtime_api()
+			.payment(&self.block_id(at), payment_uuid.to_vec())
+			.map_err(|e| runtime_error("Unable to query the payment.", e))
+	}
+
+	fn payments_by_psp(
+		&self,
+		psp: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<Bytes>> {
+		self.client
+			.runtime_api()
+			.payments_by_psp(&self.block_id(at), psp)
+			.map(|ids| ids.into_iter().map(Bytes).collect())
+			.map_err(|e| runtime_error("Unable to query the payments.", e))
+	}
+
+	fn cost_disclosure(
+		&self,
+		source_psp: AccountId,
+		settlement: SettlementStruct<AccountId, Balance>,
+		charge_bearer: ChargeBearer,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<CostDisclosure<Balance>> {
+		self.client
+			.runtime_api()
+			.cost_disclosure(&self.block_id(at), source_psp, settlement, charge_bearer)
+			.map_err(|e| runtime_error("Unable to compute the costs.", e))
+	}
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
@@ -0,0 +1,407 @@
+//! The `nexus_subscribe*` RPC methods, which push the status transitions of payments as the
+//! blocks making them join the best chain, leave it in a reorg and are finalized.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use futures::{future, stream, FutureExt, SinkExt, Stream, StreamExt};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use log::warn;
+use sc_client_api::BlockchainEvents;
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{AtLeast32BitUnsigned, Block as BlockT, Header as HeaderT, One},
+};
+
+use crate::{NexusPaymentTransitionRuntimeApi, PaymentTransition};
+
+/// What has become of the block a notification is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlockStatus {
+	/// The block has joined the best chain.
+	Best,
+	/// The block has left the best chain in a reorg, which withdraws its transitions.
+	Retracted,
+	/// The block is finalized.
+	Finalized,
+}
+
+/// A status transition of a payment, pushed to its subscribers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentNotification<BlockHash, AccountId, BlockNumber> {
+	/// The block that made the transition.
+	pub block: BlockHash,
+	/// What has become of the block.
+	pub status: BlockStatus,
+	/// The payment and the status it ended the block in.
+	pub transition: PaymentTransition<AccountId, BlockNumber>,
+}
+
+#[rpc]
+pub trait NexusPubSubApi<BlockHash, AccountId, BlockNumber> {
+	/// RPC metadata.
+	type Metadata;
+
+	/// Follow the status of the payment with the given id.
+	#[pubsub(
+		subscription = "nexus_payment",
+		subscribe,
+		name = "nexus_subscribePayment"
+	)]
+	fn subscribe_payment(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<PaymentNotification<BlockHash, AccountId, BlockNumber>>,
+		payment_uuid: Bytes,
+	);
+
+	/// Stop following a payment.
+	#[pubsub(
+		subscription = "nexus_payment",
+		unsubscribe,
+		name = "nexus_unsubscribePayment"
+	)]
+	fn unsubscribe_payment(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool>;
+
+	/// Follow the status of every payment a PSP has sent or is the destination PSP of.
+	#[pubsub(
+		subscription = "nexus_pspPayments",
+		subscribe,
+		name = "nexus_subscribePspPayments"
+	)]
+	fn subscribe_psp_payments(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<PaymentNotification<BlockHash, AccountId, BlockNumber>>,
+		psp: AccountId,
+	);
+
+	/// Stop following the payments of a PSP.
+	#[pubsub(
+		subscription = "nexus_pspPayments",
+		unsubscribe,
+		name = "nexus_unsubscribePspPayments"
+	)]
+	fn unsubscribe_psp_payments(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool>;
+}
+
+/// Serves `NexusPubSubApi` from the block import and finality notifications of a client.
+pub struct NexusPubSub<C, B> {
+	client: Arc<C>,
+	manager: SubscriptionManager,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> NexusPubSub<C, B> {
+	/// Create a new `NexusPubSub` following `client` and running its subscriptions on
+	/// `executor`.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		NexusPubSub {
+			client,
+			manager: SubscriptionManager::new(Arc::new(executor)),
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber> NexusPubSub<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C: BlockchainEvents<Block>,
+	C::Api: NexusPaymentTransitionRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec + Serialize + Send + 'static,
+	BlockNumber: Codec + Serialize + Send + 'static,
+{
+	/// Push to `subscriber` the transitions `filter` accepts when their block joins the best
+	/// chain, again if a reorg retracts it, and once more when it is finalized.
+	fn subscribe(
+		&self,
+		subscriber: Subscriber<PaymentNotification<Block::Hash, AccountId, BlockNumber>>,
+		filter: impl Fn(&PaymentTransition<AccountId, BlockNumber>) -> bool + Send + 'static,
+	) {
+		let imported = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.flat_map(|notification| {
+				let (retracted, enacted) = match &notification.tree_route {
+					Some(route) => (
+						route.retracted().iter().map(|block| block.hash).collect(),
+						route.enacted().iter().map(|block| block.hash).collect(),
+					),
+					None => (Vec::new(), Vec::new()),
+				};
+				stream::iter(best_chain_changes(retracted, enacted, notification.hash))
+			});
+		let finalized =
+			finalized_blocks(self.client.clone()).map(|hash| (hash, BlockStatus::Finalized));
+
+		let client = self.client.clone();
+		let notifications = stream::select(imported, finalized).flat_map(move |(block, status)| {
+			let notifications: Vec<_> = transitions(&*client, block)
+				.into_iter()
+				.filter(|transition| filter(transition))
+				.map(|transition| PaymentNotification {
+					block,
+					status,
+					transition,
+				})
+				.collect();
+			stream::iter(notifications)
+		});
+
+		self.manager.add(subscriber, |sink| {
+			notifications
+				.map(|notification| Ok::<_, ()>(Ok(notification)))
+				.forward(
+					sink.sink_map_err(|e| warn!("Error sending a payment notification: {:?}", e)),
+				)
+				.map(|_| ())
+		});
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber> NexusPubSubApi<Block::Hash, AccountId, BlockNumber>
+	for NexusPubSub<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C: BlockchainEvents<Block>,
+	C::Api: NexusPaymentTransitionRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec + Clone + PartialEq + Serialize + Send + Sync + 'static,
+	BlockNumber: Codec + Serialize + Send + 'static,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe_payment(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<PaymentNotification<Block::Hash, AccountId, BlockNumber>>,
+		payment_uuid: Bytes,
+	) {
+		self.subscribe(subscriber, payment_filter(payment_uuid.0));
+	}
+
+	fn unsubscribe_payment(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+
+	fn subscribe_psp_payments(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<PaymentNotification<Block::Hash, AccountId, BlockNumber>>,
+		psp: AccountId,
+	) {
+		self.subscribe(subscriber, psp_filter(psp));
+	}
+
+	fn unsubscribe_psp_payments(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+}
+
+/// Accept the transitions of the payment with the given id.
+fn payment_filter<AccountId, BlockNumber>(
+	payment_uuid: Vec<u8>,
+) -> impl Fn(&PaymentTransition<AccountId, BlockNumber>) -> bool {
+	move |transition| transition.payment_uuid == payment_uuid
+}
+
+/// Accept the transitions of every payment `psp` has sent or is the destination PSP of.
+fn psp_filter<AccountId: PartialEq, BlockNumber>(
+	psp: AccountId,
+) -> impl Fn(&PaymentTransition<AccountId, BlockNumber>) -> bool {
+	move |transition| transition.source_psp == psp || transition.dest_psp.as_ref() == Some(&psp)
+}
+
+/// The blocks a new best block moves in and out of the best chain. `retracted` are the blocks
+/// of the old best chain a reorg leaves, newest first, and `enacted` those of the new one up
+/// to the parent of `best`, oldest first.
+fn best_chain_changes<Hash>(
+	retracted: Vec<Hash>,
+	enacted: Vec<Hash>,
+	best: Hash,
+) -> Vec<(Hash, BlockStatus)> {
+	retracted
+		.into_iter()
+		.map(|block| (block, BlockStatus::Retracted))
+		.chain(enacted.into_iter().map(|block| (block, BlockStatus::Best)))
+		.chain(std::iter::once((best, BlockStatus::Best)))
+		.collect()
+}
+
+/// The payment transitions of `block`, or none if they cannot be read.
+fn transitions<C, Block, AccountId, BlockNumber>(
+	client: &C,
+	block: Block::Hash,
+) -> Vec<PaymentTransition<AccountId, BlockNumber>>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: NexusPaymentTransitionRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec,
+	BlockNumber: Codec,
+{
+	client
+		.runtime_api()
+		.payment_transitions(&BlockId::hash(block))
+		.unwrap_or_else(|e| {
+			warn!(
+				"Unable to read the payment transitions of {:?}: {:?}",
+				block, e
+			);
+			Vec::new()
+		})
+}
+
+/// Every block finalized from now on, in order.
+fn finalized_blocks<C, Block>(client: Arc<C>) -> impl Stream<Item = Block::Hash>
+where
+	Block: BlockT,
+	C: HeaderBackend<Block> + BlockchainEvents<Block>,
+{
+	let last_finalized = client.info().finalized_number;
+	let notifications = client
+		.finality_notification_stream()
+		.map(|notification| (*notification.header.number(), notification.hash));
+
+	fill_finalized(notifications, last_finalized, move |number| {
+		client.hash(number).ok().flatten()
+	})
+}
+
+/// The blocks `notifications` finalize, given as the number and hash of the last block each
+/// one finalizes. A finality notification is only sent for the last block GRANDPA finalizes
+/// in one go, so the blocks after `last_finalized` finalized with it are filled in by `hash`.
+fn fill_finalized<Number, Hash>(
+	notifications: impl Stream<Item = (Number, Hash)>,
+	last_finalized: Number,
+	hash: impl Fn(Number) -> Option<Hash>,
+) -> impl Stream<Item = Hash>
+where
+	Number: AtLeast32BitUnsigned + Copy,
+{
+	notifications
+		.scan(
+			last_finalized,
+			move |last_finalized, (finalized, finalized_hash)| {
+				let mut blocks = Vec::new();
+				let mut number = *last_finalized + One::one();
+				while number < finalized {
+					blocks.extend(hash(number));
+					number += One::one();
+				}
+				blocks.push(finalized_hash);
+				*last_finalized = finalized;
+
+				future::ready(Some(stream::iter(blocks)))
+			},
+		)
+		.flatten()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+
+	fn transition(
+		source_psp: u64,
+		dest_psp: Option<u64>,
+		uuid: &[u8],
+	) -> PaymentTransition<u64, u64> {
+		PaymentTransition {
+			source_psp,
+			dest_psp,
+			payment_uuid: uuid.to_vec(),
+			status: Default::default(),
+		}
+	}
+
+	#[test]
+	fn the_payment_filter_accepts_only_its_payment() {
+		let filter = payment_filter(b"p-1".to_vec());
+
+		assert!(filter(&transition(1, Some(2), b"p-1")));
+		assert!(!filter(&transition(1, Some(2), b"p-2")));
+	}
+
+	#[test]
+	fn the_psp_filter_accepts_payments_the_psp_sent_or_receives() {
+		let filter = psp_filter(1);
+
+		assert!(filter(&transition(1, Some(2), b"p-1")));
+		assert!(filter(&transition(1, None, b"p-2")));
+		assert!(filter(&transition(2, Some(1), b"p-3")));
+		assert!(!filter(&transition(2, Some(3), b"p-4")));
+		assert!(!filter(&transition(2, None, b"p-5")));
+	}
+
+	#[test]
+	fn a_new_best_block_on_the_best_chain_is_the_only_change() {
+		assert_eq!(
+			best_chain_changes(Vec::new(), Vec::new(), 3),
+			vec![(3, BlockStatus::Best)]
+		);
+	}
+
+	#[test]
+	fn a_reorg_retracts_the_old_branch_before_enacting_the_new_one() {
+		assert_eq!(
+			best_chain_changes(vec![13, 12], vec![22, 23], 24),
+			vec![
+				(13, BlockStatus::Retracted),
+				(12, BlockStatus::Retracted),
+				(22, BlockStatus::Best),
+				(23, BlockStatus::Best),
+				(24, BlockStatus::Best),
+			]
+		);
+	}
+
+	#[test]
+	fn blocks_finalized_together_are_filled_in() {
+		let notifications = stream::iter(vec![(3u64, 30u64), (4, 40), (7, 70)]);
+		let hash = |number: u64| Some(number * 10);
+
+		let finalized: Vec<_> = block_on(fill_finalized(notifications, 1, hash).collect());
+
+		assert_eq!(finalized, vec![20, 30, 40, 50, 60, 70]);
+	}
+
+	#[test]
+	fn finalized_blocks_without_a_hash_are_skipped() {
+		let notifications = stream::iter(vec![(4u64, 40u64)]);
+		let hash = |number: u64| if number == 2 { None } else { Some(number * 10) };
+
+		let finalized: Vec<_> = block_on(fill_finalized(notifications, 0, hash).collect());
+
+		assert_eq!(finalized, vec![10, 30, 40]);
+	}
+}
@@ -0,0 +1,625 @@
+//! Benchmarks for nexus-api-final.
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::traits::EnsureOrigin;
+use frame_system::RawOrigin;
+use sp_std::vec;
+
+const SEED: u32 = 0;
+const SOURCE_CURRENCY: CurrencyCode = *b"SGD";
+const DESTINATION_CURRENCY: CurrencyCode = *b"INR";
+const SOURCE_BANK_ID: &[u8] = b"SRCBANK";
+const DEST_BANK_ID: &[u8] = b"DESTBANK";
+
+fn field<T: Config>() -> Vec<u8> {
+	vec![b'0'; T::MaxFieldLength::get() as usize]
+}
+
+fn uuid(i: u32) -> Vec<u8> {
+	(b"payment", i).encode()
+}
+
+fn quote_uuid(i: u32) -> Vec<u8> {
+	(b"quote", i).encode()
+}
+
+fn debtor_id_hash<T: Config>(i: u32) -> T::Hash {
+	T::Hashing::hash_of(&(b"debtor_id", i))
+}
+
+/// Fund `caller` for `n` payments and the FXP for settling them, lock a quote for each payment
+/// for `caller` and register the banks of `caller` and the destination PSP. Returns the FXP and the destination
+/// PSP.
+fn prepare<T>(caller: &T::AccountId, n: u32) -> Result<(T::AccountId, T::AccountId), &'static str>
+where
+	T: Config + nexus_tokens::Config + nexus_api_quote::Config + nexus_api_payee::Config,
+{
+	let issuer: T::AccountId = account("issuer", 0, SEED);
+	let fxp = <T as nexus_api_quote::Config>::FxpOrigin::ensure_origin(
+		<T as nexus_api_quote::Config>::FxpOrigin::successful_origin(),
+	)?;
+	let dest_psp = <T as Config>::DestinationPspOrigin::ensure_origin(
+		<T as Config>::DestinationPspOrigin::successful_origin(),
+	)?;
+	<T as Config>::Directory::add_bank(caller, SOURCE_BANK_ID);
+	<T as Config>::Directory::add_bank(&dest_psp, DEST_BANK_ID);
+
+	for &(currency, beneficiary, amount) in [
+		(SOURCE_CURRENCY, caller, 1_000u32),
+		(DESTINATION_CURRENCY, &fxp, 60_500u32),
+	]
+	.iter()
+	{
+		nexus_tokens::Pallet::<T>::create_asset(
+			RawOrigin::Root.into(),
+			currency,
+			issuer.clone(),
+			2,
+		)?;
+		nexus_tokens::Pallet::<T>::mint(
+			RawOrigin::Signed(issuer.clone()).into(),
+			currency,
+			beneficiary.clone(),
+			(amount * n).into(),
+		)?;
+	}
+
+	for i in 0..n {
+		nexus_api_quote::Pallet::<T>::provide_rate(
+			RawOrigin::Signed(fxp.clone()).into(),
+			SOURCE_CURRENCY.to_vec(),
+			DESTINATION_CURRENCY.to_vec(),
+			quote_uuid(i),
+			b"fxp".to_vec(),
+			b"source_lp".to_vec(),
+			b"destination_lp".to_vec(),
+			b"60.5".to_vec(),
+			true,
+			b"0".to_vec(),
+			SOURCE_BANK_ID.to_vec(),
+		)?;
+		nexus_api_quote::Pallet::<T>::lock_quote(
+			RawOrigin::Signed(caller.clone()).into(),
+			fxp.clone(),
+			quote_uuid(i),
+		)?;
+	}
+
+	Ok((fxp, dest_psp))
+}
+
+/// Put daily and monthly limits on the debtors of the first `n` payments, on `caller` and on the
+/// corridor, so that every payment has to check and update all of them.
+fn set_limits<T: Config>(caller: &T::AccountId, n: u32) -> Result<(), &'static str> {
+	let limit = TransactionLimit {
+		max_single: Some(u32::max_value().into()),
+		max_daily: Some(u32::max_value().into()),
+		max_monthly: Some(u32::max_value().into()),
+	};
+	let scopes = (0..n)
+		.map(|i| LimitScope::Debtor(debtor_id_hash::<T>(i)))
+		.chain(vec![
+			LimitScope::Psp(caller.clone()),
+			LimitScope::Corridor(SOURCE_CURRENCY, DESTINATION_CURRENCY),
+		]);
+	for scope in scopes {
+		Pallet::<T>::set_limit(
+			T::LimitOrigin::successful_origin(),
+			scope,
+			SOURCE_CURRENCY,
+			Some(limit.clone()),
+		)?;
+	}
+	Ok(())
+}
+
+fn submission<T: Config>(
+	i: u32,
+	fxp: &T::AccountId,
+	dest_psp: &T::AccountId,
+) -> PaymentSubmissionOf<T> {
+	PaymentSubmission {
+		source_bank_id: SOURCE_BANK_ID.to_vec(),
+		source_party_hash: T::Hashing::hash_of(&(b"debtor", i)),
+		dest_bank_id: DEST_BANK_ID.to_vec(),
+		dest_party_hash: T::Hashing::hash_of(&(b"creditor", i)),
+		message_id: (b"message", i).encode(),
+		creation_time: b"2021-01-01T00:00:00".to_vec(),
+		settlement_amount: b"60500".to_vec(),
+		payment_uuid: uuid(i),
+		clearing_system_ref: b"IPS".to_vec(),
+		charge_bearer: ChargeBearer::Shared,
+		quote_uuid: quote_uuid(i),
+		lp_source: b"source_lp".to_vec(),
+		dest_psp: dest_psp.clone(),
+		fxp: fxp.clone(),
+		source_currency: SOURCE_CURRENCY,
+		source_amount: 1_000u32.into(),
+		destination_currency: DESTINATION_CURRENCY,
+		destination_amount: 60_500u32.into(),
+		debtor_id_hash: debtor_id_hash::<T>(i),
+	}
+}
+
+/// Have `caller` look up the payee of payment `i` and `dest_psp`, whose bank it is, confirm it.
+fn confirm_payee<T: Config + nexus_api_payee::Config>(
+	caller: &T::AccountId,
+	i: u32,
+	dest_psp: &T::AccountId,
+) -> Result<(), &'static str> {
+	nexus_api_payee::Pallet::<T>::confirmation_of_payee(
+		RawOrigin::Signed(caller.clone()).into(),
+		uuid(i),
+		b"IN".to_vec(),
+		DEST_BANK_ID.to_vec(),
+		b"0123456789".to_vec(),
+	)?;
+	nexus_api_payee::Pallet::<T>::confirm_subprocess(
+		RawOrigin::Signed(dest_psp.clone()).into(),
+		caller.clone(),
+		uuid(i),
+		b"Jane Doe".to_vec(),
+		b"J. Doe".to_vec(),
+	)?;
+	Ok(())
+}
+
+/// Have `dest_psp` attest the creditor of payment `i` of `caller`.
+fn attest<T: Config>(
+	caller: &T::AccountId,
+	i: u32,
+	dest_psp: &T::AccountId,
+) -> Result<(), &'static str> {
+	Pallet::<T>::attest_creditor(
+		RawOrigin::Signed(dest_psp.clone()).into(),
+		caller.clone(),
+		uuid(i),
+		DEST_BANK_ID.to_vec(),
+		T::Hashing::hash_of(&(b"creditor_id", i)),
+	)?;
+	Ok(())
+}
+
+/// Record payment `i` of `caller` as pending and return its UUID.
+fn record<T: Config + nexus_api_payee::Config>(
+	caller: &T::AccountId,
+	i: u32,
+	fxp: &T::AccountId,
+	dest_psp: &T::AccountId,
+) -> Result<Vec<u8>, &'static str> {
+	confirm_payee::<T>(caller, i, dest_psp)?;
+	attest::<T>(caller, i, dest_psp)?;
+	Pallet::<T>::submit_payments(
+		RawOrigin::Signed(caller.clone()).into(),
+		vec![submission::<T>(i, fxp, dest_psp)],
+		BatchMode::AllOrNothing,
+	)?;
+	Ok(uuid(i))
+}
+
+/// Record payment `i` of `caller` and have the FXP accept its FX leg.
+fn accept_fx_leg<T: Config + nexus_api_payee::Config>(
+	caller: &T::AccountId,
+	i: u32,
+	fxp: &T::AccountId,
+	dest_psp: &T::AccountId,
+) -> Result<Vec<u8>, &'static str> {
+	let payment_uuid = record::<T>(caller, i, fxp, dest_psp)?;
+	Pallet::<T>::accept_fx_leg(
+		RawOrigin::Signed(fxp.clone()).into(),
+		caller.clone(),
+		payment_uuid.clone(),
+	)?;
+	Ok(payment_uuid)
+}
+
+/// Record a payment of `caller` and have the FXP and the destination PSP accept it.
+fn settle<T: Config + nexus_api_payee::Config>(
+	caller: &T::AccountId,
+	fxp: &T::AccountId,
+	dest_psp: &T::AccountId,
+) -> Result<Vec<u8>, &'static str> {
+	let payment_uuid = accept_fx_leg::<T>(caller, 0, fxp, dest_psp)?;
+	Pallet::<T>::accept_payment(
+		RawOrigin::Signed(dest_psp.clone()).into(),
+		caller.clone(),
+		payment_uuid.clone(),
+	)?;
+	Ok(payment_uuid)
+}
+
+/// An envelope of `c` bytes readable by `caller`, `dest_psp` and `g` newly registered
+/// regulators, and encrypted to unregistered keys as well until it has `r` recipients.
+fn envelope<T: Config>(
+	caller: &T::AccountId,
+	dest_psp: &T::AccountId,
+	r: u32,
+	g: u32,
+	c: u32,
+) -> Result<Envelope<T::Hash>, &'static str> {
+	let mut recipients = Vec::new();
+	for (i, who) in vec![caller.clone(), dest_psp.clone()]
+		.into_iter()
+		.enumerate()
+	{
+		let key = [i as u8; 32];
+		Pallet::<T>::set_encryption_key(RawOrigin::Signed(who).into(), key)?;
+		recipients.push((key, field::<T>()));
+	}
+	for i in 0..g {
+		let key = [2 + i as u8; 32];
+		Pallet::<T>::set_regulator_key(
+			RawOrigin::Root.into(),
+			account("regulator", i, SEED),
+			Some(key),
+		)?;
+		recipients.push((key, field::<T>()));
+	}
+	for i in (g + 2)..r {
+		recipients.push(([i as u8; 32], field::<T>()));
+	}
+
+	Ok(Envelope {
+		ephemeral_public: [0; 32],
+		nonce: [0; 12],
+		ciphertext: vec![0; c as usize],
+		recipients,
+		payload_hash: T::Hash::default(),
+	})
+}
+
+benchmarks! {
+	where_clause {
+		where
+			T: Config<
+				Assets = nexus_tokens::Pallet<T>,
+				Htlc = nexus_htlc::Pallet<T>,
+				Quotes = nexus_api_quote::Pallet<T>,
+				Payees = nexus_api_payee::Pallet<T>,
+			>,
+			T: nexus_tokens::Config
+				+ nexus_htlc::Config<Assets = nexus_tokens::Pallet<T>>
+				+ nexus_api_quote::Config
+				+ nexus_api_payee::Config,
+	}
+
+	set_status {
+		let caller = <T as Config>::DestinationPspOrigin::ensure_origin(<T as Config>::DestinationPspOrigin::successful_origin())?;
+	}: _(RawOrigin::Signed(caller.clone()), field::<T>(), field::<T>())
+	verify {
+		assert!(Status::<T>::contains_key(&caller));
+	}
+
+	set_dest_bank_data {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+	}: _(RawOrigin::Signed(caller.clone()), field::<T>(), field::<T>(), T::Hash::default())
+	verify {
+		assert!(DestBankCommitment::<T>::contains_key((caller, field::<T>())));
+	}
+
+	set_source_bank_data {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		<T as Config>::Directory::add_bank(&caller, &field::<T>());
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		field::<T>(),
+		field::<T>(),
+		T::Hash::default(),
+		T::Hash::default()
+	)
+	verify {
+		assert!(SourceBankCommitment::<T>::contains_key((caller, field::<T>())));
+	}
+
+	attest_creditor {
+		let caller = <T as Config>::DestinationPspOrigin::ensure_origin(<T as Config>::DestinationPspOrigin::successful_origin())?;
+		let source_psp = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		<T as Config>::Directory::add_bank(&caller, &field::<T>());
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		source_psp.clone(),
+		field::<T>(),
+		field::<T>(),
+		T::Hash::default()
+	)
+	verify {
+		assert!(CreditorAttestations::<T>::contains_key((source_psp, field::<T>())));
+	}
+
+	// The payment passes all checks against limits in every scope and is recorded.
+	final_payment_func {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		set_limits::<T>(&caller, 1)?;
+		let payment = submission::<T>(0, &fxp, &dest_psp);
+		confirm_payee::<T>(&caller, 0, &dest_psp)?;
+		attest::<T>(&caller, 0, &dest_psp)?;
+		Pallet::<T>::set_source_bank_data(
+			RawOrigin::Signed(caller.clone()).into(),
+			payment.payment_uuid.clone(),
+			payment.source_bank_id.clone(),
+			payment.source_party_hash,
+			payment.debtor_id_hash,
+		)?;
+		Pallet::<T>::set_dest_bank_data(
+			RawOrigin::Signed(caller.clone()).into(),
+			payment.payment_uuid.clone(),
+			payment.dest_bank_id.clone(),
+			payment.dest_party_hash,
+		)?;
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		payment.message_id,
+		payment.creation_time,
+		payment.settlement_amount,
+		payment.payment_uuid,
+		payment.clearing_system_ref,
+		payment.charge_bearer,
+		payment.quote_uuid,
+		payment.lp_source,
+		payment.dest_psp,
+		payment.fxp,
+		payment.source_currency,
+		payment.source_amount,
+		payment.destination_currency,
+		payment.destination_amount
+	)
+	verify {
+		assert!(PaymentOwners::<T>::contains_key(uuid(0)));
+	}
+
+	// Every payment passes all checks against limits in every scope and is recorded.
+	submit_payments {
+		let n in 1 .. T::MaxBatchSize::get();
+
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, n)?;
+		set_limits::<T>(&caller, n)?;
+		for i in 0..n {
+			confirm_payee::<T>(&caller, i, &dest_psp)?;
+			attest::<T>(&caller, i, &dest_psp)?;
+		}
+		let payments = (0..n).map(|i| submission::<T>(i, &fxp, &dest_psp)).collect::<Vec<_>>();
+	}: _(RawOrigin::Signed(caller.clone()), payments, BatchMode::AllOrNothing)
+	verify {
+		assert!(PaymentOwners::<T>::contains_key(uuid(n - 1)));
+	}
+
+	// Removing a limit also clears the running totals of both periods.
+	set_limit {
+		let scope = LimitScope::Corridor(SOURCE_CURRENCY, DESTINATION_CURRENCY);
+		Pallet::<T>::set_limit(
+			T::LimitOrigin::successful_origin(),
+			scope.clone(),
+			SOURCE_CURRENCY,
+			Some(Default::default()),
+		)?;
+		for &period in [LimitPeriod::Daily, LimitPeriod::Monthly].iter() {
+			LimitUsages::<T>::insert((scope.clone(), SOURCE_CURRENCY), period, LimitUsage::default());
+		}
+		let origin = T::LimitOrigin::successful_origin();
+	}: _(origin, scope.clone(), SOURCE_CURRENCY, None)
+	verify {
+		assert!(!Limits::<T>::contains_key(scope, SOURCE_CURRENCY));
+	}
+
+	set_fee_schedule {
+		let caller: T::AccountId = whitelisted_caller();
+		let schedule = FeeSchedule {
+			fixed: 10u32.into(),
+			percentage: Permill::from_percent(1),
+		};
+	}: _(RawOrigin::Signed(caller.clone()), SOURCE_CURRENCY, Some(schedule))
+	verify {
+		assert!(FeeSchedules::<T>::contains_key(caller, SOURCE_CURRENCY));
+	}
+
+	// Both bank records and an envelope of the largest size are hashed into the tombstone.
+	erase_payment_data {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = record::<T>(&caller, 0, &fxp, &dest_psp)?;
+		let envelope = envelope::<T>(
+			&caller,
+			&dest_psp,
+			T::MaxEnvelopeRecipients::get(),
+			T::MaxRegulators::get(),
+			T::MaxEnvelopeLength::get(),
+		)?;
+		Pallet::<T>::set_payment_envelope(
+			RawOrigin::Signed(caller.clone()).into(),
+			payment_uuid.clone(),
+			envelope,
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), payment_uuid.clone())
+	verify {
+		assert!(Tombstones::<T>::contains_key((caller, payment_uuid)));
+	}
+
+	set_retry_mode {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+	}: _(RawOrigin::Signed(caller.clone()), RetryMode::Idempotent)
+	verify {
+		assert_eq!(Pallet::<T>::retry_mode(caller), RetryMode::Idempotent);
+	}
+
+	// Rejecting the payment also gives back the reserved amounts of the sender and the FXP.
+	resolve_screening_hold {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		set_limits::<T>(&caller, 1)?;
+		let payment_uuid = accept_fx_leg::<T>(&caller, 0, &fxp, &dest_psp)?;
+		let keys = (caller.clone(), payment_uuid.clone());
+		PaymentStatus::<T>::mutate(&keys, |status| {
+			if let Some(status) = status {
+				status.state = PaymentState::ScreeningHeld;
+			}
+		});
+		let origin = T::ScreeningOrigin::successful_origin();
+	}: _(origin, caller, payment_uuid, false)
+	verify {
+		assert_eq!(
+			Pallet::<T>::payment_status(&keys).map(|status| status.state),
+			Some(PaymentState::ScreeningRejected)
+		);
+	}
+
+	accept_fx_leg {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = record::<T>(&caller, 0, &fxp, &dest_psp)?;
+	}: _(RawOrigin::Signed(fxp), caller.clone(), payment_uuid.clone())
+	verify {
+		assert!(FxLegAcceptances::<T>::contains_key((caller, payment_uuid)));
+	}
+
+	accept_payment {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = accept_fx_leg::<T>(&caller, 0, &fxp, &dest_psp)?;
+	}: _(RawOrigin::Signed(dest_psp), caller.clone(), payment_uuid.clone())
+	verify {
+		assert!(PvpSettlements::<T>::contains_key((fxp, quote_uuid(0))));
+	}
+
+	// The FXP has accepted the FX leg, so its reserve is given back as well.
+	reject_payment {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		set_limits::<T>(&caller, 1)?;
+		let payment_uuid = accept_fx_leg::<T>(&caller, 0, &fxp, &dest_psp)?;
+		let keys = (caller.clone(), payment_uuid.clone());
+	}: _(RawOrigin::Signed(dest_psp), caller, payment_uuid, field::<T>())
+	verify {
+		assert_eq!(
+			Pallet::<T>::payment_status(&keys).map(|status| status.state),
+			Some(PaymentState::Rejected)
+		);
+	}
+
+	lock_payment_htlc {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = accept_fx_leg::<T>(&caller, 0, &fxp, &dest_psp)?;
+		let hashlock = sp_io::hashing::sha2_256(b"preimage");
+		let expires_at = <frame_system::Module<T>>::block_number()
+			+ <T as nexus_htlc::Config>::MinLockPeriod::get();
+	}: _(RawOrigin::Signed(caller), payment_uuid, hashlock, expires_at)
+	verify {
+		assert!(PaymentHtlcs::<T>::contains_key(hashlock));
+	}
+
+	set_encryption_key {
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller.clone()), [1; 32])
+	verify {
+		assert_eq!(Pallet::<T>::encryption_key(caller), Some([1; 32]));
+	}
+
+	// The regulators already registered are counted before another one is added.
+	set_regulator_key {
+		let g in 0 .. T::MaxRegulators::get() - 1;
+
+		for i in 0..g {
+			Pallet::<T>::set_regulator_key(
+				RawOrigin::Root.into(),
+				account("regulator", i, SEED),
+				Some([i as u8; 32]),
+			)?;
+		}
+		let regulator: T::AccountId = account("regulator", g, SEED);
+	}: _(RawOrigin::Root, regulator.clone(), Some([1; 32]))
+	verify {
+		assert!(RegulatorKeys::<T>::contains_key(regulator));
+	}
+
+	// The envelope is checked against each of the `g` regulators, and has at least the two PSPs
+	// and the regulators among its recipients.
+	set_payment_envelope {
+		let r in 2 .. T::MaxEnvelopeRecipients::get();
+		let c in 0 .. T::MaxEnvelopeLength::get();
+		let g in 0 .. T::MaxRegulators::get();
+
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = record::<T>(&caller, 0, &fxp, &dest_psp)?;
+		let envelope = envelope::<T>(&caller, &dest_psp, r, g, c)?;
+	}: _(RawOrigin::Signed(caller.clone()), payment_uuid.clone(), envelope)
+	verify {
+		assert!(Envelopes::<T>::contains_key((caller, payment_uuid)));
+	}
+
+	request_recall {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = settle::<T>(&caller, &fxp, &dest_psp)?;
+	}: _(RawOrigin::Signed(caller.clone()), payment_uuid.clone(), RecallReason::Fraud)
+	verify {
+		assert!(Recalls::<T>::contains_key((caller, payment_uuid)));
+	}
+
+	accept_recall {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = settle::<T>(&caller, &fxp, &dest_psp)?;
+		Pallet::<T>::request_recall(
+			RawOrigin::Signed(caller.clone()).into(),
+			payment_uuid.clone(),
+			RecallReason::Fraud,
+		)?;
+		let keys = (caller.clone(), payment_uuid.clone());
+	}: _(RawOrigin::Signed(dest_psp), caller, payment_uuid)
+	verify {
+		assert_eq!(
+			Pallet::<T>::payment_status(&keys).map(|status| status.state),
+			Some(PaymentState::RecallAccepted)
+		);
+	}
+
+	decline_recall {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		let payment_uuid = settle::<T>(&caller, &fxp, &dest_psp)?;
+		Pallet::<T>::request_recall(
+			RawOrigin::Signed(caller.clone()).into(),
+			payment_uuid.clone(),
+			RecallReason::Fraud,
+		)?;
+		let keys = (caller.clone(), payment_uuid.clone());
+	}: _(RawOrigin::Signed(dest_psp), caller, payment_uuid, RecallDeclineReason::CustomerDecision)
+	verify {
+		assert_eq!(
+			Pallet::<T>::payment_status(&keys).map(|status| status.state),
+			Some(PaymentState::RecallDeclined)
+		);
+	}
+
+	return_payment {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let (fxp, dest_psp) = prepare::<T>(&caller, 1)?;
+		set_limits::<T>(&caller, 1)?;
+		let payment_uuid = settle::<T>(&caller, &fxp, &dest_psp)?;
+		Pallet::<T>::request_recall(
+			RawOrigin::Signed(caller.clone()).into(),
+			payment_uuid.clone(),
+			RecallReason::Fraud,
+		)?;
+		Pallet::<T>::accept_recall(
+			RawOrigin::Signed(dest_psp.clone()).into(),
+			caller.clone(),
+			payment_uuid.clone(),
+		)?;
+	}: _(
+		RawOrigin::Signed(dest_psp),
+		caller.clone(),
+		payment_uuid.clone(),
+		field::<T>(),
+		field::<T>(),
+		ReturnReason::FollowingCancellationRequest
+	)
+	verify {
+		assert!(Returns::<T>::contains_key((caller, payment_uuid)));
+	}
+}
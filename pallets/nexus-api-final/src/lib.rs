@@ -1,14 +1,65 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult};
-use frame_system::ensure_signed;
+use frame_support::{
+	dispatch::DispatchResult,
+	ensure,
+	storage::{with_transaction, TransactionOutcome},
+	traits::{EnsureOrigin, Get},
+	transactional,
+	weights::Weight,
+};
+use nexus_api_payee::PayeeConfirmation;
+use nexus_api_quote::{QuoteError, QuoteProvider};
+use nexus_api_sld::MaxDestinationValue;
+use nexus_directory::ParticipantDirectory;
+use nexus_htlc::{HashLock, HtlcLocker, OnHtlcResolved};
+use nexus_screening::{ScreeningOutcome, ScreeningProvider};
+use nexus_support::ensure_field_lengths;
+use nexus_tokens::{CurrencyCode, MultiReservableCurrency};
+use sp_runtime::{
+	helpers_128bit::multiply_by_rational,
+	traits::{
+		AtLeast32BitUnsigned, Hash as HashT, One, SaturatedConversion, Saturating,
+		UniqueSaturatedInto, Zero,
+	},
+	DispatchError, Permill,
+};
 
 use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 
 use sp_std::prelude::*;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migrations;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
 pub struct FinalPaymentStruct {
+	message_id: Vec<u8>,
+	creation_time: Vec<u8>,
+	settlement_amount: Vec<u8>,
+	payment_uuid: Vec<u8>,
+	clearing_system_ref: Vec<u8>,
+	charge_bearer: ChargeBearer,
+	quote_uuid: Vec<u8>,
+	lp_source: Vec<u8>,
+}
+
+/// `FinalPaymentStruct` as stored before `ChargeBearer` existed.
+#[derive(Decode)]
+struct OldFinalPaymentStruct {
 	message_id: Vec<u8>,
 	creation_time: Vec<u8>,
 	settlement_amount: Vec<u8>,
@@ -19,26 +70,143 @@ pub struct FinalPaymentStruct {
 	lp_source: Vec<u8>,
 }
 
+/// ISO 20022 charge bearer codes: who pays the fees of a payment.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChargeBearer {
+	/// `DEBT` - the debtor pays every fee and the creditor receives the full amount.
+	Debtor,
+	/// `CRED` - the creditor pays every fee out of the amount it receives.
+	Creditor,
+	/// `SHAR` - the debtor pays the source PSP and FXP fees, the creditor the destination PSP fee.
+	Shared,
+	/// `SLEV` - the fees follow the Nexus service level, which shares them like `SHAR`.
+	FollowingServiceLevel,
+}
+
+impl ChargeBearer {
+	/// The charge bearer with the given ISO code.
+	pub fn from_code(code: &[u8]) -> Option<Self> {
+		match code {
+			b"DEBT" => Some(ChargeBearer::Debtor),
+			b"CRED" => Some(ChargeBearer::Creditor),
+			b"SHAR" => Some(ChargeBearer::Shared),
+			b"SLEV" => Some(ChargeBearer::FollowingServiceLevel),
+			_ => None,
+		}
+	}
+}
+
+impl Default for ChargeBearer {
+	fn default() -> Self {
+		ChargeBearer::Shared
+	}
+}
+
+/// The fees a PSP or FXP charges on the payments it takes part in, in one currency.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct FeeSchedule<Balance> {
+	pub fixed: Balance,
+	pub percentage: Permill,
+}
+
+impl<Balance: AtLeast32BitUnsigned + Copy> FeeSchedule<Balance> {
+	/// The fee on `amount`, rounded up.
+	pub fn fee(&self, amount: Balance) -> Balance {
+		self.fixed.saturating_add(self.percentage.mul_ceil(amount))
+	}
+}
+
+/// What a payment costs and who bears it. The debit and the source PSP and FXP fees are in the
+/// source currency; the destination PSP fee and the credit are in the destination currency.
+///
+/// Fees are settled between the PSPs and their customers off-chain: on-chain the payment still
+/// moves `source_amount` and `destination_amount`.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
-pub struct DestinationBankStruct {
+pub struct CostDisclosure<Balance> {
+	pub source_debit: Balance,
+	pub source_psp_fee: Balance,
+	pub fxp_fee: Balance,
+	pub destination_psp_fee: Balance,
+	pub destination_credit: Balance,
+}
+
+/// Storage layouts of this pallet, for migrations.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+enum Releases {
+	V1,
+	V2ChargeBearer,
+	V3PvpByFxp,
+	V4PaymentOwners,
+	V5ReceivedPayments,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
+}
+
+/// Personal data of a debtor or creditor. This never goes on-chain: the PSPs exchange it
+/// off-chain and only its salted `commitment` is stored.
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct PartyDetails {
+	pub account_number: Vec<u8>,
+	pub account_name: Vec<u8>,
+	pub address: Vec<u8>,
+	pub date_of_birth: Vec<u8>,
+	pub place_of_birth: Vec<u8>,
+	pub national_id: Vec<u8>,
+}
+
+impl PartyDetails {
+	/// The commitment stored on-chain for these details. `salt` must be random, at least 16
+	/// bytes long, and only shared with the counterparties, or the details can be guessed back.
+	pub fn commitment<H: HashT>(&self, salt: &[u8]) -> H::Output {
+		H::hash_of(&(salt, self))
+	}
+}
+
+/// X25519 public key that envelopes are encrypted to.
+pub type EncryptionKey = [u8; 32];
+
+/// The ISO payload of a payment, encrypted to its counterparties and the regulators.
+///
+/// The plaintext is encrypted once with a random content key (ChaCha20-Poly1305 under `nonce`).
+/// The content key is wrapped for each recipient with a key agreed between `ephemeral_public`
+/// and the recipient's `EncryptionKey`. `payload_hash` is the hash of the plaintext, which starts
+/// with a random salt so the hash cannot be guessed back.
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct Envelope<Hash> {
+	pub ephemeral_public: EncryptionKey,
+	pub nonce: [u8; 12],
+	pub ciphertext: Vec<u8>,
+	pub recipients: Vec<(EncryptionKey, Vec<u8>)>,
+	pub payload_hash: Hash,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct DestinationBankStruct<Hash> {
 	dest_bank_id: Vec<u8>,
-	dest_bank_acc_number: Vec<u8>,
-	dest_bank_acc_name: Vec<u8>,
-	dest_bank_acc_add: Vec<u8>,
-	dest_bank_acc_dob: Vec<u8>,
-	dest_bank_acc_dop: Vec<u8>,
-	dest_bank_acc_national_id: Vec<u8>,
+	dest_party_hash: Hash,
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
-pub struct SourceBankStruct {
+pub struct SourceBankStruct<Hash> {
 	source_bank_id: Vec<u8>,
-	source_bank_acc_number: Vec<u8>,
-	source_bank_acc_name: Vec<u8>,
-	source_bank_acc_add: Vec<u8>,
-	source_bank_acc_dob: Vec<u8>,
-	source_bank_acc_dop: Vec<u8>,
-	source_bank_acc_national_id: Vec<u8>,
+	source_party_hash: Hash,
+}
+
+/// The hashed identifier of a creditor, as given by the PSP that holds the creditor's account.
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct CreditorAttestation<AccountId, Hash> {
+	dest_psp: AccountId,
+	dest_bank_id: Vec<u8>,
+	creditor_id_hash: Hash,
 }
 
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
@@ -47,86 +215,940 @@ pub struct DestBankStauts {
 	reason_for_status: Vec<u8>,
 }
 
-pub trait Config: frame_system::Config {
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+/// How `final_payment_func` treats a payment that has been submitted before.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RetryMode {
+	/// Every resubmission fails with `DuplicatePayment`.
+	Strict,
+	/// Resubmitting exactly the same payment succeeds without changing anything and points at
+	/// the existing record. Anything else still fails with `DuplicatePayment`.
+	Idempotent,
+}
+
+impl Default for RetryMode {
+	fn default() -> Self {
+		RetryMode::Strict
+	}
+}
+
+/// What a transaction limit applies to.
+#[derive(Encode, Decode, Clone, Debug, Eq, PartialEq)]
+pub enum LimitScope<AccountId, Hash> {
+	/// Every payment of one debtor, by hashed identifier.
+	Debtor(Hash),
+	/// Every payment sent by one PSP.
+	Psp(AccountId),
+	/// Every payment from the first currency into the second.
+	Corridor(CurrencyCode, CurrencyCode),
+	/// Every payment of the debtors of one PSP that have no `Debtor` limit of their own, so a
+	/// debtor sent under another identifier still counts against a debtor limit.
+	UnlistedDebtors(AccountId),
+}
+
+/// The period a running total is kept over. A window opens with the first payment after the
+/// previous one has closed and lasts `DayLength` or `MonthLength` blocks.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LimitPeriod {
+	Daily,
+	Monthly,
+}
+
+/// Maximum source amounts of the payments within a `LimitScope`. `None` means unlimited.
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct TransactionLimit<Balance> {
+	pub max_single: Option<Balance>,
+	pub max_daily: Option<Balance>,
+	pub max_monthly: Option<Balance>,
+}
+
+/// The total of a limit in its current window.
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct LimitUsage<BlockNumber, Balance> {
+	window_start: BlockNumber,
+	total: Balance,
+}
+
+/// How `submit_payments` treats a batch in which some payments fail.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BatchMode {
+	/// The whole batch fails with the first payment that fails.
+	AllOrNothing,
+	/// Failed payments are reported and left out, the others are recorded.
+	BestEffort,
+}
+
+impl Default for BatchMode {
+	fn default() -> Self {
+		BatchMode::AllOrNothing
+	}
+}
+
+/// One payment of a `submit_payments` batch: everything `set_source_bank_data`,
+/// `set_dest_bank_data` and `final_payment_func` take for it.
+#[derive(Encode, Decode, Clone, Debug, Eq, PartialEq)]
+pub struct PaymentSubmission<AccountId, Balance, Hash> {
+	pub source_bank_id: Vec<u8>,
+	pub source_party_hash: Hash,
+	pub dest_bank_id: Vec<u8>,
+	pub dest_party_hash: Hash,
+	pub message_id: Vec<u8>,
+	pub creation_time: Vec<u8>,
+	pub settlement_amount: Vec<u8>,
+	pub payment_uuid: Vec<u8>,
+	pub clearing_system_ref: Vec<u8>,
+	pub charge_bearer: ChargeBearer,
+	pub quote_uuid: Vec<u8>,
+	pub lp_source: Vec<u8>,
+	pub dest_psp: AccountId,
+	pub fxp: AccountId,
+	pub source_currency: CurrencyCode,
+	pub source_amount: Balance,
+	pub destination_currency: CurrencyCode,
+	pub destination_amount: Balance,
+	pub debtor_id_hash: Hash,
+}
+
+/// Where a final payment is in its lifecycle.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PaymentState {
+	/// The source amount is reserved and the destination PSP has not answered yet.
+	Pending,
+	/// The destination PSP accepted the payment and the funds have been paid out.
+	Accepted,
+	/// The destination PSP rejected the payment and the source amount was unreserved.
+	Rejected,
+	/// The source PSP has asked for the funds back (camt.056).
+	RecallRequested,
+	/// The destination PSP has agreed to send the funds back.
+	RecallAccepted,
+	/// The destination PSP has refused the recall (camt.029).
+	RecallDeclined,
+	/// The funds have been sent back to the source PSP (pacs.004).
+	Returned,
+	/// The source amount is under a hash-time-lock for a destination leg on another ledger.
+	HtlcLocked,
+	/// The FXP claimed the hash-time-locked source amount with the preimage.
+	HtlcClaimed,
+	/// The hash-time-lock expired and the source amount went back to the source PSP.
+	HtlcRefunded,
+	/// Screening found a possible match. The source amount is reserved until compliance
+	/// releases or rejects the payment.
+	ScreeningHeld,
+	/// Screening rejected the payment. Nothing was reserved.
+	ScreeningRejected,
+	/// The payment was refused for being above the destination SLD's `max_destination_value` or
+	/// one of its transaction limits. Only reported in transitions; such payments are not stored.
+	LimitExceeded,
+}
+
+impl Default for PaymentState {
+	fn default() -> Self {
+		PaymentState::Pending
+	}
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct PaymentStatusInfo<BlockNumber> {
+	pub state: PaymentState,
+	pub created_at: BlockNumber,
+	pub updated_at: BlockNumber,
+}
+
+/// The value legs of a final payment.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct SettlementStruct<AccountId, Balance> {
+	dest_psp: AccountId,
+	fxp: AccountId,
+	source_currency: CurrencyCode,
+	source_amount: Balance,
+	destination_currency: CurrencyCode,
+	destination_amount: Balance,
+}
+
+/// Everything recorded about a payment, as returned by the `NexusPaymentApi` runtime API.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Debug, Eq, PartialEq)]
+pub struct PaymentInfo<AccountId, Balance, Hash, BlockNumber> {
+	pub source_psp: AccountId,
+	pub payment: FinalPaymentStruct,
+	pub source_bank: Option<SourceBankStruct<Hash>>,
+	pub dest_bank: Option<DestinationBankStruct<Hash>>,
+	pub settlement: Option<SettlementStruct<AccountId, Balance>>,
+	pub status: Option<PaymentStatusInfo<BlockNumber>>,
+	pub costs: Option<CostDisclosure<Balance>>,
+}
+
+/// The status a payment ended a block in, as pushed to the subscribers of its PSPs.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Debug, Eq, PartialEq)]
+pub struct PaymentTransition<AccountId, BlockNumber> {
+	pub source_psp: AccountId,
+	pub dest_psp: Option<AccountId>,
+	pub payment_uuid: Vec<u8>,
+	pub status: PaymentStatusInfo<BlockNumber>,
+}
+
+/// A payment-versus-payment settlement, kept per FXP and quote so a quote settles at most once.
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct PvpRecord<AccountId, BlockNumber> {
+	source_psp: AccountId,
+	payment_uuid: Vec<u8>,
+	lp_source: Vec<u8>,
+	fxp: AccountId,
+	settled_at: BlockNumber,
+}
+
+/// ISO 20022 reasons a source PSP may give when recalling a payment (camt.056).
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RecallReason {
+	/// `DUPL` - the payment was sent twice.
+	Duplicate,
+	/// `FRAD` - the payment was initiated fraudulently.
+	Fraud,
+	/// `TECH` - the payment was caused by a technical problem.
+	TechnicalProblem,
+	/// `CUST` - the debtor asked for the payment to be cancelled.
+	CustomerRequest,
+	/// `AC03` - the creditor account is wrong.
+	IncorrectCreditorAccount,
+	/// `AM09` - the amount is wrong.
+	WrongAmount,
 }
 
-decl_storage! {
-	trait Store for Module<T: Config> as NexusApiFinal {
-		FinalPayment get(fn final_payment): map hasher(blake2_128_concat) (T::AccountId , Vec<u8>)=> FinalPaymentStruct;
-		DestBank get(fn dest_bank): map hasher(blake2_128_concat) (T::AccountId , Vec<u8>)=> DestinationBankStruct;
-		SourceBank get(fn source_bank): map hasher(blake2_128_concat) (T::AccountId , Vec<u8>)=> SourceBankStruct;
-		Status get(fn status): map hasher(blake2_128_concat) T::AccountId=> DestBankStauts;
+impl Default for RecallReason {
+	fn default() -> Self {
+		RecallReason::CustomerRequest
 	}
 }
 
-decl_event!(
-	pub enum Event<T>
-	where
-		AccountId = <T as frame_system::Config>::AccountId,
-	{
+/// ISO 20022 reasons a destination PSP may give when declining a recall (camt.029).
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RecallDeclineReason {
+	/// `ARDT` - the funds have already been returned.
+	AlreadyReturned,
+	/// `CUST` - the creditor refused to give the funds back.
+	CustomerDecision,
+	/// `LEGL` - returning the funds is not allowed by law.
+	LegalDecision,
+	/// `NOAS` - the creditor did not answer.
+	NoAnswerFromCustomer,
+	/// `AM04` - there are not enough funds left on the creditor account.
+	InsufficientFunds,
+	/// `AC04` - the creditor account has been closed.
+	ClosedAccount,
+}
+
+impl Default for RecallDeclineReason {
+	fn default() -> Self {
+		RecallDeclineReason::CustomerDecision
+	}
+}
+
+/// ISO 20022 reasons carried on a return payment (pacs.004).
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReturnReason {
+	/// `FOCR` - the return follows an accepted recall.
+	FollowingCancellationRequest,
+	/// `AC01` - the account number is wrong.
+	IncorrectAccountNumber,
+	/// `AC04` - the account has been closed.
+	ClosedAccountNumber,
+	/// `AC06` - the account is blocked.
+	BlockedAccount,
+	/// `MD07` - the creditor is deceased.
+	EndCustomerDeceased,
+	/// `AM09` - the amount is wrong.
+	WrongAmount,
+}
+
+impl Default for ReturnReason {
+	fn default() -> Self {
+		ReturnReason::FollowingCancellationRequest
+	}
+}
+
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct RecallStruct<AccountId, BlockNumber> {
+	dest_psp: AccountId,
+	reason: RecallReason,
+	requested_at: BlockNumber,
+	decline_reason: Option<RecallDeclineReason>,
+}
+
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct ReturnStruct<AccountId, BlockNumber> {
+	return_id: Vec<u8>,
+	original_payment_uuid: Vec<u8>,
+	returned_amount: Vec<u8>,
+	reason: ReturnReason,
+	returned_by: AccountId,
+	returned_at: BlockNumber,
+}
+
+type BalanceOf<T> = <<T as Config>::Assets as MultiReservableCurrency<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+type LimitScopeOf<T> =
+	LimitScope<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::Hash>;
+
+type PaymentSubmissionOf<T> = PaymentSubmission<
+	<T as frame_system::Config>::AccountId,
+	BalanceOf<T>,
+	<T as frame_system::Config>::Hash,
+>;
+
+type LimitUsageOf<T> = LimitUsage<<T as frame_system::Config>::BlockNumber, BalanceOf<T>>;
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The CBDC ledger the payments are settled in.
+		type Assets: MultiReservableCurrency<Self::AccountId>;
+
+		/// Hash-time-locks used for payments whose destination leg settles on another ledger.
+		type Htlc: HtlcLocker<Self::AccountId, BalanceOf<Self>, Self::BlockNumber>;
+
+		/// Who may send payments and act on them as their source PSP.
+		type SourcePspOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+		/// Who may answer payments as their destination PSP.
+		type DestinationPspOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+		/// The directory that tells which PSP a bank id belongs to.
+		type Directory: ParticipantDirectory<Self::AccountId>;
+
+		/// Sanctions and AML screening of the debtor and creditor of every new payment.
+		type Screening: ScreeningProvider<Self::AccountId, Self::Hash>;
+
+		/// Who may release or reject a payment held by screening.
+		type ScreeningOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The quotes payments are made with.
+		type Quotes: QuoteProvider<Self::AccountId>;
+
+		/// The Confirmation of Payee every payment needs, looked up under the payment id.
+		type Payees: PayeeConfirmation<Self::AccountId>;
+
+		/// The SLDs whose `max_destination_value` caps every payment.
+		type Sld: MaxDestinationValue<Self::AccountId>;
+
+		/// Who may set transaction limits.
+		type LimitOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Number of blocks a daily limit window lasts.
+		#[pallet::constant]
+		type DayLength: Get<Self::BlockNumber>;
+
+		/// Number of blocks a monthly limit window lasts.
+		#[pallet::constant]
+		type MonthLength: Get<Self::BlockNumber>;
+
+		/// Number of blocks after creation during which the source PSP may recall a payment.
+		#[pallet::constant]
+		type RecallWindow: Get<Self::BlockNumber>;
+
+		/// Number of blocks the destination PSP has to answer a recall.
+		#[pallet::constant]
+		type RecallResponseWindow: Get<Self::BlockNumber>;
+
+		/// Number of blocks the destination PSP has to send the return once it accepted a recall.
+		#[pallet::constant]
+		type ReturnWindow: Get<Self::BlockNumber>;
+
+		/// Number of blocks the bank data and envelope of a payment are kept before they are erased.
+		#[pallet::constant]
+		type RetentionPeriod: Get<Self::BlockNumber>;
+
+		/// The most payments whose personal data is erased in one block. The rest are left for
+		/// the next block.
+		#[pallet::constant]
+		type MaxErasuresPerBlock: Get<u32>;
+
+		/// The most payments `submit_payments` takes at once.
+		#[pallet::constant]
+		type MaxBatchSize: Get<u32>;
+
+		/// The longest any byte field of a payment, its bank data or status may be.
+		#[pallet::constant]
+		type MaxFieldLength: Get<u32>;
+
+		/// The longest the ciphertext of an envelope may be.
+		#[pallet::constant]
+		type MaxEnvelopeLength: Get<u32>;
+
+		/// The most recipients an envelope may be encrypted to.
+		#[pallet::constant]
+		type MaxEnvelopeRecipients: Get<u32>;
+
+		/// The most regulator view keys there may be. Every envelope is also encrypted to both
+		/// PSPs, so this must leave room for two more recipients within `MaxEnvelopeRecipients`.
+		#[pallet::constant]
+		type MaxRegulators: Get<u32>;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	#[pallet::getter(fn final_payment)]
+	pub(super) type FinalPayment<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), FinalPaymentStruct, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn dest_bank)]
+	pub(super) type DestBankCommitment<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		DestinationBankStruct<T::Hash>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn source_bank)]
+	pub(super) type SourceBankCommitment<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		SourceBankStruct<T::Hash>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn debtor_id_hash)]
+	pub(super) type DebtorIdHashes<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), T::Hash>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn creditor_attestation)]
+	pub(super) type CreditorAttestations<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		CreditorAttestation<T::AccountId, T::Hash>,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn status)]
+	pub(super) type Status<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, DestBankStauts, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn payment_owner)]
+	pub(super) type PaymentOwners<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn message_payment)]
+	pub(super) type MessageIds<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, Vec<u8>, Vec<u8>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn retry_mode)]
+	pub(super) type RetryModes<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, RetryMode, ValueQuery>;
+
+	#[pallet::storage]
+	pub(super) type StorageVersion<T> = StorageValue<_, Releases, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn fee_schedule)]
+	pub(super) type FeeSchedules<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		CurrencyCode,
+		FeeSchedule<BalanceOf<T>>,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn payment_costs)]
+	pub(super) type Costs<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), CostDisclosure<BalanceOf<T>>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn settlement)]
+	pub(super) type Settlements<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		SettlementStruct<T::AccountId, BalanceOf<T>>,
+	>;
+
+	/// The payments each destination PSP receives, by source PSP and payment id.
+	#[pallet::storage]
+	pub(super) type ReceivedPayments<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		(),
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn fx_leg_accepted_at)]
+	pub(super) type FxLegAcceptances<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), T::BlockNumber>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn pvp_settlement)]
+	pub(super) type PvpSettlements<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		PvpRecord<T::AccountId, T::BlockNumber>,
+	>;
+
+	/// The payment, by source PSP and uuid, that each quote, by FXP and uuid, backs.
+	#[pallet::storage]
+	#[pallet::getter(fn quote_use)]
+	pub(super) type QuoteUses<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), (T::AccountId, Vec<u8>)>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn payment_htlc)]
+	pub(super) type PaymentHtlcs<T: Config> =
+		StorageMap<_, Blake2_128Concat, HashLock, (T::AccountId, Vec<u8>)>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn payment_status)]
+	pub(super) type PaymentStatus<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), PaymentStatusInfo<T::BlockNumber>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn recall)]
+	pub(super) type Recalls<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		RecallStruct<T::AccountId, T::BlockNumber>,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn encryption_key)]
+	pub(super) type EncryptionKeys<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, EncryptionKey>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn regulator_key)]
+	pub(super) type RegulatorKeys<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, EncryptionKey>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn envelope)]
+	pub(super) type Envelopes<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), Envelope<T::Hash>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn limit)]
+	pub(super) type Limits<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		LimitScopeOf<T>,
+		Blake2_128Concat,
+		CurrencyCode,
+		TransactionLimit<BalanceOf<T>>,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn limit_usage)]
+	pub(super) type LimitUsages<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(LimitScopeOf<T>, CurrencyCode),
+		Twox64Concat,
+		LimitPeriod,
+		LimitUsageOf<T>,
+		ValueQuery,
+	>;
+
+	/// The running totals a payment was added to, with the start of the window it was added in.
+	#[pallet::storage]
+	#[pallet::getter(fn limit_charges)]
+	pub(super) type LimitCharges<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		Vec<((LimitScopeOf<T>, CurrencyCode), LimitPeriod, T::BlockNumber)>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn erasure_queue)]
+	pub(super) type ErasureQueue<T: Config> =
+		StorageMap<_, Twox64Concat, T::BlockNumber, Vec<(T::AccountId, Vec<u8>)>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn erasure_due)]
+	pub(super) type ErasureDue<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), T::BlockNumber>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn tombstone)]
+	pub(super) type Tombstones<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), T::Hash>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn payment_return)]
+	pub(super) type Returns<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		ReturnStruct<T::AccountId, T::BlockNumber>,
+	>;
+
+	#[pallet::genesis_config]
+	#[derive(Default)]
+	pub struct GenesisConfig {}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		/// A new chain starts out with the latest storage layout, so no migration runs on it.
+		fn build(&self) {
+			<StorageVersion<T>>::put(Releases::V5ReceivedPayments);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", BalanceOf<T> = "Balance", T::Hash = "Hash", LimitScopeOf<T> = "Scope")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
 		/// Status has Set
-		StatusSet(AccountId, Vec<u8>, Vec<u8>),
+		StatusSet(T::AccountId, Vec<u8>, Vec<u8>),
 
-		/// Dest Bank has been given
-		DestBankSet(AccountId, Vec<u8>),
+		/// Dest Bank has been given. [user, payment_id, dest_party_hash]
+		DestBankSet(T::AccountId, Vec<u8>, T::Hash),
 
-		/// Source Bank data has been given
-		SourceBankSet(AccountId, Vec<u8>),
+		/// Source Bank data has been given. [user, payment_id, source_party_hash]
+		SourceBankSet(T::AccountId, Vec<u8>, T::Hash),
 
 		/// Final Payment has been given
-		FinalPaymentSet(AccountId, Vec<u8>),
-
-		FinalData(
-			AccountId,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
-			Vec<u8>,
+		FinalPaymentSet(T::AccountId, Vec<u8>),
+
+		/// The destination PSP has given the hashed identifier of a creditor. [source_psp, payment_uuid, dest_psp]
+		CreditorAttested(T::AccountId, Vec<u8>, T::AccountId),
+
+		/// A PSP or FXP has set or removed its fees in a currency. [user, currency, schedule]
+		FeeScheduleSet(
+			T::AccountId,
+			CurrencyCode,
+			Option<FeeSchedule<BalanceOf<T>>>,
 		),
+
+		/// A payment of a best-effort batch failed and was left out. [source_psp, index, payment_uuid, error]
+		BatchItemFailed(T::AccountId, u32, Vec<u8>, DispatchError),
+
+		/// A batch has been processed. [source_psp, recorded, failed]
+		BatchSubmitted(T::AccountId, u32, u32),
+
+		/// A sending PSP has changed how its resubmissions are treated. [user, mode]
+		RetryModeSet(T::AccountId, RetryMode),
+
+		/// An identical resubmission was ignored; the existing record is unchanged. [source_psp, payment_uuid, state]
+		DuplicatePaymentIgnored(T::AccountId, Vec<u8>, PaymentState),
+
+		/// The limits of a scope have been set or removed. [scope, currency, limit]
+		LimitSet(
+			LimitScopeOf<T>,
+			CurrencyCode,
+			Option<TransactionLimit<BalanceOf<T>>>,
+		),
+
+		/// A payment was refused because it breaks a limit; `None` is the single payment maximum. [source_psp, payment_uuid, scope, period]
+		TransactionLimitExceeded(T::AccountId, Vec<u8>, LimitScopeOf<T>, Option<LimitPeriod>),
+
+		/// A payment was refused because it credits more than the destination SLD allows. [source_psp, payment_uuid, max_destination_value]
+		DestinationValueExceeded(T::AccountId, Vec<u8>, u128),
+
+		/// A new payment has been screened. [source_psp, payment_uuid, outcome]
+		PaymentScreened(T::AccountId, Vec<u8>, ScreeningOutcome),
+
+		/// Compliance has released or rejected a held payment. [source_psp, payment_uuid, released]
+		ScreeningHoldResolved(T::AccountId, Vec<u8>, bool),
+
+		/// Destination PSP accepted a payment and it has been paid out. [source_psp, payment_uuid, dest_psp, destination_amount]
+		PaymentSettled(T::AccountId, Vec<u8>, T::AccountId, BalanceOf<T>),
+
+		/// The FXP of a payment has accepted its FX leg and reserved the destination amount. [source_psp, payment_uuid, fxp, destination_amount]
+		FxLegAccepted(T::AccountId, Vec<u8>, T::AccountId, BalanceOf<T>),
+
+		/// Both legs of a quote have settled together. [quote_uuid, lp_source, fxp, source_amount, destination_amount]
+		PvpSettled(Vec<u8>, Vec<u8>, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+
+		/// Destination PSP rejected a payment and the source amount was unreserved. [source_psp, payment_uuid, dest_psp, reason]
+		PaymentRejected(T::AccountId, Vec<u8>, T::AccountId, Vec<u8>),
+
+		/// Source PSP has locked a pending payment under a hash-time-lock. [source_psp, payment_uuid, hashlock]
+		PaymentHtlcLocked(T::AccountId, Vec<u8>, HashLock),
+
+		/// The hash-time-lock of a payment has been claimed or refunded. [source_psp, payment_uuid, state]
+		PaymentHtlcResolved(T::AccountId, Vec<u8>, PaymentState),
+
+		/// Source PSP has asked the destination PSP to give a payment back. [source_psp, payment_uuid, dest_psp, reason]
+		RecallRequested(T::AccountId, Vec<u8>, T::AccountId, RecallReason),
+
+		/// Destination PSP has agreed to give a payment back. [source_psp, payment_uuid, dest_psp]
+		RecallAccepted(T::AccountId, Vec<u8>, T::AccountId),
+
+		/// Destination PSP has refused to give a payment back. [source_psp, payment_uuid, dest_psp, reason]
+		RecallDeclined(T::AccountId, Vec<u8>, T::AccountId, RecallDeclineReason),
+
+		/// Destination PSP has returned a payment. [source_psp, payment_uuid, return_id, reason]
+		PaymentReturned(T::AccountId, Vec<u8>, Vec<u8>, ReturnReason),
+
+		/// An account has registered the key envelopes are encrypted to. [user, key]
+		EncryptionKeySet(T::AccountId, EncryptionKey),
+
+		/// A regulator view key has been added, changed or removed. [regulator, key]
+		RegulatorKeySet(T::AccountId, Option<EncryptionKey>),
+
+		/// The bank data and envelope of a payment have been erased. [source_psp, payment_uuid, tombstone]
+		PersonalDataErased(T::AccountId, Vec<u8>, T::Hash),
+
+		/// The encrypted payload of a payment has been stored. [source_psp, payment_uuid, payload_hash]
+		EnvelopeStored(T::AccountId, Vec<u8>, T::Hash),
 	}
-);
 
-decl_error! {
-	pub enum Error for Module<T: Config> {
+	#[pallet::error]
+	pub enum Error<T> {
 		/// The requested user has not stored a value yet
 		NoValueStored,
 
 		/// The value cannot be incremented further because it has reached the maximum allowed value
 		MaxValueReached,
+
+		/// There is no final payment for the given payment id
+		PaymentNotFound,
+
+		/// There is no personal data stored for the given payment id
+		NothingToErase,
+
+		/// The batch holds more payments than `MaxBatchSize`
+		BatchTooLarge,
+
+		/// The payment id or the sender's message id has already been used
+		DuplicatePayment,
+
+		/// The fees borne by the creditor take up the whole destination amount
+		FeesExceedAmount,
+
+		/// The payment breaks a transaction limit of its debtor, PSP or corridor
+		TransactionLimitExceeded,
+
+		/// The payment credits more than the destination SLD allows
+		DestinationValueExceeded,
+
+		/// There is no quote with the payment's quote id
+		QuoteNotFound,
+
+		/// The payment's quote is no longer valid
+		QuoteExpired,
+
+		/// The payment's quote has not been locked by the sender
+		QuoteNotLocked,
+
+		/// The FXP or currencies of the payment differ from its quote
+		QuoteMismatch,
+
+		/// The settlement or destination amount is not the source amount at the quoted rate
+		SettlementAmountMismatch,
+
+		/// The destination bank has not confirmed the payee
+		PayeeNotConfirmed,
+
+		/// Only the destination PSP of the payment may answer it
+		NotDestinationPsp,
+
+		/// A reserved amount could not be paid out in full
+		InsufficientReservedBalance,
+
+		/// The quote of this payment has already been used by another settlement
+		QuoteAlreadySettled,
+
+		/// The quote already backs another payment
+		QuoteInUse,
+
+		/// Only the FXP of the payment may accept its FX leg
+		NotPaymentFxp,
+
+		/// The FXP of the payment has not accepted its FX leg
+		FxLegNotAccepted,
+
+		/// The FXP of the payment has already accepted its FX leg
+		FxLegAlreadyAccepted,
+
+		/// The payment is not in a state that allows this call
+		InvalidPaymentState,
+
+		/// The payment is too old to be recalled
+		RecallWindowExpired,
+
+		/// There is no recall for the given payment id
+		NoRecall,
+
+		/// Only the destination PSP named in the recall may answer it
+		NotRecallAssignee,
+
+		/// The destination PSP answered the recall too late
+		RecallResponseExpired,
+
+		/// The return was sent too late after the recall was accepted
+		ReturnWindowExpired,
+
+		/// A counterparty of the payment has not registered an encryption key
+		NoEncryptionKey,
+
+		/// The envelope is not readable by every counterparty and regulator
+		MissingEnvelopeRecipient,
+
+		/// A field is longer than `MaxFieldLength`
+		FieldTooLong,
+
+		/// The ciphertext of the envelope is longer than `MaxEnvelopeLength`
+		EnvelopeTooLong,
+
+		/// The envelope has more recipients than `MaxEnvelopeRecipients`
+		TooManyEnvelopeRecipients,
+
+		/// There are already `MaxRegulators` regulator view keys
+		TooManyRegulators,
+
+		/// The payment already has an envelope
+		EnvelopeExists,
+
+		/// The personal data of the payment has been erased
+		PaymentDataErased,
+
+		/// The source bank id is not a bank of the sender
+		NotSenderBank,
+
+		/// The destination bank id is not a bank of the destination PSP
+		NotDestinationBank,
+
+		/// The sender has not given the source bank data of the payment
+		SourceBankNotSet,
+
+		/// The destination PSP has not attested the creditor at the payment's destination bank
+		CreditorNotAttested,
 	}
-}
 
-decl_module! {
-	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn integrity_test() {
+			assert!(
+				T::MaxRegulators::get().saturating_add(2) <= T::MaxEnvelopeRecipients::get(),
+				"Envelopes must have room for both PSPs and every regulator",
+			);
+		}
 
-		// Initialize errors
-		type Error = Error<T>;
+		/// Erase the personal data whose retention period ends in this block.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let mut due = <ErasureQueue<T>>::take(now);
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
 
-		// Initialize events
-		fn deposit_event() = default;
+			let max = T::MaxErasuresPerBlock::get() as usize;
+			if due.len() > max {
+				// Erase the rest first thing in the next block.
+				let mut rest = due.split_off(max);
+				<ErasureQueue<T>>::mutate(now.saturating_add(One::one()), |next| {
+					rest.append(next);
+					*next = rest;
+				});
+				weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			}
 
-		#[weight = 10_000]
-		fn set_status(origin, current_status: Vec<u8>, reason_for_status: Vec<u8>) -> DispatchResult{
-			let user = ensure_signed(origin)?;
+			let count = due.len() as Weight;
+			for (source_psp, payment_uuid) in due {
+				Self::erase(&source_psp, &payment_uuid);
+			}
+
+			weight.saturating_add(T::DbWeight::get().reads_writes(5 * count, 8 * count))
+		}
+
+		/// Bring storage written by earlier versions of the pallet to the latest layout, one
+		/// release at a time.
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
+
+			if <StorageVersion<T>>::get() == Releases::V1 {
+				// Drop the plaintext bank data written before only commitments were stored, and
+				// turn the charge bearer codes stored before `ChargeBearer` existed into the enum.
+				weight = weight.saturating_add(migrations::remove_plaintext_bank_data::<T>());
+
+				let mut translated = 0u64;
+				<FinalPayment<T>>::translate::<OldFinalPaymentStruct, _>(|_, old| {
+					translated += 1;
+					Some(FinalPaymentStruct {
+						message_id: old.message_id,
+						creation_time: old.creation_time,
+						settlement_amount: old.settlement_amount,
+						payment_uuid: old.payment_uuid,
+						clearing_system_ref: old.clearing_system_ref,
+						charge_bearer: ChargeBearer::from_code(&old.charge_bearer)
+							.unwrap_or_default(),
+						quote_uuid: old.quote_uuid,
+						lp_source: old.lp_source,
+					})
+				});
+				<StorageVersion<T>>::put(Releases::V2ChargeBearer);
+				weight = weight
+					.saturating_add(T::DbWeight::get().reads_writes(translated, translated + 1));
+			}
+
+			if <StorageVersion<T>>::get() == Releases::V2ChargeBearer {
+				// Key the payment-versus-payment settlements by FXP as well as quote.
+				weight = weight.saturating_add(migrations::key_pvp_settlements_by_fxp::<T>());
+				<StorageVersion<T>>::put(Releases::V3PvpByFxp);
+			}
+
+			if <StorageVersion<T>>::get() == Releases::V3PvpByFxp {
+				// Record the sender of the payments made before `PaymentOwners` existed.
+				weight = weight.saturating_add(migrations::backfill_payment_owners::<T>());
+				<StorageVersion<T>>::put(Releases::V4PaymentOwners);
+			}
+
+			if <StorageVersion<T>>::get() == Releases::V4PaymentOwners {
+				// Index the payments recorded before `ReceivedPayments` existed by destination PSP.
+				weight = weight.saturating_add(migrations::index_received_payments::<T>());
+				<StorageVersion<T>>::put(Releases::V5ReceivedPayments);
+			}
+
+			weight
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::weight(T::WeightInfo::set_status())]
+		pub fn set_status(
+			origin: OriginFor<T>,
+			current_status: Vec<u8>,
+			reason_for_status: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[&current_status, &reason_for_status],
+				Error::<T>::FieldTooLong,
+			)?;
 
 			let current_status_clone = current_status.clone();
 
@@ -139,53 +1161,129 @@ decl_module! {
 
 			<Status<T>>::insert(&user, status);
 
-			Self::deposit_event(RawEvent::StatusSet(user, current_status_clone, reason_for_status_clone));
+			Self::deposit_event(Event::StatusSet(
+				user,
+				current_status_clone,
+				reason_for_status_clone,
+			));
 
-			Ok(())
+			Ok(().into())
 		}
 
-		#[weight = 10_000_000]
-		fn set_dest_bank_data(origin,payment_id: Vec<u8> ,dest_bank_id: Vec<u8>, dest_bank_acc_number: Vec<u8>, dest_bank_acc_name: Vec<u8>, dest_bank_acc_add: Vec<u8>, dest_bank_acc_dob: Vec<u8>, dest_bank_acc_dop: Vec<u8>, dest_bank_acc_national_id: Vec<u8>) -> DispatchResult {
-			let user = ensure_signed(origin)?;
-			let dest_bank = DestinationBankStruct {
-				dest_bank_id: dest_bank_id,
-				dest_bank_acc_number: dest_bank_acc_number,
-				dest_bank_acc_name: dest_bank_acc_name,
-				dest_bank_acc_add: dest_bank_acc_add,
-				dest_bank_acc_dob: dest_bank_acc_dob,
-				dest_bank_acc_dop: dest_bank_acc_dop,
-				dest_bank_acc_national_id: dest_bank_acc_national_id,
-			};
-			<DestBank<T>>::insert((&user, &payment_id), dest_bank);
-
-			Self::deposit_event(RawEvent::DestBankSet(user, payment_id));
+		/// Record the creditor of a payment. `dest_party_hash` is `PartyDetails::commitment` of the
+		/// creditor's details; the details themselves stay off-chain.
+		#[pallet::weight(T::WeightInfo::set_dest_bank_data())]
+		pub fn set_dest_bank_data(
+			origin: OriginFor<T>,
+			payment_id: Vec<u8>,
+			dest_bank_id: Vec<u8>,
+			dest_party_hash: T::Hash,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+			Self::store_dest_bank(user, payment_id, dest_bank_id, dest_party_hash)?;
 
-			Ok(())
+			Ok(().into())
 		}
 
-		#[weight = 10_000_000]
-		fn set_source_bank_data(origin,payment_id: Vec<u8>  ,source_bank_id: Vec<u8>, source_bank_acc_number: Vec<u8>, source_bank_acc_name: Vec<u8>, source_bank_acc_add: Vec<u8>, source_bank_acc_dob: Vec<u8>, source_bank_acc_dop: Vec<u8>, source_bank_acc_national_id: Vec<u8>) -> DispatchResult {
-			let user = ensure_signed(origin)?;
-			let source_bank = SourceBankStruct {
+		/// Record the debtor of a payment. `source_party_hash` is `PartyDetails::commitment` of the
+		/// debtor's details; the details themselves stay off-chain. `debtor_id_hash` is the hashed
+		/// identifier the debtor is screened and limited by.
+		///
+		/// `source_bank_id` must be the BIC or LEI of the sender.
+		#[pallet::weight(T::WeightInfo::set_source_bank_data())]
+		pub fn set_source_bank_data(
+			origin: OriginFor<T>,
+			payment_id: Vec<u8>,
+			source_bank_id: Vec<u8>,
+			source_party_hash: T::Hash,
+			debtor_id_hash: T::Hash,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+			Self::store_source_bank(
+				user,
+				payment_id,
 				source_bank_id,
-				 source_bank_acc_number,
-				 source_bank_acc_name,
-				 source_bank_acc_add,
-				 source_bank_acc_dob,
-				 source_bank_acc_dop,
-				 source_bank_acc_national_id,
+				source_party_hash,
+				debtor_id_hash,
+			)?;
+
+			Ok(().into())
+		}
+
+		/// Give the hashed identifier of the creditor of a payment `source_psp` is about to send
+		/// to an account at `dest_bank_id`, which must be a bank of the sender. The payment is
+		/// screened with this rather than anything its sender claims about the creditor.
+		#[pallet::weight(T::WeightInfo::attest_creditor())]
+		pub fn attest_creditor(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+			dest_bank_id: Vec<u8>,
+			creditor_id_hash: T::Hash,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[&payment_uuid, &dest_bank_id],
+				Error::<T>::FieldTooLong,
+			)?;
+			ensure!(
+				T::Directory::is_bank_of(&user, &dest_bank_id),
+				Error::<T>::NotDestinationBank
+			);
+			// Once the payment is recorded its creditor no longer changes.
+			ensure!(
+				!<FinalPayment<T>>::contains_key((&source_psp, &payment_uuid)),
+				Error::<T>::DuplicatePayment
+			);
+
+			let attestation = CreditorAttestation {
+				dest_psp: user.clone(),
+				dest_bank_id,
+				creditor_id_hash,
 			};
-			<SourceBank<T>>::insert((&user, &payment_id), source_bank);
+			<CreditorAttestations<T>>::insert((&source_psp, &payment_uuid), attestation);
+			Self::schedule_erasure(source_psp.clone(), payment_uuid.clone());
 
-			Self::deposit_event(RawEvent::SourceBankSet(user, payment_id));
+			Self::deposit_event(Event::CreditorAttested(source_psp, payment_uuid, user));
 
-			Ok(())
+			Ok(().into())
 		}
 
-		#[weight = 10_000_000]
-		fn final_payment_func(origin, message_id: Vec<u8>, creation_time: Vec<u8>, settlement_amount: Vec<u8>, payment_uuid: Vec<u8>, clearing_system_ref: Vec<u8>, charge_bearer: Vec<u8>, quote_uuid: Vec<u8>, lp_source: Vec<u8>) -> DispatchResult {
-			let user = ensure_signed(origin)?;
-			let payment_uuid_clone = payment_uuid.clone();
+		/// Record a final payment and reserve `source_amount` of `source_currency` from the sender
+		/// until the destination PSP answers. The parties are screened first, the debtor with the
+		/// `debtor_id_hash` given in `set_source_bank_data` and the creditor with the hash the
+		/// destination PSP gave in `attest_creditor`: a held payment waits for compliance and a
+		/// rejected one is recorded without reserving anything.
+		///
+		/// The payment must use a quote the sender has locked with `fxp`, and `destination_amount`
+		/// as well as `settlement_amount`, in the smallest unit of the destination currency, must
+		/// be `source_amount` at the quoted rate. The payee at the bank given in
+		/// `set_dest_bank_data` must have been looked up under the payment id and confirmed, and
+		/// that bank must be `dest_psp`.
+		///
+		/// The payment must stay within the `max_destination_value` the SLD of the IPS `dest_psp`
+		/// settles in published for its country, and within the limits of its debtor, PSP and
+		/// corridor. A payment in breach is refused with `TransactionLimitExceeded` or
+		/// `DestinationValueExceeded`, and the breach is also reported in an event.
+		#[pallet::weight(T::WeightInfo::final_payment_func())]
+		pub fn final_payment_func(
+			origin: OriginFor<T>,
+			message_id: Vec<u8>,
+			creation_time: Vec<u8>,
+			settlement_amount: Vec<u8>,
+			payment_uuid: Vec<u8>,
+			clearing_system_ref: Vec<u8>,
+			charge_bearer: ChargeBearer,
+			quote_uuid: Vec<u8>,
+			lp_source: Vec<u8>,
+			dest_psp: T::AccountId,
+			fxp: T::AccountId,
+			source_currency: CurrencyCode,
+			source_amount: BalanceOf<T>,
+			destination_currency: CurrencyCode,
+			destination_amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
 
 			let final_payment = FinalPaymentStruct {
 				message_id,
@@ -198,27 +1296,1403 @@ decl_module! {
 				lp_source,
 			};
 
-			<FinalPayment<T>>::insert((&user, &payment_uuid_clone), final_payment);
+			let settlement = SettlementStruct {
+				dest_psp,
+				fxp,
+				source_currency,
+				source_amount,
+				destination_currency,
+				destination_amount,
+			};
+
+			Self::record_payment(user, final_payment, settlement)?;
+			Ok(().into())
+		}
+
+		/// Record many fully formed payments at once, with their bank data. With
+		/// `BatchMode::AllOrNothing` the batch fails as a whole when any payment fails; with
+		/// `BatchMode::BestEffort` each failed payment is reported in `BatchItemFailed` and left out.
+		#[pallet::weight(T::WeightInfo::submit_payments(payments.len() as u32))]
+		#[transactional]
+		pub fn submit_payments(
+			origin: OriginFor<T>,
+			payments: Vec<PaymentSubmissionOf<T>>,
+			mode: BatchMode,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+			ensure!(
+				payments.len() as u32 <= T::MaxBatchSize::get(),
+				Error::<T>::BatchTooLarge
+			);
+
+			let total = payments.len() as u32;
+			let mut failed = 0u32;
+			for (index, payment) in payments.into_iter().enumerate() {
+				let payment_uuid = payment.payment_uuid.clone();
+				let result = with_transaction(|| {
+					let result = Self::submit_payment(user.clone(), payment);
+					if result.is_ok() {
+						TransactionOutcome::Commit(result)
+					} else {
+						TransactionOutcome::Rollback(result)
+					}
+				});
+
+				if let Err(error) = result {
+					if mode == BatchMode::AllOrNothing {
+						return Err(error.into());
+					}
+					failed += 1;
+					Self::deposit_event(Event::BatchItemFailed(
+						user.clone(),
+						index as u32,
+						payment_uuid,
+						error,
+					));
+				}
+			}
+
+			Self::deposit_event(Event::BatchSubmitted(user, total - failed, failed));
+
+			Ok(().into())
+		}
+
+		/// Set the limits on payments in `currency` within `scope`, or remove them with `None`.
+		#[pallet::weight(T::WeightInfo::set_limit())]
+		pub fn set_limit(
+			origin: OriginFor<T>,
+			scope: LimitScopeOf<T>,
+			currency: CurrencyCode,
+			limit: Option<TransactionLimit<BalanceOf<T>>>,
+		) -> DispatchResultWithPostInfo {
+			T::LimitOrigin::ensure_origin(origin)?;
+
+			match &limit {
+				Some(limit) => <Limits<T>>::insert(&scope, currency, limit),
+				None => {
+					<Limits<T>>::remove(&scope, currency);
+					<LimitUsages<T>>::remove_prefix((scope.clone(), currency));
+				}
+			}
+
+			Self::deposit_event(Event::LimitSet(scope, currency, limit));
+
+			Ok(().into())
+		}
+
+		/// Set the fees the sender charges on payments in `currency`, or remove them with `None`.
+		/// As a source PSP or FXP they apply to the source amount, as a destination PSP to the
+		/// destination amount.
+		#[pallet::weight(T::WeightInfo::set_fee_schedule())]
+		pub fn set_fee_schedule(
+			origin: OriginFor<T>,
+			currency: CurrencyCode,
+			schedule: Option<FeeSchedule<BalanceOf<T>>>,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+
+			match &schedule {
+				Some(schedule) => <FeeSchedules<T>>::insert(&user, currency, schedule),
+				None => <FeeSchedules<T>>::remove(&user, currency),
+			}
+
+			Self::deposit_event(Event::FeeScheduleSet(user, currency, schedule));
+
+			Ok(().into())
+		}
+
+		/// Erase the bank data and envelope of a payment before its retention period ends. Only the
+		/// source PSP, as the data controller, may do this. A hash of the erased data is kept.
+		#[pallet::weight(T::WeightInfo::erase_payment_data())]
+		pub fn erase_payment_data(
+			origin: OriginFor<T>,
+			payment_uuid: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+
+			ensure!(
+				Self::erase(&user, &payment_uuid),
+				Error::<T>::NothingToErase
+			);
+
+			Ok(().into())
+		}
+
+		/// Choose how resubmissions of the sender's payments are treated.
+		#[pallet::weight(T::WeightInfo::set_retry_mode())]
+		pub fn set_retry_mode(origin: OriginFor<T>, mode: RetryMode) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+
+			<RetryModes<T>>::insert(&user, mode);
+
+			Self::deposit_event(Event::RetryModeSet(user, mode));
+
+			Ok(().into())
+		}
+
+		/// Release a payment held by screening so the destination PSP can answer it, or reject it
+		/// and give the reserved source amount back.
+		#[pallet::weight(T::WeightInfo::resolve_screening_hold())]
+		pub fn resolve_screening_hold(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+			release: bool,
+		) -> DispatchResultWithPostInfo {
+			T::ScreeningOrigin::ensure_origin(origin)?;
+			let keys = (source_psp.clone(), payment_uuid.clone());
+
+			let settlement = Self::settlement(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			let mut status = Self::payment_status(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(
+				status.state == PaymentState::ScreeningHeld,
+				Error::<T>::InvalidPaymentState
+			);
+
+			if release {
+				status.state = PaymentState::Pending;
+			} else {
+				T::Assets::unreserve(
+					settlement.source_currency,
+					&source_psp,
+					settlement.source_amount,
+				);
+				Self::release_fx_leg(&keys, &settlement);
+				Self::release_limits(&keys, settlement.source_amount);
+				status.state = PaymentState::ScreeningRejected;
+			}
+			status.updated_at = <frame_system::Module<T>>::block_number();
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::ScreeningHoldResolved(
+				source_psp,
+				payment_uuid,
+				release,
+			));
+
+			Ok(().into())
+		}
+
+		/// Accept the FX leg of a payment as its FXP by reserving the destination amount until
+		/// the destination PSP answers. The FXP also agrees to unwind the payment at the same
+		/// amounts if it is returned. No value moves from the FXP before it has done this.
+		#[pallet::weight(T::WeightInfo::accept_fx_leg())]
+		pub fn accept_fx_leg(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+			let keys = (source_psp.clone(), payment_uuid.clone());
+
+			let settlement = Self::settlement(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(settlement.fxp == user, Error::<T>::NotPaymentFxp);
+			let status = Self::payment_status(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(
+				status.state == PaymentState::Pending
+					|| status.state == PaymentState::ScreeningHeld,
+				Error::<T>::InvalidPaymentState
+			);
+			ensure!(
+				!<FxLegAcceptances<T>>::contains_key(&keys),
+				Error::<T>::FxLegAlreadyAccepted
+			);
+
+			T::Assets::reserve(
+				settlement.destination_currency,
+				&user,
+				settlement.destination_amount,
+			)?;
+			<FxLegAcceptances<T>>::insert(&keys, <frame_system::Module<T>>::block_number());
+
+			Self::deposit_event(Event::FxLegAccepted(
+				source_psp,
+				payment_uuid,
+				user,
+				settlement.destination_amount,
+			));
+
+			Ok(().into())
+		}
+
+		/// Accept a pending payment and settle both of its legs payment-versus-payment. The FXP
+		/// must have accepted the FX leg with `accept_fx_leg`.
+		#[pallet::weight(T::WeightInfo::accept_payment())]
+		pub fn accept_payment(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			let keys = (source_psp.clone(), payment_uuid.clone());
+
+			let (settlement, mut status) = Self::pending_payment(&keys, &user)?;
+			let payment = Self::final_payment(&keys);
+
+			Self::settle_pvp(&source_psp, &user, &payment, &settlement)?;
+
+			status.state = PaymentState::Accepted;
+			status.updated_at = <frame_system::Module<T>>::block_number();
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::PaymentSettled(
+				source_psp,
+				payment_uuid,
+				user,
+				settlement.destination_amount,
+			));
+
+			Ok(().into())
+		}
+
+		/// Reject a pending payment and give the reserved amounts back to the sender and the FXP.
+		#[pallet::weight(T::WeightInfo::reject_payment())]
+		pub fn reject_payment(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+			reason_for_status: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[&reason_for_status],
+				Error::<T>::FieldTooLong,
+			)?;
+			let keys = (source_psp.clone(), payment_uuid.clone());
+
+			let (settlement, mut status) = Self::pending_payment(&keys, &user)?;
+
+			T::Assets::unreserve(
+				settlement.source_currency,
+				&source_psp,
+				settlement.source_amount,
+			);
+			Self::release_fx_leg(&keys, &settlement);
+			Self::release_limits(&keys, settlement.source_amount);
+
+			status.state = PaymentState::Rejected;
+			status.updated_at = <frame_system::Module<T>>::block_number();
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::PaymentRejected(
+				source_psp,
+				payment_uuid,
+				user,
+				reason_for_status,
+			));
+
+			Ok(().into())
+		}
+
+		/// Move a pending payment onto a hash-time-lock so that its destination leg can settle on
+		/// another ledger. The reserved source amount is locked for the FXP under `hashlock`, and
+		/// what the FXP reserved for the destination leg here is given back.
+		#[pallet::weight(T::WeightInfo::lock_payment_htlc())]
+		#[transactional]
+		pub fn lock_payment_htlc(
+			origin: OriginFor<T>,
+			payment_uuid: Vec<u8>,
+			hashlock: HashLock,
+			expires_at: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+			let keys = (user.clone(), payment_uuid.clone());
+
+			let settlement = Self::settlement(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			let mut status = Self::payment_status(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(
+				status.state == PaymentState::Pending,
+				Error::<T>::InvalidPaymentState
+			);
 
-			Self::deposit_event(RawEvent::FinalPaymentSet(user, payment_uuid_clone));
+			T::Assets::unreserve(settlement.source_currency, &user, settlement.source_amount);
+			Self::release_fx_leg(&keys, &settlement);
+			T::Htlc::lock(
+				&user,
+				&settlement.fxp,
+				settlement.source_currency,
+				settlement.source_amount,
+				hashlock,
+				expires_at,
+			)?;
 
-			Ok(())
+			status.state = PaymentState::HtlcLocked;
+			status.updated_at = <frame_system::Module<T>>::block_number();
 
+			<PaymentHtlcs<T>>::insert(&hashlock, &keys);
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::PaymentHtlcLocked(user, payment_uuid, hashlock));
+
+			Ok(().into())
 		}
 
-		#[weight = 10_000_000]
-		fn get_final_payment(origin, payment_id: Vec<u8>) -> DispatchResult {
+		/// Register the key that payment envelopes for this account are encrypted to.
+		#[pallet::weight(T::WeightInfo::set_encryption_key())]
+		pub fn set_encryption_key(
+			origin: OriginFor<T>,
+			key: EncryptionKey,
+		) -> DispatchResultWithPostInfo {
 			let user = ensure_signed(origin)?;
-			let keys = (&user, payment_id);
-			let keys_clone = keys.clone();
-			let sec_clone = keys.clone();
 
-			let dest_data = <DestBank<T>>::get(keys_clone);
-			let source_data = <SourceBank<T>>::get(keys);
-			let final_data = <FinalPayment<T>>::get(sec_clone);
+			<EncryptionKeys<T>>::insert(&user, key);
+
+			Self::deposit_event(Event::EncryptionKeySet(user, key));
+
+			Ok(().into())
+		}
+
+		/// Add, change or remove (`None`) the view key of a regulator. Every envelope stored
+		/// afterwards must be readable with it. There may be at most `MaxRegulators` of them.
+		#[pallet::weight(T::WeightInfo::set_regulator_key(T::MaxRegulators::get()))]
+		pub fn set_regulator_key(
+			origin: OriginFor<T>,
+			regulator: T::AccountId,
+			key: Option<EncryptionKey>,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			match key {
+				Some(key) => {
+					ensure!(
+						<RegulatorKeys<T>>::contains_key(&regulator)
+							|| (<RegulatorKeys<T>>::iter().count() as u32)
+								< T::MaxRegulators::get(),
+						Error::<T>::TooManyRegulators
+					);
+					<RegulatorKeys<T>>::insert(&regulator, key);
+				}
+				None => <RegulatorKeys<T>>::remove(&regulator),
+			}
+
+			Self::deposit_event(Event::RegulatorKeySet(regulator, key));
+
+			Ok(().into())
+		}
+
+		/// Attach the encrypted ISO payload of a payment. The envelope must be readable by the
+		/// source PSP, the destination PSP and every registered regulator. Once attached, it
+		/// cannot be replaced, so its `EnvelopeStored` hash stays the one the payload is checked
+		/// against, nor attached again once the payment's personal data has been erased.
+		#[pallet::weight(T::WeightInfo::set_payment_envelope(
+			envelope.recipients.len() as u32,
+			envelope.ciphertext.len() as u32,
+			T::MaxRegulators::get(),
+		))]
+		pub fn set_payment_envelope(
+			origin: OriginFor<T>,
+			payment_uuid: Vec<u8>,
+			envelope: Envelope<T::Hash>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+			ensure!(
+				envelope.ciphertext.len() <= T::MaxEnvelopeLength::get() as usize,
+				Error::<T>::EnvelopeTooLong
+			);
+			ensure!(
+				envelope.recipients.len() <= T::MaxEnvelopeRecipients::get() as usize,
+				Error::<T>::TooManyEnvelopeRecipients
+			);
+			let wrapped_keys: Vec<&[u8]> = envelope
+				.recipients
+				.iter()
+				.map(|(_, wrapped_key)| &wrapped_key[..])
+				.collect();
+			ensure_field_lengths::<T::MaxFieldLength, _>(&wrapped_keys, Error::<T>::FieldTooLong)?;
+			let keys = (user.clone(), payment_uuid.clone());
+
+			let settlement = Self::settlement(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(
+				!<Envelopes<T>>::contains_key(&keys),
+				Error::<T>::EnvelopeExists
+			);
+			ensure!(
+				!<Tombstones<T>>::contains_key(&keys),
+				Error::<T>::PaymentDataErased
+			);
+			let source_key = Self::encryption_key(&user).ok_or(Error::<T>::NoEncryptionKey)?;
+			let dest_key =
+				Self::encryption_key(&settlement.dest_psp).ok_or(Error::<T>::NoEncryptionKey)?;
 
-			Self::deposit_event(RawEvent::FinalData(user, dest_data.dest_bank_id, dest_data.dest_bank_acc_number, dest_data.dest_bank_acc_name, dest_data.dest_bank_acc_add, dest_data.dest_bank_acc_dob, dest_data.dest_bank_acc_dop, dest_data.dest_bank_acc_national_id, source_data.source_bank_id, source_data.source_bank_acc_number, source_data.source_bank_acc_name, source_data.source_bank_acc_add, source_data.source_bank_acc_dob, source_data.source_bank_acc_dop, source_data.source_bank_acc_national_id, final_data.message_id, final_data.creation_time, final_data.settlement_amount, final_data.payment_uuid, final_data.clearing_system_ref, final_data.charge_bearer, final_data.quote_uuid, final_data.lp_source));
-			Ok(())
+			let is_recipient =
+				|key: &EncryptionKey| envelope.recipients.iter().any(|(k, _)| k == key);
+			ensure!(
+				is_recipient(&source_key) && is_recipient(&dest_key),
+				Error::<T>::MissingEnvelopeRecipient
+			);
+			ensure!(
+				<RegulatorKeys<T>>::iter().all(|(_, key)| is_recipient(&key)),
+				Error::<T>::MissingEnvelopeRecipient
+			);
+
+			let payload_hash = envelope.payload_hash;
+			<Envelopes<T>>::insert(&keys, envelope);
+			Self::schedule_erasure(user.clone(), payment_uuid.clone());
+
+			Self::deposit_event(Event::EnvelopeStored(user, payment_uuid, payload_hash));
+
+			Ok(().into())
 		}
+
+		/// Ask the destination PSP to give back a payment (camt.056).
+		#[pallet::weight(T::WeightInfo::request_recall())]
+		pub fn request_recall(
+			origin: OriginFor<T>,
+			payment_uuid: Vec<u8>,
+			reason: RecallReason,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+			let keys = (user.clone(), payment_uuid.clone());
+
+			let dest_psp = Self::settlement(&keys)
+				.ok_or(Error::<T>::PaymentNotFound)?
+				.dest_psp;
+
+			let mut status = Self::payment_status(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(
+				status.state == PaymentState::Accepted,
+				Error::<T>::InvalidPaymentState
+			);
+
+			let now = <frame_system::Module<T>>::block_number();
+			ensure!(
+				now <= status.created_at.saturating_add(T::RecallWindow::get()),
+				Error::<T>::RecallWindowExpired
+			);
+
+			let recall = RecallStruct {
+				dest_psp: dest_psp.clone(),
+				reason,
+				requested_at: now,
+				decline_reason: None,
+			};
+
+			status.state = PaymentState::RecallRequested;
+			status.updated_at = now;
+
+			<Recalls<T>>::insert(&keys, recall);
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::RecallRequested(user, payment_uuid, dest_psp, reason));
+
+			Ok(().into())
+		}
+
+		/// Agree to give back a recalled payment. The return itself is sent with `return_payment`.
+		#[pallet::weight(T::WeightInfo::accept_recall())]
+		pub fn accept_recall(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			let keys = (source_psp.clone(), payment_uuid.clone());
+
+			let (_, mut status) = Self::pending_recall(&keys, &user)?;
+
+			status.state = PaymentState::RecallAccepted;
+			status.updated_at = <frame_system::Module<T>>::block_number();
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::RecallAccepted(source_psp, payment_uuid, user));
+
+			Ok(().into())
+		}
+
+		/// Refuse to give back a recalled payment (camt.029).
+		#[pallet::weight(T::WeightInfo::decline_recall())]
+		pub fn decline_recall(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+			reason: RecallDeclineReason,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			let keys = (source_psp.clone(), payment_uuid.clone());
+
+			let (mut recall, mut status) = Self::pending_recall(&keys, &user)?;
+
+			recall.decline_reason = Some(reason);
+			status.state = PaymentState::RecallDeclined;
+			status.updated_at = <frame_system::Module<T>>::block_number();
+
+			<Recalls<T>>::insert(&keys, recall);
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::RecallDeclined(
+				source_psp,
+				payment_uuid,
+				user,
+				reason,
+			));
+
+			Ok(().into())
+		}
+
+		/// Send back the funds of an accepted recall (pacs.004). The destination amount goes back
+		/// to the FXP and the FXP pays the source amount back to the source PSP, as it agreed to in
+		/// `accept_fx_leg`.
+		#[pallet::weight(T::WeightInfo::return_payment())]
+		#[transactional]
+		pub fn return_payment(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			payment_uuid: Vec<u8>,
+			return_id: Vec<u8>,
+			returned_amount: Vec<u8>,
+			reason: ReturnReason,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[&return_id, &returned_amount],
+				Error::<T>::FieldTooLong,
+			)?;
+			let keys = (source_psp.clone(), payment_uuid.clone());
+
+			let recall = Self::recall(&keys).ok_or(Error::<T>::NoRecall)?;
+			ensure!(recall.dest_psp == user, Error::<T>::NotRecallAssignee);
+
+			let mut status = Self::payment_status(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(
+				status.state == PaymentState::RecallAccepted,
+				Error::<T>::InvalidPaymentState
+			);
+
+			let now = <frame_system::Module<T>>::block_number();
+			ensure!(
+				now <= status.updated_at.saturating_add(T::ReturnWindow::get()),
+				Error::<T>::ReturnWindowExpired
+			);
+
+			let settlement = Self::settlement(&keys).ok_or(Error::<T>::PaymentNotFound)?;
+			ensure!(
+				<FxLegAcceptances<T>>::contains_key(&keys),
+				Error::<T>::FxLegNotAccepted
+			);
+			T::Assets::transfer(
+				settlement.destination_currency,
+				&user,
+				&settlement.fxp,
+				settlement.destination_amount,
+			)?;
+			T::Assets::transfer(
+				settlement.source_currency,
+				&settlement.fxp,
+				&source_psp,
+				settlement.source_amount,
+			)?;
+
+			let payment_return = ReturnStruct {
+				return_id: return_id.clone(),
+				original_payment_uuid: payment_uuid.clone(),
+				returned_amount,
+				reason,
+				returned_by: user,
+				returned_at: now,
+			};
+
+			Self::release_limits(&keys, settlement.source_amount);
+			status.state = PaymentState::Returned;
+			status.updated_at = now;
+
+			<Returns<T>>::insert(&keys, payment_return);
+			<PaymentStatus<T>>::insert(&keys, status);
+
+			Self::deposit_event(Event::PaymentReturned(
+				source_psp,
+				payment_uuid,
+				return_id,
+				reason,
+			));
+
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Everything recorded about the payment `payment_uuid`, whoever sent it.
+	pub fn payment_info(
+		payment_uuid: Vec<u8>,
+	) -> Option<PaymentInfo<T::AccountId, BalanceOf<T>, T::Hash, T::BlockNumber>> {
+		let source_psp = Self::payment_owner(&payment_uuid)?;
+		let keys = (source_psp.clone(), payment_uuid);
+		if !<FinalPayment<T>>::contains_key(&keys) {
+			return None;
+		}
+
+		let source_bank = if <SourceBankCommitment<T>>::contains_key(&keys) {
+			Some(Self::source_bank(&keys))
+		} else {
+			None
+		};
+		let dest_bank = if <DestBankCommitment<T>>::contains_key(&keys) {
+			Some(Self::dest_bank(&keys))
+		} else {
+			None
+		};
+
+		Some(PaymentInfo {
+			source_psp,
+			payment: Self::final_payment(&keys),
+			source_bank,
+			dest_bank,
+			settlement: Self::settlement(&keys),
+			status: Self::payment_status(&keys),
+			costs: Self::payment_costs(&keys),
+		})
+	}
+
+	/// The ids of the payments `psp` has sent, followed by those it is the destination PSP of.
+	pub fn payments_by_psp(psp: &T::AccountId) -> Vec<Vec<u8>> {
+		let sent = <MessageIds<T>>::iter_prefix_values(psp);
+		let received = <ReceivedPayments<T>>::iter_prefix(psp)
+			.filter(|((source_psp, _), _)| source_psp != psp)
+			.map(|((_, payment_uuid), _)| payment_uuid);
+
+		sent.chain(received).collect()
+	}
+
+	/// The payments whose status one of `events` changed, each once, with the status they
+	/// have now. Given the events of a block, this is what the block did to each payment. A
+	/// payment refused for a limit breach, and not recorded since, is reported as
+	/// `LimitExceeded` in the current block.
+	pub fn payment_transitions(
+		events: impl IntoIterator<Item = Event<T>>,
+	) -> Vec<PaymentTransition<T::AccountId, T::BlockNumber>> {
+		let mut transitions: Vec<PaymentTransition<T::AccountId, T::BlockNumber>> = Vec::new();
+		for event in events {
+			let (source_psp, payment_uuid, breached) = match event {
+				Event::TransactionLimitExceeded(source_psp, payment_uuid, _, _)
+				| Event::DestinationValueExceeded(source_psp, payment_uuid, _) => {
+					(source_psp, payment_uuid, true)
+				}
+				Event::PaymentScreened(source_psp, payment_uuid, _)
+				| Event::ScreeningHoldResolved(source_psp, payment_uuid, _)
+				| Event::PaymentSettled(source_psp, payment_uuid, _, _)
+				| Event::PaymentRejected(source_psp, payment_uuid, _, _)
+				| Event::PaymentHtlcLocked(source_psp, payment_uuid, _)
+				| Event::PaymentHtlcResolved(source_psp, payment_uuid, _)
+				| Event::RecallRequested(source_psp, payment_uuid, _, _)
+				| Event::RecallAccepted(source_psp, payment_uuid, _)
+				| Event::RecallDeclined(source_psp, payment_uuid, _, _)
+				| Event::PaymentReturned(source_psp, payment_uuid, _, _) => (source_psp, payment_uuid, false),
+				_ => continue,
+			};
+			if transitions
+				.iter()
+				.any(|t| t.source_psp == source_psp && t.payment_uuid == payment_uuid)
+			{
+				continue;
+			}
+
+			let keys = (source_psp, payment_uuid);
+			let dest_psp = Self::settlement(&keys).map(|settlement| settlement.dest_psp);
+			let status = match Self::payment_status(&keys) {
+				Some(status) => status,
+				None if breached => {
+					let now = <frame_system::Module<T>>::block_number();
+					PaymentStatusInfo {
+						state: PaymentState::LimitExceeded,
+						created_at: now,
+						updated_at: now,
+					}
+				}
+				None => continue,
+			};
+			let (source_psp, payment_uuid) = keys;
+			transitions.push(PaymentTransition {
+				source_psp,
+				dest_psp,
+				payment_uuid,
+				status,
+			});
+		}
+		transitions
+	}
+
+	/// Check `payment` against the payments `source_psp` and everyone else have sent before.
+	/// Returns the state of the existing record if `payment` is an identical resubmission that
+	/// the sender's `RetryMode` allows to be ignored.
+	fn check_duplicate(
+		source_psp: &T::AccountId,
+		payment: &FinalPaymentStruct,
+	) -> Result<Option<PaymentState>, Error<T>> {
+		let keys = (source_psp.clone(), payment.payment_uuid.clone());
+
+		let owner = Self::payment_owner(&payment.payment_uuid);
+		if owner.is_none() && !<FinalPayment<T>>::contains_key(&keys) {
+			ensure!(
+				!<MessageIds<T>>::contains_key(source_psp, &payment.message_id),
+				Error::<T>::DuplicatePayment
+			);
+			return Ok(None);
+		}
+
+		let is_retry = owner.map_or(true, |owner| &owner == source_psp)
+			&& Self::retry_mode(source_psp) == RetryMode::Idempotent
+			&& &Self::final_payment(&keys) == payment;
+		ensure!(is_retry, Error::<T>::DuplicatePayment);
+
+		Ok(Some(
+			Self::payment_status(&keys)
+				.map(|status| status.state)
+				.unwrap_or_default(),
+		))
+	}
+
+	/// Check and record a final payment; see `final_payment_func`.
+	fn record_payment(
+		user: T::AccountId,
+		final_payment: FinalPaymentStruct,
+		settlement: SettlementStruct<T::AccountId, BalanceOf<T>>,
+	) -> DispatchResult {
+		ensure_field_lengths::<T::MaxFieldLength, _>(
+			&[
+				&final_payment.message_id,
+				&final_payment.creation_time,
+				&final_payment.settlement_amount,
+				&final_payment.payment_uuid,
+				&final_payment.clearing_system_ref,
+				&final_payment.quote_uuid,
+				&final_payment.lp_source,
+			],
+			Error::<T>::FieldTooLong,
+		)?;
+
+		let payment_uuid_clone = final_payment.payment_uuid.clone();
+		let message_id_clone = final_payment.message_id.clone();
+		let source_currency = settlement.source_currency;
+		let source_amount = settlement.source_amount;
+		let destination_currency = settlement.destination_currency;
+		let destination_amount = settlement.destination_amount;
+
+		if let Some(state) = Self::check_duplicate(&user, &final_payment)? {
+			Self::deposit_event(Event::DuplicatePaymentIgnored(
+				user,
+				payment_uuid_clone,
+				state,
+			));
+			return Ok(());
+		}
+
+		Self::check_quote(&user, &final_payment, &settlement)?;
+
+		let costs = Self::cost_disclosure(&user, &settlement, final_payment.charge_bearer);
+		ensure!(
+			!costs.destination_credit.is_zero(),
+			Error::<T>::FeesExceedAmount
+		);
+
+		let keys = (user.clone(), payment_uuid_clone.clone());
+		ensure!(
+			<DestBankCommitment<T>>::contains_key(&keys)
+				&& T::Payees::is_confirmed(
+					&user,
+					&payment_uuid_clone,
+					&Self::dest_bank(&keys).dest_bank_id
+				),
+			Error::<T>::PayeeNotConfirmed
+		);
+		ensure!(
+			T::Directory::is_bank_of(&settlement.dest_psp, &Self::dest_bank(&keys).dest_bank_id),
+			Error::<T>::NotDestinationBank
+		);
+		let debtor_id_hash = Self::debtor_id_hash(&keys).ok_or(Error::<T>::SourceBankNotSet)?;
+		let creditor_id_hash = Self::creditor_attestation(&keys)
+			.filter(|attestation| {
+				attestation.dest_psp == settlement.dest_psp
+					&& attestation.dest_bank_id == Self::dest_bank(&keys).dest_bank_id
+			})
+			.map(|attestation| attestation.creditor_id_hash)
+			.ok_or(Error::<T>::CreditorNotAttested)?;
+
+		let debtor_scope =
+			if <Limits<T>>::contains_key(LimitScope::Debtor(debtor_id_hash), source_currency) {
+				LimitScope::Debtor(debtor_id_hash)
+			} else {
+				LimitScope::UnlistedDebtors(user.clone())
+			};
+		let scopes = [
+			debtor_scope,
+			LimitScope::Psp(user.clone()),
+			LimitScope::Corridor(source_currency, destination_currency),
+		];
+		// A breach fails the call, so the payment uuid and message id can be used again, but its
+		// event is deposited first so that it is kept unless the call is rolled back.
+		let max_destination_value = T::Directory::home_of(&settlement.dest_psp)
+			.and_then(|(ips, country)| T::Sld::max_destination_value(&ips, &country))
+			.filter(|&max| {
+				let destination_value: u128 = destination_amount.unique_saturated_into();
+				destination_value > max
+			});
+		if let Some(max) = max_destination_value {
+			Self::deposit_event(Event::DestinationValueExceeded(
+				user,
+				payment_uuid_clone,
+				max,
+			));
+			return Err(Error::<T>::DestinationValueExceeded.into());
+		}
+		let usages = match Self::check_limits(&scopes, source_currency, source_amount) {
+			Ok(usages) => usages,
+			Err((scope, period)) => {
+				Self::deposit_event(Event::TransactionLimitExceeded(
+					user,
+					payment_uuid_clone,
+					scope,
+					period,
+				));
+				return Err(Error::<T>::TransactionLimitExceeded.into());
+			}
+		};
+
+		let outcome = T::Screening::screen(&user, &debtor_id_hash, &creditor_id_hash);
+		let state = match outcome {
+			ScreeningOutcome::Pass => PaymentState::Pending,
+			ScreeningOutcome::Hold => PaymentState::ScreeningHeld,
+			ScreeningOutcome::Reject => PaymentState::ScreeningRejected,
+		};
+
+		if state != PaymentState::ScreeningRejected {
+			T::Assets::reserve(source_currency, &user, source_amount)?;
+
+			let mut charges = Vec::new();
+			for (key, period, usage) in usages {
+				charges.push((key.clone(), period, usage.window_start));
+				<LimitUsages<T>>::insert(key, period, usage);
+			}
+			if !charges.is_empty() {
+				<LimitCharges<T>>::insert(&keys, charges);
+			}
+		}
+
+		Self::deposit_event(Event::PaymentScreened(
+			user.clone(),
+			payment_uuid_clone.clone(),
+			outcome,
+		));
+
+		let now = <frame_system::Module<T>>::block_number();
+		let status = PaymentStatusInfo {
+			state,
+			created_at: now,
+			updated_at: now,
+		};
+
+		<QuoteUses<T>>::insert(
+			(&settlement.fxp, &final_payment.quote_uuid),
+			(&user, &payment_uuid_clone),
+		);
+		<FinalPayment<T>>::insert((&user, &payment_uuid_clone), final_payment);
+		<ReceivedPayments<T>>::insert(&settlement.dest_psp, (&user, &payment_uuid_clone), ());
+		<Settlements<T>>::insert((&user, &payment_uuid_clone), settlement);
+		<Costs<T>>::insert((&user, &payment_uuid_clone), costs);
+		<PaymentStatus<T>>::insert((&user, &payment_uuid_clone), status);
+		<PaymentOwners<T>>::insert(&payment_uuid_clone, &user);
+		<MessageIds<T>>::insert(&user, &message_id_clone, &payment_uuid_clone);
+
+		Self::deposit_event(Event::FinalPaymentSet(user, payment_uuid_clone));
+
+		Ok(())
+	}
+
+	/// Store the bank data of one batched payment and record it.
+	fn submit_payment(user: T::AccountId, payment: PaymentSubmissionOf<T>) -> DispatchResult {
+		Self::store_source_bank(
+			user.clone(),
+			payment.payment_uuid.clone(),
+			payment.source_bank_id,
+			payment.source_party_hash,
+			payment.debtor_id_hash,
+		)?;
+		Self::store_dest_bank(
+			user.clone(),
+			payment.payment_uuid.clone(),
+			payment.dest_bank_id,
+			payment.dest_party_hash,
+		)?;
+
+		let final_payment = FinalPaymentStruct {
+			message_id: payment.message_id,
+			creation_time: payment.creation_time,
+			settlement_amount: payment.settlement_amount,
+			payment_uuid: payment.payment_uuid,
+			clearing_system_ref: payment.clearing_system_ref,
+			charge_bearer: payment.charge_bearer,
+			quote_uuid: payment.quote_uuid,
+			lp_source: payment.lp_source,
+		};
+
+		let settlement = SettlementStruct {
+			dest_psp: payment.dest_psp,
+			fxp: payment.fxp,
+			source_currency: payment.source_currency,
+			source_amount: payment.source_amount,
+			destination_currency: payment.destination_currency,
+			destination_amount: payment.destination_amount,
+		};
+
+		Self::record_payment(user, final_payment, settlement)
+	}
+
+	fn store_source_bank(
+		user: T::AccountId,
+		payment_id: Vec<u8>,
+		source_bank_id: Vec<u8>,
+		source_party_hash: T::Hash,
+		debtor_id_hash: T::Hash,
+	) -> DispatchResult {
+		ensure_field_lengths::<T::MaxFieldLength, _>(
+			&[&payment_id, &source_bank_id],
+			Error::<T>::FieldTooLong,
+		)?;
+		ensure!(
+			T::Directory::is_bank_of(&user, &source_bank_id),
+			Error::<T>::NotSenderBank
+		);
+
+		let source_bank = SourceBankStruct {
+			source_bank_id,
+			source_party_hash,
+		};
+		<SourceBankCommitment<T>>::insert((&user, &payment_id), source_bank);
+		<DebtorIdHashes<T>>::insert((&user, &payment_id), debtor_id_hash);
+		Self::schedule_erasure(user.clone(), payment_id.clone());
+
+		Self::deposit_event(Event::SourceBankSet(user, payment_id, source_party_hash));
+
+		Ok(())
+	}
+
+	fn store_dest_bank(
+		user: T::AccountId,
+		payment_id: Vec<u8>,
+		dest_bank_id: Vec<u8>,
+		dest_party_hash: T::Hash,
+	) -> DispatchResult {
+		ensure_field_lengths::<T::MaxFieldLength, _>(
+			&[&payment_id, &dest_bank_id],
+			Error::<T>::FieldTooLong,
+		)?;
+
+		let dest_bank = DestinationBankStruct {
+			dest_bank_id,
+			dest_party_hash,
+		};
+		<DestBankCommitment<T>>::insert((&user, &payment_id), dest_bank);
+		Self::schedule_erasure(user.clone(), payment_id.clone());
+
+		Self::deposit_event(Event::DestBankSet(user, payment_id, dest_party_hash));
+
+		Ok(())
+	}
+
+	/// Erase the personal data of a payment once its retention period is over, counted from
+	/// when the first of it was stored.
+	fn schedule_erasure(source_psp: T::AccountId, payment_uuid: Vec<u8>) {
+		let keys = (source_psp, payment_uuid);
+		if <ErasureDue<T>>::contains_key(&keys) {
+			return;
+		}
+
+		let due =
+			<frame_system::Module<T>>::block_number().saturating_add(T::RetentionPeriod::get());
+		<ErasureDue<T>>::insert(&keys, due);
+		<ErasureQueue<T>>::append(due, keys);
+	}
+
+	/// Remove the bank data, party identifiers and envelope of a payment and keep a hash of them
+	/// as a tombstone.
+	/// Returns whether there was anything to erase.
+	fn erase(source_psp: &T::AccountId, payment_uuid: &[u8]) -> bool {
+		let keys = (source_psp.clone(), payment_uuid.to_vec());
+		<ErasureDue<T>>::remove(&keys);
+
+		let source_bank = if <SourceBankCommitment<T>>::contains_key(&keys) {
+			Some(<SourceBankCommitment<T>>::take(&keys))
+		} else {
+			None
+		};
+		let dest_bank = if <DestBankCommitment<T>>::contains_key(&keys) {
+			Some(<DestBankCommitment<T>>::take(&keys))
+		} else {
+			None
+		};
+		<LimitCharges<T>>::remove(&keys);
+		let debtor_id_hash = <DebtorIdHashes<T>>::take(&keys);
+		let creditor = <CreditorAttestations<T>>::take(&keys);
+		let envelope = <Envelopes<T>>::take(&keys);
+
+		if source_bank.is_none()
+			&& dest_bank.is_none()
+			&& debtor_id_hash.is_none()
+			&& creditor.is_none()
+			&& envelope.is_none()
+		{
+			return false;
+		}
+
+		let tombstone =
+			T::Hashing::hash_of(&(source_bank, dest_bank, debtor_id_hash, creditor, envelope));
+		<Tombstones<T>>::insert(&keys, tombstone);
+
+		Self::deposit_event(Event::PersonalDataErased(
+			source_psp.clone(),
+			payment_uuid.to_vec(),
+			tombstone,
+		));
+
+		true
+	}
+
+	/// What a payment sent by `source_psp` with `settlement` costs when `charge_bearer` pays the
+	/// fees. Fees borne on the other side of the conversion are converted at the payment's rate.
+	pub fn cost_disclosure(
+		source_psp: &T::AccountId,
+		settlement: &SettlementStruct<T::AccountId, BalanceOf<T>>,
+		charge_bearer: ChargeBearer,
+	) -> CostDisclosure<BalanceOf<T>> {
+		let fee = |who: &T::AccountId, currency: CurrencyCode, amount: BalanceOf<T>| {
+			Self::fee_schedule(who, currency)
+				.map_or_else(Zero::zero, |schedule| schedule.fee(amount))
+		};
+		let source_psp_fee = fee(
+			source_psp,
+			settlement.source_currency,
+			settlement.source_amount,
+		);
+		let fxp_fee = fee(
+			&settlement.fxp,
+			settlement.source_currency,
+			settlement.source_amount,
+		);
+		let destination_psp_fee = fee(
+			&settlement.dest_psp,
+			settlement.destination_currency,
+			settlement.destination_amount,
+		);
+
+		let convert =
+			|amount: BalanceOf<T>, from: BalanceOf<T>, to: BalanceOf<T>| -> BalanceOf<T> {
+				if from.is_zero() {
+					return Zero::zero();
+				}
+				multiply_by_rational(
+					amount.unique_saturated_into(),
+					to.unique_saturated_into(),
+					from.unique_saturated_into(),
+				)
+				.unwrap_or(u128::max_value())
+				.saturated_into()
+			};
+		let source_side_fees = source_psp_fee.saturating_add(fxp_fee);
+
+		let (source_debit, destination_credit) = match charge_bearer {
+			ChargeBearer::Debtor => (
+				settlement
+					.source_amount
+					.saturating_add(source_side_fees)
+					.saturating_add(convert(
+						destination_psp_fee,
+						settlement.destination_amount,
+						settlement.source_amount,
+					)),
+				settlement.destination_amount,
+			),
+			ChargeBearer::Creditor => (
+				settlement.source_amount,
+				settlement
+					.destination_amount
+					.saturating_sub(destination_psp_fee)
+					.saturating_sub(convert(
+						source_side_fees,
+						settlement.source_amount,
+						settlement.destination_amount,
+					)),
+			),
+			ChargeBearer::Shared | ChargeBearer::FollowingServiceLevel => (
+				settlement.source_amount.saturating_add(source_side_fees),
+				settlement
+					.destination_amount
+					.saturating_sub(destination_psp_fee),
+			),
+		};
+
+		CostDisclosure {
+			source_debit,
+			source_psp_fee,
+			fxp_fee,
+			destination_psp_fee,
+			destination_credit,
+		}
+	}
+
+	/// Check that `payment` uses a quote `source_psp` has locked and no other payment uses, and
+	/// that its amounts follow the quoted rate.
+	fn check_quote(
+		source_psp: &T::AccountId,
+		payment: &FinalPaymentStruct,
+		settlement: &SettlementStruct<T::AccountId, BalanceOf<T>>,
+	) -> DispatchResult {
+		let quote = T::Quotes::locked_quote(&settlement.fxp, &payment.quote_uuid, source_psp)
+			.map_err(|e| match e {
+				QuoteError::NotFound => Error::<T>::QuoteNotFound,
+				QuoteError::Expired => Error::<T>::QuoteExpired,
+				QuoteError::NotLocked => Error::<T>::QuoteNotLocked,
+			})?;
+		ensure!(
+			!<QuoteUses<T>>::contains_key((&settlement.fxp, &payment.quote_uuid)),
+			Error::<T>::QuoteInUse
+		);
+		ensure!(
+			quote.fxp == settlement.fxp
+				&& quote.source_currency == settlement.source_currency
+				&& quote.destination_currency == settlement.destination_currency,
+			Error::<T>::QuoteMismatch
+		);
+
+		let destination_value: u128 = settlement.destination_amount.unique_saturated_into();
+		let settlement_value = sp_std::str::from_utf8(&payment.settlement_amount)
+			.ok()
+			.and_then(|amount| amount.trim().parse::<u128>().ok());
+		ensure!(
+			quote.convert(settlement.source_amount.unique_saturated_into())
+				== Some(destination_value)
+				&& settlement_value == Some(destination_value),
+			Error::<T>::SettlementAmountMismatch
+		);
+
+		Ok(())
+	}
+
+	/// Check `amount` against the limits of every scope of a payment and return the running
+	/// totals to store once the payment is recorded, or the scope and period it breaches.
+	fn check_limits(
+		scopes: &[LimitScopeOf<T>],
+		currency: CurrencyCode,
+		amount: BalanceOf<T>,
+	) -> Result<
+		Vec<(
+			(LimitScopeOf<T>, CurrencyCode),
+			LimitPeriod,
+			LimitUsageOf<T>,
+		)>,
+		(LimitScopeOf<T>, Option<LimitPeriod>),
+	> {
+		let now = <frame_system::Module<T>>::block_number();
+		let mut usages = Vec::new();
+
+		for scope in scopes {
+			let limit = match Self::limit(scope, currency) {
+				Some(limit) => limit,
+				None => continue,
+			};
+
+			if limit.max_single.map_or(false, |max| amount > max) {
+				return Err((scope.clone(), None));
+			}
+
+			let key = (scope.clone(), currency);
+			let periods = [
+				(LimitPeriod::Daily, limit.max_daily, T::DayLength::get()),
+				(
+					LimitPeriod::Monthly,
+					limit.max_monthly,
+					T::MonthLength::get(),
+				),
+			];
+			for &(period, max, length) in periods.iter() {
+				let max = match max {
+					Some(max) => max,
+					None => continue,
+				};
+
+				let mut usage = Self::limit_usage(&key, period);
+				if now >= usage.window_start.saturating_add(length) {
+					usage = LimitUsage {
+						window_start: now,
+						total: Zero::zero(),
+					};
+				}
+				usage.total = usage.total.saturating_add(amount);
+
+				if usage.total > max {
+					return Err((key.0, Some(period)));
+				}
+				usages.push((key.clone(), period, usage));
+			}
+		}
+
+		Ok(usages)
+	}
+
+	/// Take the amount of a payment that did not go through back out of the running totals it
+	/// was added to, unless their window has closed since.
+	fn release_limits(keys: &(T::AccountId, Vec<u8>), amount: BalanceOf<T>) {
+		for (key, period, window_start) in <LimitCharges<T>>::take(keys) {
+			<LimitUsages<T>>::mutate(key, period, |usage| {
+				if usage.window_start == window_start {
+					usage.total = usage.total.saturating_sub(amount);
+				}
+			});
+		}
+	}
+
+	/// Move the payment locked under `hashlock`, if any, to `state`.
+	fn resolve_htlc(hashlock: &HashLock, state: PaymentState) {
+		if let Some(keys) = Self::payment_htlc(hashlock) {
+			let now = <frame_system::Module<T>>::block_number();
+			<PaymentStatus<T>>::mutate(&keys, |maybe_status| {
+				if let Some(status) = maybe_status {
+					status.state = state;
+					status.updated_at = now;
+				}
+			});
+			if state == PaymentState::HtlcRefunded {
+				if let Some(settlement) = Self::settlement(&keys) {
+					Self::release_limits(&keys, settlement.source_amount);
+				}
+			}
+
+			Self::deposit_event(Event::PaymentHtlcResolved(keys.0, keys.1, state));
+		}
+	}
+
+	/// Settle the two legs of a payment against its quote. The source leg pays the payer PSP's
+	/// reserved funds to the FXP and the destination leg pays what the FXP reserved in
+	/// `accept_fx_leg` to the payee PSP. Either both legs are applied or neither is.
+	#[transactional]
+	fn settle_pvp(
+		source_psp: &T::AccountId,
+		dest_psp: &T::AccountId,
+		payment: &FinalPaymentStruct,
+		settlement: &SettlementStruct<T::AccountId, BalanceOf<T>>,
+	) -> DispatchResult {
+		ensure!(
+			!<PvpSettlements<T>>::contains_key((&settlement.fxp, &payment.quote_uuid)),
+			Error::<T>::QuoteAlreadySettled
+		);
+		ensure!(
+			<FxLegAcceptances<T>>::contains_key((source_psp, &payment.payment_uuid)),
+			Error::<T>::FxLegNotAccepted
+		);
+
+		let unpaid = T::Assets::repatriate_reserved(
+			settlement.source_currency,
+			source_psp,
+			&settlement.fxp,
+			settlement.source_amount,
+		)?;
+		ensure!(unpaid.is_zero(), Error::<T>::InsufficientReservedBalance);
+
+		let unpaid = T::Assets::repatriate_reserved(
+			settlement.destination_currency,
+			&settlement.fxp,
+			dest_psp,
+			settlement.destination_amount,
+		)?;
+		ensure!(unpaid.is_zero(), Error::<T>::InsufficientReservedBalance);
+
+		let record = PvpRecord {
+			source_psp: source_psp.clone(),
+			payment_uuid: payment.payment_uuid.clone(),
+			lp_source: payment.lp_source.clone(),
+			fxp: settlement.fxp.clone(),
+			settled_at: <frame_system::Module<T>>::block_number(),
+		};
+		<PvpSettlements<T>>::insert((&settlement.fxp, &payment.quote_uuid), record);
+
+		Self::deposit_event(Event::PvpSettled(
+			payment.quote_uuid.clone(),
+			payment.lp_source.clone(),
+			settlement.fxp.clone(),
+			settlement.source_amount,
+			settlement.destination_amount,
+		));
+
+		Ok(())
+	}
+
+	/// Give the FXP back what it reserved for the destination leg of a payment that will not
+	/// settle on this ledger, if it accepted the leg.
+	fn release_fx_leg(
+		keys: &(T::AccountId, Vec<u8>),
+		settlement: &SettlementStruct<T::AccountId, BalanceOf<T>>,
+	) {
+		if <FxLegAcceptances<T>>::take(keys).is_some() {
+			T::Assets::unreserve(
+				settlement.destination_currency,
+				&settlement.fxp,
+				settlement.destination_amount,
+			);
+		}
+	}
+
+	/// Fetch a payment that `dest_psp` may still accept or reject, together with its status.
+	fn pending_payment(
+		keys: &(T::AccountId, Vec<u8>),
+		dest_psp: &T::AccountId,
+	) -> Result<
+		(
+			SettlementStruct<T::AccountId, BalanceOf<T>>,
+			PaymentStatusInfo<T::BlockNumber>,
+		),
+		Error<T>,
+	> {
+		let settlement = Self::settlement(keys).ok_or(Error::<T>::PaymentNotFound)?;
+		ensure!(
+			&settlement.dest_psp == dest_psp,
+			Error::<T>::NotDestinationPsp
+		);
+
+		let status = Self::payment_status(keys).ok_or(Error::<T>::PaymentNotFound)?;
+		ensure!(
+			status.state == PaymentState::Pending,
+			Error::<T>::InvalidPaymentState
+		);
+
+		Ok((settlement, status))
+	}
+
+	/// Fetch a recall that `dest_psp` may still answer, together with the payment status.
+	fn pending_recall(
+		keys: &(T::AccountId, Vec<u8>),
+		dest_psp: &T::AccountId,
+	) -> Result<
+		(
+			RecallStruct<T::AccountId, T::BlockNumber>,
+			PaymentStatusInfo<T::BlockNumber>,
+		),
+		Error<T>,
+	> {
+		let recall = Self::recall(keys).ok_or(Error::<T>::NoRecall)?;
+		ensure!(&recall.dest_psp == dest_psp, Error::<T>::NotRecallAssignee);
+
+		let status = Self::payment_status(keys).ok_or(Error::<T>::PaymentNotFound)?;
+		ensure!(
+			status.state == PaymentState::RecallRequested,
+			Error::<T>::InvalidPaymentState
+		);
+
+		let now = <frame_system::Module<T>>::block_number();
+		ensure!(
+			now <= recall
+				.requested_at
+				.saturating_add(T::RecallResponseWindow::get()),
+			Error::<T>::RecallResponseExpired
+		);
+
+		Ok((recall, status))
+	}
+}
+
+impl<T: Config> OnHtlcResolved for Pallet<T> {
+	fn on_claimed(hashlock: &HashLock) {
+		Self::resolve_htlc(hashlock, PaymentState::HtlcClaimed);
+	}
+
+	fn on_refunded(hashlock: &HashLock) {
+		Self::resolve_htlc(hashlock, PaymentState::HtlcRefunded);
 	}
 }
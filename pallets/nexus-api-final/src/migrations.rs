@@ -0,0 +1,70 @@
+//! Storage migrations of the final payment pallet.
+
+use super::*;
+use frame_support::{
+	storage::{migration::storage_key_iter, StoragePrefixedMap},
+	traits::Get,
+	weights::Weight,
+	Blake2_128Concat,
+};
+use nexus_support::remove_storage_item;
+
+/// Remove the plaintext `DestBank` and `SourceBank` items written before only commitments of the
+/// bank data were stored, and return the weight of the keys actually removed.
+pub fn remove_plaintext_bank_data<T: Config>() -> Weight {
+	let pallet = FinalPayment::<T>::module_prefix();
+	let removed: u64 = [&b"DestBank"[..], &b"SourceBank"[..]]
+		.iter()
+		.map(|item| remove_storage_item(pallet, item))
+		.sum();
+
+	T::DbWeight::get().reads_writes(removed + 2, removed)
+}
+
+/// Key the payment-versus-payment settlements by FXP and quote instead of by quote alone, as
+/// quote ids are only unique per FXP. The FXP is taken from each record.
+pub fn key_pvp_settlements_by_fxp<T: Config>() -> Weight {
+	let records =
+		storage_key_iter::<Vec<u8>, PvpRecord<T::AccountId, T::BlockNumber>, Blake2_128Concat>(
+			PvpSettlements::<T>::module_prefix(),
+			PvpSettlements::<T>::storage_prefix(),
+		)
+		.drain()
+		.collect::<Vec<_>>();
+
+	let count = records.len() as Weight;
+	for (quote_uuid, record) in records {
+		<PvpSettlements<T>>::insert((record.fxp.clone(), quote_uuid), record);
+	}
+
+	T::DbWeight::get().reads_writes(count, count * 2 + 1)
+}
+
+/// Record the sender of every payment made before `PaymentOwners` existed, so that
+/// `payment_info` finds them. Should two senders have used the same payment id, the first one
+/// found keeps it.
+pub fn backfill_payment_owners<T: Config>() -> Weight {
+	let mut reads = 0;
+	let mut writes = 0;
+	for ((source_psp, payment_uuid), _) in <FinalPayment<T>>::iter() {
+		reads += 2;
+		if !<PaymentOwners<T>>::contains_key(&payment_uuid) {
+			<PaymentOwners<T>>::insert(&payment_uuid, source_psp);
+			writes += 1;
+		}
+	}
+
+	T::DbWeight::get().reads_writes(reads, writes + 1)
+}
+
+/// Index every payment recorded before `ReceivedPayments` existed by its destination PSP, so
+/// that `payments_by_psp` finds it.
+pub fn index_received_payments<T: Config>() -> Weight {
+	let mut count = 0;
+	for ((source_psp, payment_uuid), settlement) in <Settlements<T>>::iter() {
+		<ReceivedPayments<T>>::insert(&settlement.dest_psp, (source_psp, payment_uuid), ());
+		count += 1;
+	}
+
+	T::DbWeight::get().reads_writes(count, count + 1)
+}
@@ -0,0 +1,195 @@
+use crate as nexus_api_final;
+use frame_support::parameter_types;
+use frame_system::{EnsureRoot, EnsureSigned};
+use nexus_api_sld::MaxDestinationValue;
+use nexus_directory::ParticipantDirectory;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		NexusTokens: nexus_tokens::{Module, Call, Storage, Config<T>, Event<T>},
+		NexusHtlc: nexus_htlc::{Module, Call, Storage, Event<T>},
+		NexusApiQuote: nexus_api_quote::{Module, Call, Storage, Event<T>},
+		NexusFinal: nexus_api_final::{Module, Call, Storage, Config, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+impl nexus_tokens::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MinLockPeriod: u64 = 5;
+}
+
+impl nexus_htlc::Config for Test {
+	type Event = Event;
+	type Assets = NexusTokens;
+	type MinLockPeriod = MinLockPeriod;
+	type OnResolved = NexusFinal;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const QuoteLifetime: u64 = 10;
+}
+
+impl nexus_api_quote::Config for Test {
+	type Event = Event;
+	type FxpOrigin = EnsureSigned<u64>;
+	type SourcePspOrigin = EnsureSigned<u64>;
+	type QuoteLifetime = QuoteLifetime;
+	type MaxFieldLength = MaxFieldLength;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const DayLength: u64 = 10;
+	pub const MonthLength: u64 = 300;
+	pub const RecallWindow: u64 = 20;
+	pub const RecallResponseWindow: u64 = 20;
+	pub const ReturnWindow: u64 = 5;
+	pub const RetentionPeriod: u64 = 100;
+	pub const MaxErasuresPerBlock: u32 = 2;
+	pub const MaxBatchSize: u32 = 10;
+	pub const MaxFieldLength: u32 = 32;
+	pub const MaxEnvelopeLength: u32 = 256;
+	pub const MaxEnvelopeRecipients: u32 = 4;
+	pub const MaxRegulators: u32 = 2;
+}
+
+/// The operator of the IPS account 2 settles in.
+pub const DEST_IPS: u64 = 6;
+
+/// `SRCBANK` is the bank of account 1 and `DESTBANK` the bank of account 2, which settles in
+/// `DEST_IPS` in India.
+pub struct Directory;
+
+impl ParticipantDirectory<u64> for Directory {
+	fn is_bank_of(who: &u64, bank_id: &[u8]) -> bool {
+		match bank_id {
+			b"SRCBANK" => *who == 1,
+			b"DESTBANK" => *who == 2,
+			_ => false,
+		}
+	}
+
+	fn home_of(who: &u64) -> Option<(u64, Vec<u8>)> {
+		if *who == 2 {
+			Some((DEST_IPS, b"IN".to_vec()))
+		} else {
+			None
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn add_bank(_: &u64, _: &[u8]) {}
+}
+
+/// `DEST_IPS` lets a single payment credit at most 100_000 in India.
+pub struct Sld;
+
+impl MaxDestinationValue<u64> for Sld {
+	fn max_destination_value(ips: &u64, country_id: &[u8]) -> Option<u128> {
+		if *ips == DEST_IPS && country_id == b"IN" {
+			Some(100_000)
+		} else {
+			None
+		}
+	}
+}
+
+impl nexus_api_final::Config for Test {
+	type Event = Event;
+	type Assets = NexusTokens;
+	type Htlc = NexusHtlc;
+	type SourcePspOrigin = EnsureSigned<u64>;
+	type DestinationPspOrigin = EnsureSigned<u64>;
+	type Directory = Directory;
+	type Screening = ();
+	type ScreeningOrigin = EnsureRoot<u64>;
+	type Quotes = NexusApiQuote;
+	type Payees = ();
+	type Sld = Sld;
+	type LimitOrigin = EnsureRoot<u64>;
+	type DayLength = DayLength;
+	type MonthLength = MonthLength;
+	type RecallWindow = RecallWindow;
+	type RecallResponseWindow = RecallResponseWindow;
+	type ReturnWindow = ReturnWindow;
+	type RetentionPeriod = RetentionPeriod;
+	type MaxErasuresPerBlock = MaxErasuresPerBlock;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxFieldLength = MaxFieldLength;
+	type MaxEnvelopeLength = MaxEnvelopeLength;
+	type MaxEnvelopeRecipients = MaxEnvelopeRecipients;
+	type MaxRegulators = MaxRegulators;
+	type WeightInfo = ();
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap()
+		.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// The last event deposited by the final payment pallet.
+pub fn last_event() -> nexus_api_final::Event<Test> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::nexus_api_final(event) => Some(event),
+			_ => None,
+		})
+		.last()
+		.expect("an event was deposited")
+}
@@ -0,0 +1,1885 @@
+use crate::{
+	mock::*, BatchMode, ChargeBearer, CostDisclosure, Envelope, Error, Event, FeeSchedule,
+	FinalPaymentStruct, LimitPeriod, LimitScope, PaymentState, PaymentSubmission, PvpRecord,
+	RecallDeclineReason, RecallReason, Releases, RetryMode, ReturnReason, TransactionLimit,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::{get_storage_value, put_storage_value},
+	traits::{GenesisBuild, OnInitialize, OnRuntimeUpgrade},
+	Blake2_128Concat, StorageHasher,
+};
+use nexus_tokens::{CurrencyCode, MultiReservableCurrency};
+use sp_core::H256;
+use sp_runtime::{
+	traits::{BlakeTwo256, Hash},
+	DispatchError, Permill,
+};
+
+const SOURCE_PSP: u64 = 1;
+const DEST_PSP: u64 = 2;
+const FXP: u64 = 3;
+const ISSUER: u64 = 4;
+const REGULATOR: u64 = 5;
+const SGD: CurrencyCode = *b"SGD";
+const INR: CurrencyCode = *b"INR";
+const QUOTE_UUID: &[u8] = b"q-1";
+
+fn payment_key() -> Vec<u8> {
+	Blake2_128Concat::hash(&(SOURCE_PSP, b"p-1".to_vec()).encode())
+}
+
+fn payment(charge_bearer: ChargeBearer) -> FinalPaymentStruct {
+	FinalPaymentStruct {
+		message_id: b"m-1".to_vec(),
+		creation_time: b"2021-03-01T10:00:00Z".to_vec(),
+		settlement_amount: b"60500".to_vec(),
+		payment_uuid: b"p-1".to_vec(),
+		clearing_system_ref: b"IPS-1".to_vec(),
+		charge_bearer,
+		quote_uuid: b"q-1".to_vec(),
+		lp_source: b"LP-SG".to_vec(),
+	}
+}
+
+#[test]
+fn migration_translates_charge_bearer_codes() {
+	new_test_ext().execute_with(|| {
+		// A payment as stored before `ChargeBearer` existed, with no storage version written.
+		let legacy = (
+			b"m-1".to_vec(),
+			b"2021-03-01T10:00:00Z".to_vec(),
+			b"60500".to_vec(),
+			b"p-1".to_vec(),
+			b"IPS-1".to_vec(),
+			b"DEBT".to_vec(),
+			b"q-1".to_vec(),
+			b"LP-SG".to_vec(),
+		);
+		put_storage_value(b"NexusFinal", b"FinalPayment", &payment_key(), legacy);
+		put_storage_value(
+			b"NexusFinal",
+			b"PaymentOwners",
+			&Blake2_128Concat::hash(&b"p-1".to_vec().encode()),
+			SOURCE_PSP,
+		);
+
+		NexusFinal::on_runtime_upgrade();
+
+		assert_eq!(
+			NexusFinal::final_payment((SOURCE_PSP, b"p-1".to_vec())),
+			payment(ChargeBearer::Debtor)
+		);
+		assert_eq!(NexusFinal::payment_owner(b"p-1".to_vec()), Some(SOURCE_PSP));
+		assert_eq!(
+			crate::StorageVersion::<Test>::get(),
+			Releases::V5ReceivedPayments
+		);
+	});
+}
+
+#[test]
+fn migration_keeps_payments_already_in_the_current_format() {
+	new_test_ext().execute_with(|| {
+		put_storage_value(
+			b"NexusFinal",
+			b"FinalPayment",
+			&payment_key(),
+			payment(ChargeBearer::Creditor),
+		);
+		put_storage_value(
+			b"NexusFinal",
+			b"StorageVersion",
+			&[],
+			Releases::V2ChargeBearer,
+		);
+
+		NexusFinal::on_runtime_upgrade();
+		NexusFinal::on_runtime_upgrade();
+
+		assert_eq!(
+			NexusFinal::final_payment((SOURCE_PSP, b"p-1".to_vec())),
+			payment(ChargeBearer::Creditor)
+		);
+	});
+}
+
+#[test]
+fn migration_removes_plaintext_bank_data_once() {
+	new_test_ext().execute_with(|| {
+		let bank_data = || (b"BANK".to_vec(), b"Jane Doe".to_vec());
+		let stored = |item: &[u8]| {
+			get_storage_value::<(Vec<u8>, Vec<u8>)>(b"NexusFinal", item, &payment_key())
+		};
+		for item in [&b"DestBank"[..], &b"SourceBank"[..]].iter() {
+			put_storage_value(b"NexusFinal", item, &payment_key(), bank_data());
+		}
+
+		NexusFinal::on_runtime_upgrade();
+		assert_eq!(stored(b"DestBank"), None);
+		assert_eq!(stored(b"SourceBank"), None);
+
+		// Later upgrades leave the prefix alone.
+		put_storage_value(b"NexusFinal", b"DestBank", &payment_key(), bank_data());
+		NexusFinal::on_runtime_upgrade();
+		assert_eq!(stored(b"DestBank"), Some(bank_data()));
+	});
+}
+
+#[test]
+fn migration_keys_pvp_settlements_by_fxp() {
+	new_test_ext().execute_with(|| {
+		let record = PvpRecord {
+			source_psp: SOURCE_PSP,
+			payment_uuid: b"p-1".to_vec(),
+			lp_source: b"LP-SG".to_vec(),
+			fxp: FXP,
+			settled_at: 1,
+		};
+		put_storage_value(
+			b"NexusFinal",
+			b"PvpSettlements",
+			&Blake2_128Concat::hash(&QUOTE_UUID.to_vec().encode()),
+			record.clone(),
+		);
+		crate::StorageVersion::<Test>::put(Releases::V2ChargeBearer);
+
+		NexusFinal::on_runtime_upgrade();
+
+		assert_eq!(
+			NexusFinal::pvp_settlement((FXP, QUOTE_UUID.to_vec())),
+			Some(record)
+		);
+		assert_eq!(crate::PvpSettlements::<Test>::iter().count(), 1);
+		assert_eq!(
+			crate::StorageVersion::<Test>::get(),
+			Releases::V5ReceivedPayments
+		);
+	});
+}
+
+#[test]
+fn migration_backfills_payment_owners() {
+	new_test_ext().execute_with(|| {
+		crate::FinalPayment::<Test>::insert(
+			(SOURCE_PSP, b"p-1".to_vec()),
+			payment(ChargeBearer::Shared),
+		);
+		crate::StorageVersion::<Test>::put(Releases::V3PvpByFxp);
+		assert!(NexusFinal::payment_info(b"p-1".to_vec()).is_none());
+
+		NexusFinal::on_runtime_upgrade();
+
+		assert_eq!(NexusFinal::payment_owner(b"p-1".to_vec()), Some(SOURCE_PSP));
+		assert_eq!(
+			NexusFinal::payment_info(b"p-1".to_vec()).map(|info| info.source_psp),
+			Some(SOURCE_PSP)
+		);
+		assert_eq!(
+			crate::StorageVersion::<Test>::get(),
+			Releases::V5ReceivedPayments
+		);
+	});
+}
+
+#[test]
+fn migration_indexes_received_payments() {
+	new_test_ext().execute_with(|| {
+		settle();
+		crate::ReceivedPayments::<Test>::remove(DEST_PSP, (SOURCE_PSP, b"p-1".to_vec()));
+		crate::StorageVersion::<Test>::put(Releases::V4PaymentOwners);
+		assert!(NexusFinal::payments_by_psp(&DEST_PSP).is_empty());
+
+		NexusFinal::on_runtime_upgrade();
+
+		assert_eq!(
+			NexusFinal::payments_by_psp(&DEST_PSP),
+			vec![b"p-1".to_vec()]
+		);
+		assert_eq!(
+			crate::StorageVersion::<Test>::get(),
+			Releases::V5ReceivedPayments
+		);
+	});
+}
+
+#[test]
+fn a_new_chain_is_not_migrated() {
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	GenesisBuild::<Test>::assimilate_storage(&crate::GenesisConfig::default(), &mut storage)
+		.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| {
+		crate::FinalPayment::<Test>::insert(
+			(SOURCE_PSP, b"p-1".to_vec()),
+			payment(ChargeBearer::Debtor),
+		);
+
+		NexusFinal::on_runtime_upgrade();
+
+		assert_eq!(
+			NexusFinal::final_payment((SOURCE_PSP, b"p-1".to_vec())),
+			payment(ChargeBearer::Debtor)
+		);
+		assert_eq!(
+			crate::StorageVersion::<Test>::get(),
+			Releases::V5ReceivedPayments
+		);
+	});
+}
+
+fn hash(data: &[u8]) -> H256 {
+	BlakeTwo256::hash(data)
+}
+
+fn free(currency: CurrencyCode, who: u64) -> u64 {
+	<NexusTokens as MultiReservableCurrency<u64>>::free_balance(currency, &who)
+}
+
+fn reserved(currency: CurrencyCode, who: u64) -> u64 {
+	<NexusTokens as MultiReservableCurrency<u64>>::reserved_balance(currency, &who)
+}
+
+/// Fund the source PSP with SGD and the FXP with INR, and lock a quote of 60.5 INR per SGD for
+/// each of payments `p-1` to `p-3` for the source PSP.
+fn prepare() {
+	for &(currency, who, amount) in [(SGD, SOURCE_PSP, 5_000), (INR, FXP, 302_500)].iter() {
+		assert_ok!(NexusTokens::create_asset(
+			Origin::root(),
+			currency,
+			ISSUER,
+			2
+		));
+		assert_ok!(NexusTokens::mint(
+			Origin::signed(ISSUER),
+			currency,
+			who,
+			amount
+		));
+	}
+
+	for uuid in [b"p-1", b"p-2", b"p-3"].iter() {
+		quote(&quote_uuid(*uuid));
+	}
+}
+
+/// The uuid of the quote locked for payment `uuid`.
+fn quote_uuid(uuid: &[u8]) -> Vec<u8> {
+	[b"q-", uuid].concat()
+}
+
+/// Have the FXP quote 60.5 INR per SGD under `quote_uuid` and lock the quote for the source PSP.
+fn quote(quote_uuid: &[u8]) {
+	assert_ok!(NexusApiQuote::provide_rate(
+		Origin::signed(FXP),
+		b"SGD".to_vec(),
+		b"INR".to_vec(),
+		quote_uuid.to_vec(),
+		b"fxp".to_vec(),
+		b"LP-SG".to_vec(),
+		b"LP-IN".to_vec(),
+		b"60.5".to_vec(),
+		true,
+		b"0".to_vec(),
+		b"SRCBANK".to_vec()
+	));
+	assert_ok!(NexusApiQuote::lock_quote(
+		Origin::signed(SOURCE_PSP),
+		FXP,
+		quote_uuid.to_vec()
+	));
+}
+
+/// Payment `uuid` of 1000 SGD cents, which credits 60500 INR paise under its own quote.
+fn submission(uuid: &[u8]) -> PaymentSubmission<u64, u64, H256> {
+	PaymentSubmission {
+		source_bank_id: b"SRCBANK".to_vec(),
+		source_party_hash: hash(b"debtor"),
+		dest_bank_id: b"DESTBANK".to_vec(),
+		dest_party_hash: hash(b"creditor"),
+		message_id: [b"m-", uuid].concat(),
+		creation_time: b"2021-03-01T10:00:00Z".to_vec(),
+		settlement_amount: b"60500".to_vec(),
+		payment_uuid: uuid.to_vec(),
+		clearing_system_ref: b"IPS-1".to_vec(),
+		charge_bearer: ChargeBearer::Shared,
+		quote_uuid: quote_uuid(uuid),
+		lp_source: b"LP-SG".to_vec(),
+		dest_psp: DEST_PSP,
+		fxp: FXP,
+		source_currency: SGD,
+		source_amount: 1_000,
+		destination_currency: INR,
+		destination_amount: 60_500,
+		debtor_id_hash: hash(b"debtor id"),
+	}
+}
+
+/// Have the destination PSP attest the creditor of payment `uuid` at its bank.
+fn attest(uuid: &[u8]) {
+	assert_ok!(NexusFinal::attest_creditor(
+		Origin::signed(DEST_PSP),
+		SOURCE_PSP,
+		uuid.to_vec(),
+		b"DESTBANK".to_vec(),
+		hash(b"creditor id")
+	));
+}
+
+/// Send `payment` with `final_payment_func`, after giving its banks and having its creditor
+/// attested.
+fn send(payment: PaymentSubmission<u64, u64, H256>) -> Result<(), DispatchError> {
+	assert_ok!(NexusFinal::set_source_bank_data(
+		Origin::signed(SOURCE_PSP),
+		payment.payment_uuid.clone(),
+		payment.source_bank_id,
+		payment.source_party_hash,
+		payment.debtor_id_hash
+	));
+	attest(&payment.payment_uuid);
+	assert_ok!(NexusFinal::set_dest_bank_data(
+		Origin::signed(SOURCE_PSP),
+		payment.payment_uuid.clone(),
+		payment.dest_bank_id,
+		payment.dest_party_hash
+	));
+	NexusFinal::final_payment_func(
+		Origin::signed(SOURCE_PSP),
+		payment.message_id,
+		payment.creation_time,
+		payment.settlement_amount,
+		payment.payment_uuid,
+		payment.clearing_system_ref,
+		payment.charge_bearer,
+		payment.quote_uuid,
+		payment.lp_source,
+		payment.dest_psp,
+		payment.fxp,
+		payment.source_currency,
+		payment.source_amount,
+		payment.destination_currency,
+		payment.destination_amount,
+	)
+	.map(|_| ())
+	.map_err(|e| e.error)
+}
+
+/// Whether the pallet deposited `event`.
+fn deposited(event: Event<Test>) -> bool {
+	System::events()
+		.iter()
+		.any(|record| record.event == crate::mock::Event::nexus_api_final(event.clone()))
+}
+
+fn state(uuid: &[u8]) -> Option<PaymentState> {
+	NexusFinal::payment_status((SOURCE_PSP, uuid.to_vec())).map(|status| status.state)
+}
+
+/// Have the FXP accept the FX leg of payment `uuid`.
+fn accept_fx_leg(uuid: &[u8]) {
+	assert_ok!(NexusFinal::accept_fx_leg(
+		Origin::signed(FXP),
+		SOURCE_PSP,
+		uuid.to_vec()
+	));
+}
+
+/// Send payment `p-1` and have the FXP and the destination PSP accept it.
+fn settle() {
+	prepare();
+	assert_ok!(send(submission(b"p-1")));
+	accept_fx_leg(b"p-1");
+	assert_ok!(NexusFinal::accept_payment(
+		Origin::signed(DEST_PSP),
+		SOURCE_PSP,
+		b"p-1".to_vec()
+	));
+}
+
+/// Settle payment `p-1` and recall it.
+fn recall() {
+	settle();
+	assert_ok!(NexusFinal::request_recall(
+		Origin::signed(SOURCE_PSP),
+		b"p-1".to_vec(),
+		RecallReason::Fraud
+	));
+}
+
+fn envelope(recipients: &[[u8; 32]]) -> Envelope<H256> {
+	Envelope {
+		ephemeral_public: [9; 32],
+		nonce: [0; 12],
+		ciphertext: b"ciphertext".to_vec(),
+		recipients: recipients
+			.iter()
+			.map(|key| (*key, b"wrapped".to_vec()))
+			.collect(),
+		payload_hash: hash(b"payload"),
+	}
+}
+
+#[test]
+fn set_status_stores_the_status() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusFinal::set_status(
+			Origin::signed(DEST_PSP),
+			b"ACCC".to_vec(),
+			b"settled".to_vec()
+		));
+
+		assert_eq!(
+			last_event(),
+			Event::StatusSet(DEST_PSP, b"ACCC".to_vec(), b"settled".to_vec())
+		);
+		assert_noop!(
+			NexusFinal::set_status(Origin::signed(DEST_PSP), vec![b'x'; 33], Vec::new()),
+			Error::<Test>::FieldTooLong
+		);
+	});
+}
+
+#[test]
+fn bank_data_is_stored_as_commitments() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusFinal::set_source_bank_data(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec(),
+			b"SRCBANK".to_vec(),
+			hash(b"debtor"),
+			hash(b"debtor id")
+		));
+		assert_eq!(
+			last_event(),
+			Event::SourceBankSet(SOURCE_PSP, b"p-1".to_vec(), hash(b"debtor"))
+		);
+		assert_ok!(NexusFinal::set_dest_bank_data(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec(),
+			b"DESTBANK".to_vec(),
+			hash(b"creditor")
+		));
+		assert_eq!(
+			last_event(),
+			Event::DestBankSet(SOURCE_PSP, b"p-1".to_vec(), hash(b"creditor"))
+		);
+
+		let keys = (SOURCE_PSP, b"p-1".to_vec());
+		assert_eq!(
+			NexusFinal::source_bank(&keys).source_party_hash,
+			hash(b"debtor")
+		);
+		assert_eq!(
+			NexusFinal::dest_bank(&keys).dest_bank_id,
+			b"DESTBANK".to_vec()
+		);
+		assert_eq!(
+			NexusFinal::erasure_due(&keys),
+			Some(1 + RetentionPeriod::get())
+		);
+	});
+}
+
+#[test]
+fn final_payment_func_reserves_the_source_amount() {
+	new_test_ext().execute_with(|| {
+		prepare();
+
+		assert_ok!(send(submission(b"p-1")));
+
+		assert_eq!(free(SGD, SOURCE_PSP), 4_000);
+		assert_eq!(reserved(SGD, SOURCE_PSP), 1_000);
+		assert_eq!(state(b"p-1"), Some(PaymentState::Pending));
+		assert_eq!(NexusFinal::payment_owner(b"p-1".to_vec()), Some(SOURCE_PSP));
+		assert_eq!(
+			NexusFinal::message_payment(SOURCE_PSP, b"m-p-1".to_vec()),
+			Some(b"p-1".to_vec())
+		);
+		assert_eq!(
+			last_event(),
+			Event::FinalPaymentSet(SOURCE_PSP, b"p-1".to_vec())
+		);
+	});
+}
+
+#[test]
+fn final_payment_func_checks_the_quote() {
+	new_test_ext().execute_with(|| {
+		prepare();
+
+		let mut payment = submission(b"p-1");
+		payment.quote_uuid = b"q-2".to_vec();
+		assert_eq!(send(payment), Err(Error::<Test>::QuoteNotFound.into()));
+
+		let mut payment = submission(b"p-1");
+		payment.fxp = DEST_PSP;
+		assert_eq!(send(payment), Err(Error::<Test>::QuoteMismatch.into()));
+
+		let mut payment = submission(b"p-1");
+		payment.destination_amount = 60_501;
+		assert_eq!(
+			send(payment),
+			Err(Error::<Test>::SettlementAmountMismatch.into())
+		);
+
+		let mut payment = submission(b"p-1");
+		payment.settlement_amount = b"60000".to_vec();
+		assert_eq!(
+			send(payment),
+			Err(Error::<Test>::SettlementAmountMismatch.into())
+		);
+	});
+}
+
+#[test]
+fn final_payment_func_fails_once_the_quote_expires() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		System::set_block_number(1 + QuoteLifetime::get());
+
+		assert_eq!(
+			send(submission(b"p-1")),
+			Err(Error::<Test>::QuoteExpired.into())
+		);
+	});
+}
+
+#[test]
+fn final_payment_func_needs_the_destination_bank() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		let payment = submission(b"p-1");
+
+		assert_noop!(
+			NexusFinal::final_payment_func(
+				Origin::signed(SOURCE_PSP),
+				payment.message_id,
+				payment.creation_time,
+				payment.settlement_amount,
+				payment.payment_uuid,
+				payment.clearing_system_ref,
+				payment.charge_bearer,
+				payment.quote_uuid,
+				payment.lp_source,
+				payment.dest_psp,
+				payment.fxp,
+				payment.source_currency,
+				payment.source_amount,
+				payment.destination_currency,
+				payment.destination_amount,
+			),
+			Error::<Test>::PayeeNotConfirmed
+		);
+	});
+}
+
+#[test]
+fn final_payment_func_needs_a_bank_of_the_destination_psp() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		let payment = PaymentSubmission {
+			dest_bank_id: b"SRCBANK".to_vec(),
+			..submission(b"p-1")
+		};
+
+		assert_eq!(send(payment), Err(Error::<Test>::NotDestinationBank.into()));
+		assert_eq!(state(b"p-1"), None);
+	});
+}
+
+#[test]
+fn final_payment_func_needs_the_creditor_attested() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		let payment = submission(b"p-1");
+		assert_ok!(NexusFinal::attest_creditor(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-2".to_vec(),
+			b"DESTBANK".to_vec(),
+			hash(b"creditor id")
+		));
+		assert_ok!(NexusFinal::submit_payments(
+			Origin::signed(SOURCE_PSP),
+			vec![payment],
+			BatchMode::BestEffort
+		));
+
+		assert_eq!(state(b"p-1"), None);
+		assert!(System::events().iter().any(|record| record.event
+			== crate::mock::Event::nexus_api_final(Event::BatchItemFailed(
+				SOURCE_PSP,
+				0,
+				b"p-1".to_vec(),
+				Error::<Test>::CreditorNotAttested.into()
+			))));
+	});
+}
+
+#[test]
+fn attest_creditor_needs_a_bank_of_the_caller() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusFinal::attest_creditor(
+				Origin::signed(SOURCE_PSP),
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				b"DESTBANK".to_vec(),
+				hash(b"creditor id")
+			),
+			Error::<Test>::NotDestinationBank
+		);
+
+		attest(b"p-1");
+		assert_eq!(
+			last_event(),
+			Event::CreditorAttested(SOURCE_PSP, b"p-1".to_vec(), DEST_PSP)
+		);
+		assert_eq!(
+			NexusFinal::creditor_attestation((SOURCE_PSP, b"p-1".to_vec()))
+				.map(|attestation| attestation.creditor_id_hash),
+			Some(hash(b"creditor id"))
+		);
+
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		assert_noop!(
+			NexusFinal::attest_creditor(
+				Origin::signed(DEST_PSP),
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				b"DESTBANK".to_vec(),
+				hash(b"other creditor")
+			),
+			Error::<Test>::DuplicatePayment
+		);
+	});
+}
+
+#[test]
+fn source_bank_data_needs_a_bank_of_the_sender() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusFinal::set_source_bank_data(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				b"DESTBANK".to_vec(),
+				hash(b"debtor"),
+				hash(b"debtor id")
+			),
+			Error::<Test>::NotSenderBank
+		);
+		assert_noop!(
+			NexusFinal::set_source_bank_data(
+				Origin::signed(DEST_PSP),
+				b"p-1".to_vec(),
+				b"SRCBANK".to_vec(),
+				hash(b"debtor"),
+				hash(b"debtor id")
+			),
+			Error::<Test>::NotSenderBank
+		);
+	});
+}
+
+#[test]
+fn resubmissions_fail_in_strict_mode() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+
+		assert_eq!(
+			send(submission(b"p-1")),
+			Err(Error::<Test>::DuplicatePayment.into())
+		);
+
+		// A new payment id may not reuse the message id either.
+		let mut payment = submission(b"p-2");
+		payment.message_id = b"m-p-1".to_vec();
+		assert_eq!(send(payment), Err(Error::<Test>::DuplicatePayment.into()));
+	});
+}
+
+#[test]
+fn identical_resubmissions_are_ignored_in_idempotent_mode() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(NexusFinal::set_retry_mode(
+			Origin::signed(SOURCE_PSP),
+			RetryMode::Idempotent
+		));
+		assert_eq!(
+			last_event(),
+			Event::RetryModeSet(SOURCE_PSP, RetryMode::Idempotent)
+		);
+		assert_ok!(send(submission(b"p-1")));
+
+		assert_ok!(send(submission(b"p-1")));
+		assert_eq!(
+			last_event(),
+			Event::DuplicatePaymentIgnored(SOURCE_PSP, b"p-1".to_vec(), PaymentState::Pending)
+		);
+		assert_eq!(reserved(SGD, SOURCE_PSP), 1_000);
+
+		let mut payment = submission(b"p-1");
+		payment.creation_time = b"2021-03-01T10:00:01Z".to_vec();
+		assert_eq!(send(payment), Err(Error::<Test>::DuplicatePayment.into()));
+	});
+}
+
+#[test]
+fn submit_payments_records_every_payment() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		attest(b"p-1");
+		attest(b"p-2");
+
+		assert_ok!(NexusFinal::submit_payments(
+			Origin::signed(SOURCE_PSP),
+			vec![submission(b"p-1"), submission(b"p-2")],
+			BatchMode::AllOrNothing
+		));
+
+		assert_eq!(state(b"p-1"), Some(PaymentState::Pending));
+		assert_eq!(state(b"p-2"), Some(PaymentState::Pending));
+		assert_eq!(reserved(SGD, SOURCE_PSP), 2_000);
+		assert_eq!(last_event(), Event::BatchSubmitted(SOURCE_PSP, 2, 0));
+	});
+}
+
+#[test]
+fn submit_payments_all_or_nothing_fails_as_a_whole() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		attest(b"p-1");
+		let mut bad = submission(b"p-2");
+		bad.destination_amount = 1;
+
+		assert_noop!(
+			NexusFinal::submit_payments(
+				Origin::signed(SOURCE_PSP),
+				vec![submission(b"p-1"), bad],
+				BatchMode::AllOrNothing
+			),
+			Error::<Test>::SettlementAmountMismatch
+		);
+		assert_eq!(state(b"p-1"), None);
+	});
+}
+
+#[test]
+fn submit_payments_best_effort_leaves_out_failed_payments() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		attest(b"p-1");
+		attest(b"p-3");
+		let mut bad = submission(b"p-2");
+		bad.destination_amount = 1;
+
+		assert_ok!(NexusFinal::submit_payments(
+			Origin::signed(SOURCE_PSP),
+			vec![submission(b"p-1"), bad, submission(b"p-3")],
+			BatchMode::BestEffort
+		));
+
+		assert_eq!(state(b"p-1"), Some(PaymentState::Pending));
+		assert_eq!(state(b"p-2"), None);
+		assert!(!crate::DestBankCommitment::<Test>::contains_key((
+			SOURCE_PSP,
+			b"p-2".to_vec()
+		)));
+		assert_eq!(state(b"p-3"), Some(PaymentState::Pending));
+		assert!(System::events().iter().any(|record| record.event
+			== crate::mock::Event::nexus_api_final(Event::BatchItemFailed(
+				SOURCE_PSP,
+				1,
+				b"p-2".to_vec(),
+				Error::<Test>::SettlementAmountMismatch.into()
+			))));
+		assert_eq!(last_event(), Event::BatchSubmitted(SOURCE_PSP, 2, 1));
+	});
+}
+
+#[test]
+fn submit_payments_rejects_large_batches() {
+	new_test_ext().execute_with(|| {
+		let payments = (0..=MaxBatchSize::get())
+			.map(|i| submission(&[b'p', i as u8]))
+			.collect();
+
+		assert_noop!(
+			NexusFinal::submit_payments(
+				Origin::signed(SOURCE_PSP),
+				payments,
+				BatchMode::BestEffort
+			),
+			Error::<Test>::BatchTooLarge
+		);
+	});
+}
+
+#[test]
+fn set_limit_needs_the_limit_origin() {
+	new_test_ext().execute_with(|| {
+		let scope = LimitScope::Psp(SOURCE_PSP);
+
+		assert_noop!(
+			NexusFinal::set_limit(
+				Origin::signed(SOURCE_PSP),
+				scope.clone(),
+				SGD,
+				Some(Default::default())
+			),
+			DispatchError::BadOrigin
+		);
+
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			scope.clone(),
+			SGD,
+			Some(Default::default())
+		));
+		assert_eq!(NexusFinal::limit(&scope, SGD), Some(Default::default()));
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			scope.clone(),
+			SGD,
+			None
+		));
+		assert_eq!(NexusFinal::limit(&scope, SGD), None);
+		assert_eq!(last_event(), Event::LimitSet(scope, SGD, None));
+	});
+}
+
+#[test]
+fn payments_above_the_single_limit_are_refused() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		let scope = LimitScope::Corridor(SGD, INR);
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			scope.clone(),
+			SGD,
+			Some(TransactionLimit {
+				max_single: Some(999),
+				..Default::default()
+			})
+		));
+
+		assert_eq!(
+			send(submission(b"p-1")),
+			Err(Error::<Test>::TransactionLimitExceeded.into())
+		);
+		assert_eq!(state(b"p-1"), None);
+		assert!(deposited(Event::TransactionLimitExceeded(
+			SOURCE_PSP,
+			b"p-1".to_vec(),
+			scope.clone(),
+			None
+		)));
+		assert_eq!(reserved(SGD, SOURCE_PSP), 0);
+
+		// The refused payment leaves its uuid and message id free.
+		assert_ok!(NexusFinal::set_limit(Origin::root(), scope, SGD, None));
+		assert_ok!(send(submission(b"p-1")));
+		assert_eq!(state(b"p-1"), Some(PaymentState::Pending));
+	});
+}
+
+#[test]
+fn daily_limits_reset_with_the_window() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		let scope = LimitScope::Debtor(hash(b"debtor id"));
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			scope.clone(),
+			SGD,
+			Some(TransactionLimit {
+				max_daily: Some(1_500),
+				..Default::default()
+			})
+		));
+		assert_ok!(send(submission(b"p-1")));
+
+		assert_eq!(
+			send(submission(b"p-2")),
+			Err(Error::<Test>::TransactionLimitExceeded.into())
+		);
+		assert!(deposited(Event::TransactionLimitExceeded(
+			SOURCE_PSP,
+			b"p-2".to_vec(),
+			scope,
+			Some(LimitPeriod::Daily)
+		)));
+
+		System::set_block_number(1 + DayLength::get());
+		quote(b"q-2");
+		let mut payment = submission(b"p-3");
+		payment.quote_uuid = b"q-2".to_vec();
+		assert_ok!(send(payment));
+	});
+}
+
+#[test]
+fn limit_breaches_fail_batched_payments() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			LimitScope::Psp(SOURCE_PSP),
+			SGD,
+			Some(TransactionLimit {
+				max_single: Some(999),
+				..Default::default()
+			})
+		));
+		attest(b"p-1");
+
+		assert_noop!(
+			NexusFinal::submit_payments(
+				Origin::signed(SOURCE_PSP),
+				vec![submission(b"p-1")],
+				BatchMode::AllOrNothing
+			),
+			Error::<Test>::TransactionLimitExceeded
+		);
+
+		assert_ok!(NexusFinal::submit_payments(
+			Origin::signed(SOURCE_PSP),
+			vec![submission(b"p-1")],
+			BatchMode::BestEffort
+		));
+		assert_eq!(state(b"p-1"), None);
+		assert!(deposited(Event::BatchItemFailed(
+			SOURCE_PSP,
+			0,
+			b"p-1".to_vec(),
+			Error::<Test>::TransactionLimitExceeded.into()
+		)));
+		assert_eq!(last_event(), Event::BatchSubmitted(SOURCE_PSP, 0, 1));
+	});
+}
+
+#[test]
+fn debtors_without_limits_share_the_limit_of_unlisted_debtors() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			LimitScope::Debtor(hash(b"debtor id")),
+			SGD,
+			Some(TransactionLimit {
+				max_daily: Some(1_000),
+				..Default::default()
+			})
+		));
+		let scope = LimitScope::UnlistedDebtors(SOURCE_PSP);
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			scope.clone(),
+			SGD,
+			Some(TransactionLimit {
+				max_daily: Some(1_500),
+				..Default::default()
+			})
+		));
+		assert_ok!(send(submission(b"p-1")));
+
+		let mut payment = submission(b"p-2");
+		payment.debtor_id_hash = hash(b"another debtor id");
+		assert_ok!(send(payment));
+		assert_eq!(state(b"p-2"), Some(PaymentState::Pending));
+
+		let mut payment = submission(b"p-3");
+		payment.debtor_id_hash = hash(b"yet another debtor id");
+		assert_eq!(
+			send(payment),
+			Err(Error::<Test>::TransactionLimitExceeded.into())
+		);
+		assert_eq!(state(b"p-3"), None);
+		assert!(deposited(Event::TransactionLimitExceeded(
+			SOURCE_PSP,
+			b"p-3".to_vec(),
+			scope,
+			Some(LimitPeriod::Daily)
+		)));
+	});
+}
+
+#[test]
+fn rejected_payments_are_taken_out_of_the_limits() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		let scope = LimitScope::Psp(SOURCE_PSP);
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			scope.clone(),
+			SGD,
+			Some(TransactionLimit {
+				max_daily: Some(1_500),
+				..Default::default()
+			})
+		));
+		assert_ok!(send(submission(b"p-1")));
+		assert_eq!(
+			NexusFinal::limit_usage((scope.clone(), SGD), LimitPeriod::Daily).total,
+			1_000
+		);
+
+		assert_ok!(NexusFinal::reject_payment(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec(),
+			b"AC04".to_vec()
+		));
+		assert_eq!(
+			NexusFinal::limit_usage((scope, SGD), LimitPeriod::Daily).total,
+			0
+		);
+
+		assert_ok!(send(submission(b"p-2")));
+		assert_eq!(state(b"p-2"), Some(PaymentState::Pending));
+	});
+}
+
+#[test]
+fn payments_above_the_destination_sld_maximum_are_refused() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		let payment = PaymentSubmission {
+			settlement_amount: b"121000".to_vec(),
+			source_amount: 2_000,
+			destination_amount: 121_000,
+			..submission(b"p-1")
+		};
+
+		assert_eq!(
+			send(payment),
+			Err(Error::<Test>::DestinationValueExceeded.into())
+		);
+		assert_eq!(state(b"p-1"), None);
+		assert!(deposited(Event::DestinationValueExceeded(
+			SOURCE_PSP,
+			b"p-1".to_vec(),
+			100_000
+		)));
+		assert_eq!(reserved(SGD, SOURCE_PSP), 0);
+	});
+}
+
+#[test]
+fn fees_are_disclosed_by_charge_bearer() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(NexusFinal::set_fee_schedule(
+			Origin::signed(SOURCE_PSP),
+			SGD,
+			Some(FeeSchedule {
+				fixed: 10,
+				percentage: Permill::zero(),
+			})
+		));
+		assert_ok!(NexusFinal::set_fee_schedule(
+			Origin::signed(DEST_PSP),
+			INR,
+			Some(FeeSchedule {
+				fixed: 0,
+				percentage: Permill::from_percent(1),
+			})
+		));
+
+		let mut payment = submission(b"p-1");
+		payment.charge_bearer = ChargeBearer::Debtor;
+		assert_ok!(send(payment));
+		assert_eq!(
+			NexusFinal::payment_costs((SOURCE_PSP, b"p-1".to_vec())),
+			Some(CostDisclosure {
+				source_debit: 1_020,
+				source_psp_fee: 10,
+				fxp_fee: 0,
+				destination_psp_fee: 605,
+				destination_credit: 60_500,
+			})
+		);
+
+		assert_ok!(send(submission(b"p-2")));
+		assert_eq!(
+			NexusFinal::payment_costs((SOURCE_PSP, b"p-2".to_vec())),
+			Some(CostDisclosure {
+				source_debit: 1_010,
+				source_psp_fee: 10,
+				fxp_fee: 0,
+				destination_psp_fee: 605,
+				destination_credit: 59_895,
+			})
+		);
+
+		// The reserve is the source amount whoever bears the fees.
+		assert_eq!(reserved(SGD, SOURCE_PSP), 2_000);
+	});
+}
+
+#[test]
+fn accept_fx_leg_reserves_the_destination_amount() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+
+		assert_noop!(
+			NexusFinal::accept_fx_leg(Origin::signed(DEST_PSP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::NotPaymentFxp
+		);
+		assert_noop!(
+			NexusFinal::accept_fx_leg(Origin::signed(FXP), SOURCE_PSP, b"p-2".to_vec()),
+			Error::<Test>::PaymentNotFound
+		);
+		accept_fx_leg(b"p-1");
+
+		assert_eq!(reserved(INR, FXP), 60_500);
+		assert_eq!(
+			last_event(),
+			Event::FxLegAccepted(SOURCE_PSP, b"p-1".to_vec(), FXP, 60_500)
+		);
+		assert_noop!(
+			NexusFinal::accept_fx_leg(Origin::signed(FXP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::FxLegAlreadyAccepted
+		);
+	});
+}
+
+#[test]
+fn accept_payment_needs_the_fxp_to_accept_first() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+
+		assert_noop!(
+			NexusFinal::accept_payment(Origin::signed(DEST_PSP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::FxLegNotAccepted
+		);
+		assert_eq!(free(INR, FXP), 302_500);
+	});
+}
+
+#[test]
+fn accept_payment_settles_both_legs() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		accept_fx_leg(b"p-1");
+
+		assert_noop!(
+			NexusFinal::accept_payment(Origin::signed(FXP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::NotDestinationPsp
+		);
+		assert_ok!(NexusFinal::accept_payment(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec()
+		));
+
+		assert_eq!(reserved(SGD, SOURCE_PSP), 0);
+		assert_eq!(free(SGD, FXP), 1_000);
+		assert_eq!(free(INR, FXP), 242_000);
+		assert_eq!(reserved(INR, FXP), 0);
+		assert_eq!(free(INR, DEST_PSP), 60_500);
+		assert_eq!(state(b"p-1"), Some(PaymentState::Accepted));
+		assert!(NexusFinal::pvp_settlement((FXP, quote_uuid(b"p-1"))).is_some());
+		assert_eq!(
+			last_event(),
+			Event::PaymentSettled(SOURCE_PSP, b"p-1".to_vec(), DEST_PSP, 60_500)
+		);
+		assert_noop!(
+			NexusFinal::accept_payment(Origin::signed(DEST_PSP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::InvalidPaymentState
+		);
+	});
+}
+
+#[test]
+fn a_quote_backs_only_one_payment() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		assert_eq!(
+			NexusFinal::quote_use((FXP, quote_uuid(b"p-1"))),
+			Some((SOURCE_PSP, b"p-1".to_vec()))
+		);
+
+		let mut payment = submission(b"p-2");
+		payment.quote_uuid = quote_uuid(b"p-1");
+		assert_eq!(send(payment), Err(Error::<Test>::QuoteInUse.into()));
+		assert_eq!(state(b"p-2"), None);
+
+		assert_ok!(send(submission(b"p-2")));
+	});
+}
+
+#[test]
+fn reject_payment_unreserves_the_source_amount() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		accept_fx_leg(b"p-1");
+
+		assert_ok!(NexusFinal::reject_payment(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec(),
+			b"AC01".to_vec()
+		));
+
+		assert_eq!(free(SGD, SOURCE_PSP), 5_000);
+		assert_eq!(free(INR, FXP), 302_500);
+		assert_eq!(
+			NexusFinal::fx_leg_accepted_at((SOURCE_PSP, b"p-1".to_vec())),
+			None
+		);
+		assert_eq!(state(b"p-1"), Some(PaymentState::Rejected));
+		assert_eq!(
+			last_event(),
+			Event::PaymentRejected(SOURCE_PSP, b"p-1".to_vec(), DEST_PSP, b"AC01".to_vec())
+		);
+	});
+}
+
+#[test]
+fn htlc_payments_follow_the_lock() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		let hashlock = sp_io::hashing::sha2_256(b"secret");
+
+		assert_ok!(NexusFinal::lock_payment_htlc(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec(),
+			hashlock,
+			1 + MinLockPeriod::get()
+		));
+		assert_eq!(state(b"p-1"), Some(PaymentState::HtlcLocked));
+		assert_eq!(
+			last_event(),
+			Event::PaymentHtlcLocked(SOURCE_PSP, b"p-1".to_vec(), hashlock)
+		);
+
+		assert_ok!(NexusHtlc::claim(
+			Origin::signed(FXP),
+			hashlock,
+			b"secret".to_vec()
+		));
+		assert_eq!(state(b"p-1"), Some(PaymentState::HtlcClaimed));
+		assert_eq!(free(SGD, FXP), 1_000);
+		assert_eq!(
+			last_event(),
+			Event::PaymentHtlcResolved(SOURCE_PSP, b"p-1".to_vec(), PaymentState::HtlcClaimed)
+		);
+	});
+}
+
+#[test]
+fn refunded_htlc_payments_go_back_to_the_sender() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		let hashlock = sp_io::hashing::sha2_256(b"secret");
+		let expires_at = 1 + MinLockPeriod::get();
+		assert_ok!(NexusFinal::lock_payment_htlc(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec(),
+			hashlock,
+			expires_at
+		));
+
+		System::set_block_number(expires_at);
+		assert_ok!(NexusHtlc::refund(Origin::signed(SOURCE_PSP), hashlock));
+
+		assert_eq!(state(b"p-1"), Some(PaymentState::HtlcRefunded));
+		assert_eq!(free(SGD, SOURCE_PSP), 5_000);
+	});
+}
+
+#[test]
+fn screening_holds_are_resolved_by_compliance() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		assert_ok!(send(submission(b"p-2")));
+		for uuid in [b"p-1", b"p-2"].iter() {
+			crate::PaymentStatus::<Test>::mutate((SOURCE_PSP, uuid.to_vec()), |status| {
+				if let Some(status) = status {
+					status.state = PaymentState::ScreeningHeld;
+				}
+			});
+		}
+
+		assert_noop!(
+			NexusFinal::resolve_screening_hold(
+				Origin::signed(SOURCE_PSP),
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				true
+			),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			NexusFinal::accept_payment(Origin::signed(DEST_PSP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::InvalidPaymentState
+		);
+
+		assert_ok!(NexusFinal::resolve_screening_hold(
+			Origin::root(),
+			SOURCE_PSP,
+			b"p-1".to_vec(),
+			true
+		));
+		assert_eq!(state(b"p-1"), Some(PaymentState::Pending));
+		assert_eq!(
+			last_event(),
+			Event::ScreeningHoldResolved(SOURCE_PSP, b"p-1".to_vec(), true)
+		);
+
+		assert_ok!(NexusFinal::resolve_screening_hold(
+			Origin::root(),
+			SOURCE_PSP,
+			b"p-2".to_vec(),
+			false
+		));
+		assert_eq!(state(b"p-2"), Some(PaymentState::ScreeningRejected));
+		assert_eq!(reserved(SGD, SOURCE_PSP), 1_000);
+
+		assert_noop!(
+			NexusFinal::resolve_screening_hold(Origin::root(), SOURCE_PSP, b"p-2".to_vec(), true),
+			Error::<Test>::InvalidPaymentState
+		);
+	});
+}
+
+#[test]
+fn envelopes_must_be_readable_by_every_party() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+
+		assert_noop!(
+			NexusFinal::set_payment_envelope(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				envelope(&[[1; 32], [2; 32]])
+			),
+			Error::<Test>::NoEncryptionKey
+		);
+
+		assert_ok!(NexusFinal::set_encryption_key(
+			Origin::signed(SOURCE_PSP),
+			[1; 32]
+		));
+		assert_ok!(NexusFinal::set_encryption_key(
+			Origin::signed(DEST_PSP),
+			[2; 32]
+		));
+		assert_eq!(last_event(), Event::EncryptionKeySet(DEST_PSP, [2; 32]));
+		assert_noop!(
+			NexusFinal::set_regulator_key(Origin::signed(REGULATOR), REGULATOR, Some([3; 32])),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(NexusFinal::set_regulator_key(
+			Origin::root(),
+			REGULATOR,
+			Some([3; 32])
+		));
+		assert_eq!(
+			last_event(),
+			Event::RegulatorKeySet(REGULATOR, Some([3; 32]))
+		);
+
+		assert_noop!(
+			NexusFinal::set_payment_envelope(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				envelope(&[[1; 32], [2; 32]])
+			),
+			Error::<Test>::MissingEnvelopeRecipient
+		);
+		assert_ok!(NexusFinal::set_payment_envelope(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec(),
+			envelope(&[[1; 32], [2; 32], [3; 32]])
+		));
+		assert_eq!(
+			NexusFinal::envelope((SOURCE_PSP, b"p-1".to_vec())),
+			Some(envelope(&[[1; 32], [2; 32], [3; 32]]))
+		);
+		assert_eq!(
+			last_event(),
+			Event::EnvelopeStored(SOURCE_PSP, b"p-1".to_vec(), hash(b"payload"))
+		);
+		assert_noop!(
+			NexusFinal::set_payment_envelope(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				envelope(&[[1; 32], [2; 32], [3; 32], [4; 32]])
+			),
+			Error::<Test>::EnvelopeExists
+		);
+	});
+}
+
+#[test]
+fn regulators_are_bounded() {
+	new_test_ext().execute_with(|| {
+		for regulator in 10..10 + MaxRegulators::get() as u64 {
+			assert_ok!(NexusFinal::set_regulator_key(
+				Origin::root(),
+				regulator,
+				Some([regulator as u8; 32])
+			));
+		}
+
+		assert_noop!(
+			NexusFinal::set_regulator_key(Origin::root(), REGULATOR, Some([3; 32])),
+			Error::<Test>::TooManyRegulators
+		);
+		assert_ok!(NexusFinal::set_regulator_key(
+			Origin::root(),
+			10,
+			Some([3; 32])
+		));
+		assert_ok!(NexusFinal::set_regulator_key(Origin::root(), 10, None));
+		assert_ok!(NexusFinal::set_regulator_key(
+			Origin::root(),
+			REGULATOR,
+			Some([3; 32])
+		));
+	});
+}
+
+#[test]
+fn bank_data_rejects_long_fields() {
+	new_test_ext().execute_with(|| {
+		let long = vec![b'x'; MaxFieldLength::get() as usize + 1];
+		assert_noop!(
+			NexusFinal::set_source_bank_data(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				long.clone(),
+				hash(b"debtor"),
+				hash(b"debtor id")
+			),
+			Error::<Test>::FieldTooLong
+		);
+		assert_noop!(
+			NexusFinal::set_dest_bank_data(
+				Origin::signed(SOURCE_PSP),
+				long,
+				b"DESTBANK".to_vec(),
+				hash(b"creditor")
+			),
+			Error::<Test>::FieldTooLong
+		);
+	});
+}
+
+#[test]
+fn long_envelopes_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let mut long = envelope(&[]);
+		long.ciphertext = vec![0; MaxEnvelopeLength::get() as usize + 1];
+		assert_noop!(
+			NexusFinal::set_payment_envelope(Origin::signed(SOURCE_PSP), b"p-1".to_vec(), long),
+			Error::<Test>::EnvelopeTooLong
+		);
+	});
+}
+
+#[test]
+fn envelopes_with_too_many_recipients_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let crowded = envelope(&[[0; 32]; 5]);
+		assert_noop!(
+			NexusFinal::set_payment_envelope(Origin::signed(SOURCE_PSP), b"p-1".to_vec(), crowded),
+			Error::<Test>::TooManyEnvelopeRecipients
+		);
+	});
+}
+
+#[test]
+fn erase_payment_data_keeps_a_tombstone() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		let keys = (SOURCE_PSP, b"p-1".to_vec());
+		let tombstone = BlakeTwo256::hash_of(&(
+			None::<crate::SourceBankStruct<H256>>,
+			Some(NexusFinal::dest_bank(&keys)),
+			None::<Envelope<H256>>,
+		));
+
+		assert_ok!(NexusFinal::erase_payment_data(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec()
+		));
+
+		assert!(!crate::DestBankCommitment::<Test>::contains_key(&keys));
+		assert_eq!(NexusFinal::tombstone(&keys), Some(tombstone));
+		assert_eq!(
+			last_event(),
+			Event::PersonalDataErased(SOURCE_PSP, b"p-1".to_vec(), tombstone)
+		);
+		// The payment itself is kept.
+		assert_eq!(state(b"p-1"), Some(PaymentState::Pending));
+		assert_noop!(
+			NexusFinal::erase_payment_data(Origin::signed(SOURCE_PSP), b"p-1".to_vec()),
+			Error::<Test>::NothingToErase
+		);
+	});
+}
+
+#[test]
+fn envelopes_cannot_be_attached_after_erasure() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		assert_ok!(NexusFinal::set_encryption_key(
+			Origin::signed(SOURCE_PSP),
+			[1; 32]
+		));
+		assert_ok!(NexusFinal::set_encryption_key(
+			Origin::signed(DEST_PSP),
+			[2; 32]
+		));
+		assert_ok!(NexusFinal::erase_payment_data(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec()
+		));
+
+		assert_noop!(
+			NexusFinal::set_payment_envelope(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				envelope(&[[1; 32], [2; 32]])
+			),
+			Error::<Test>::PaymentDataErased
+		);
+	});
+}
+
+#[test]
+fn payment_data_is_erased_after_the_retention_period() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusFinal::set_source_bank_data(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec(),
+			b"SRCBANK".to_vec(),
+			hash(b"debtor"),
+			hash(b"debtor id")
+		));
+		let keys = (SOURCE_PSP, b"p-1".to_vec());
+		let due = 1 + RetentionPeriod::get();
+
+		NexusFinal::on_initialize(due - 1);
+		assert!(crate::SourceBankCommitment::<Test>::contains_key(&keys));
+
+		NexusFinal::on_initialize(due);
+		assert!(!crate::SourceBankCommitment::<Test>::contains_key(&keys));
+		assert!(NexusFinal::tombstone(&keys).is_some());
+		assert_eq!(NexusFinal::erasure_due(&keys), None);
+	});
+}
+
+#[test]
+fn erasures_beyond_the_limit_are_left_for_the_next_block() {
+	new_test_ext().execute_with(|| {
+		let uuids = [&b"p-1"[..], b"p-2", b"p-3"];
+		for uuid in uuids.iter() {
+			assert_ok!(NexusFinal::set_source_bank_data(
+				Origin::signed(SOURCE_PSP),
+				uuid.to_vec(),
+				b"SRCBANK".to_vec(),
+				hash(b"debtor"),
+				hash(b"debtor id")
+			));
+		}
+		let erased = |uuid: &[u8]| NexusFinal::tombstone(&(SOURCE_PSP, uuid.to_vec())).is_some();
+		let due = 1 + RetentionPeriod::get();
+
+		NexusFinal::on_initialize(due);
+		assert!(erased(uuids[0]) && erased(uuids[1]));
+		assert!(!erased(uuids[2]));
+		assert_eq!(
+			NexusFinal::erasure_queue(due + 1),
+			vec![(SOURCE_PSP, uuids[2].to_vec())]
+		);
+
+		NexusFinal::on_initialize(due + 1);
+		assert!(erased(uuids[2]));
+		assert!(NexusFinal::erasure_queue(due + 1).is_empty());
+	});
+}
+
+#[test]
+fn recalls_are_requested_within_the_window() {
+	new_test_ext().execute_with(|| {
+		prepare();
+		assert_ok!(send(submission(b"p-1")));
+		assert_noop!(
+			NexusFinal::request_recall(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				RecallReason::Duplicate
+			),
+			Error::<Test>::InvalidPaymentState
+		);
+		accept_fx_leg(b"p-1");
+		assert_ok!(NexusFinal::accept_payment(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec()
+		));
+
+		System::set_block_number(2 + RecallWindow::get());
+		assert_noop!(
+			NexusFinal::request_recall(
+				Origin::signed(SOURCE_PSP),
+				b"p-1".to_vec(),
+				RecallReason::Duplicate
+			),
+			Error::<Test>::RecallWindowExpired
+		);
+
+		System::set_block_number(1 + RecallWindow::get());
+		assert_ok!(NexusFinal::request_recall(
+			Origin::signed(SOURCE_PSP),
+			b"p-1".to_vec(),
+			RecallReason::Duplicate
+		));
+		assert_eq!(state(b"p-1"), Some(PaymentState::RecallRequested));
+		assert_eq!(
+			last_event(),
+			Event::RecallRequested(
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				DEST_PSP,
+				RecallReason::Duplicate
+			)
+		);
+	});
+}
+
+#[test]
+fn recalls_are_answered_by_the_destination_psp() {
+	new_test_ext().execute_with(|| {
+		recall();
+
+		assert_noop!(
+			NexusFinal::accept_recall(Origin::signed(FXP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::NotRecallAssignee
+		);
+		assert_noop!(
+			NexusFinal::accept_recall(Origin::signed(DEST_PSP), SOURCE_PSP, b"p-2".to_vec()),
+			Error::<Test>::NoRecall
+		);
+
+		System::set_block_number(2 + RecallResponseWindow::get());
+		assert_noop!(
+			NexusFinal::accept_recall(Origin::signed(DEST_PSP), SOURCE_PSP, b"p-1".to_vec()),
+			Error::<Test>::RecallResponseExpired
+		);
+	});
+}
+
+#[test]
+fn decline_recall_keeps_the_funds() {
+	new_test_ext().execute_with(|| {
+		recall();
+
+		assert_ok!(NexusFinal::decline_recall(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec(),
+			RecallDeclineReason::CustomerDecision
+		));
+
+		assert_eq!(state(b"p-1"), Some(PaymentState::RecallDeclined));
+		assert_eq!(
+			NexusFinal::recall((SOURCE_PSP, b"p-1".to_vec())).and_then(|r| r.decline_reason),
+			Some(RecallDeclineReason::CustomerDecision)
+		);
+		assert_eq!(free(INR, DEST_PSP), 60_500);
+		assert_eq!(
+			last_event(),
+			Event::RecallDeclined(
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				DEST_PSP,
+				RecallDeclineReason::CustomerDecision
+			)
+		);
+	});
+}
+
+#[test]
+fn return_payment_sends_the_funds_back() {
+	new_test_ext().execute_with(|| {
+		recall();
+		assert_noop!(
+			NexusFinal::return_payment(
+				Origin::signed(DEST_PSP),
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				b"r-1".to_vec(),
+				b"60500".to_vec(),
+				ReturnReason::FollowingCancellationRequest
+			),
+			Error::<Test>::InvalidPaymentState
+		);
+		assert_ok!(NexusFinal::accept_recall(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec()
+		));
+		assert_eq!(
+			last_event(),
+			Event::RecallAccepted(SOURCE_PSP, b"p-1".to_vec(), DEST_PSP)
+		);
+
+		assert_ok!(NexusFinal::return_payment(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec(),
+			b"r-1".to_vec(),
+			b"60500".to_vec(),
+			ReturnReason::FollowingCancellationRequest
+		));
+
+		assert_eq!(state(b"p-1"), Some(PaymentState::Returned));
+		assert_eq!(free(SGD, SOURCE_PSP), 5_000);
+		assert_eq!(free(SGD, FXP), 0);
+		assert_eq!(free(INR, FXP), 302_500);
+		assert_eq!(free(INR, DEST_PSP), 0);
+		assert!(NexusFinal::payment_return((SOURCE_PSP, b"p-1".to_vec())).is_some());
+		assert_eq!(
+			last_event(),
+			Event::PaymentReturned(
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				b"r-1".to_vec(),
+				ReturnReason::FollowingCancellationRequest
+			)
+		);
+	});
+}
+
+#[test]
+fn returns_are_sent_within_the_window() {
+	new_test_ext().execute_with(|| {
+		recall();
+		assert_ok!(NexusFinal::accept_recall(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			b"p-1".to_vec()
+		));
+
+		System::set_block_number(2 + ReturnWindow::get());
+		assert_noop!(
+			NexusFinal::return_payment(
+				Origin::signed(DEST_PSP),
+				SOURCE_PSP,
+				b"p-1".to_vec(),
+				b"r-1".to_vec(),
+				b"60500".to_vec(),
+				ReturnReason::FollowingCancellationRequest
+			),
+			Error::<Test>::ReturnWindowExpired
+		);
+	});
+}
+
+#[test]
+fn payment_info_collects_the_records_of_a_payment() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(NexusFinal::payment_info(b"p-1".to_vec()), None);
+		settle();
+
+		let info = NexusFinal::payment_info(b"p-1".to_vec()).unwrap();
+		assert_eq!(info.source_psp, SOURCE_PSP);
+		assert_eq!(info.payment.payment_uuid, b"p-1".to_vec());
+		assert_eq!(info.source_bank, None);
+		assert!(info.dest_bank.is_some());
+		assert_eq!(info.settlement.map(|s| s.destination_amount), Some(60_500));
+		assert_eq!(info.status.map(|s| s.state), Some(PaymentState::Accepted));
+		assert!(info.costs.is_some());
+	});
+}
+
+#[test]
+fn payments_by_psp_lists_sent_and_received_payments() {
+	new_test_ext().execute_with(|| {
+		settle();
+
+		assert_eq!(
+			NexusFinal::payments_by_psp(&SOURCE_PSP),
+			vec![b"p-1".to_vec()]
+		);
+		assert_eq!(
+			NexusFinal::payments_by_psp(&DEST_PSP),
+			vec![b"p-1".to_vec()]
+		);
+		assert!(NexusFinal::payments_by_psp(&FXP).is_empty());
+	});
+}
+
+#[test]
+fn payment_transitions_report_each_payment_once() {
+	new_test_ext().execute_with(|| {
+		settle();
+		assert_ok!(NexusFinal::set_limit(
+			Origin::root(),
+			LimitScope::Psp(SOURCE_PSP),
+			SGD,
+			Some(TransactionLimit {
+				max_single: Some(999),
+				..Default::default()
+			})
+		));
+		assert_eq!(
+			send(submission(b"p-2")),
+			Err(Error::<Test>::TransactionLimitExceeded.into())
+		);
+		let payment = PaymentSubmission {
+			settlement_amount: b"121000".to_vec(),
+			source_amount: 2_000,
+			destination_amount: 121_000,
+			..submission(b"p-3")
+		};
+		assert_eq!(
+			send(payment),
+			Err(Error::<Test>::DestinationValueExceeded.into())
+		);
+
+		let events = System::events()
+			.into_iter()
+			.filter_map(|record| match record.event {
+				crate::mock::Event::nexus_api_final(event) => Some(event),
+				_ => None,
+			});
+		let transitions = NexusFinal::payment_transitions(events);
+
+		assert_eq!(transitions.len(), 3);
+		assert_eq!(transitions[0].source_psp, SOURCE_PSP);
+		assert_eq!(transitions[0].dest_psp, Some(DEST_PSP));
+		assert_eq!(transitions[0].payment_uuid, b"p-1".to_vec());
+		assert_eq!(transitions[0].status.state, PaymentState::Accepted);
+		for (transition, uuid) in transitions[1..].iter().zip([b"p-2", b"p-3"].iter()) {
+			assert_eq!(transition.source_psp, SOURCE_PSP);
+			assert_eq!(transition.dest_psp, None);
+			assert_eq!(transition.payment_uuid, uuid.to_vec());
+			assert_eq!(transition.status.state, PaymentState::LimitExceeded);
+			assert_eq!(transition.status.created_at, 1);
+		}
+		assert!(NexusFinal::payment_transitions(vec![]).is_empty());
+	});
+}
@@ -0,0 +1,259 @@
+//! Weights for nexus_api_final.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for nexus_api_final.
+pub trait WeightInfo {
+	fn set_status() -> Weight;
+	fn set_dest_bank_data() -> Weight;
+	fn set_source_bank_data() -> Weight;
+	fn attest_creditor() -> Weight;
+	fn final_payment_func() -> Weight;
+	fn submit_payments(n: u32) -> Weight;
+	fn set_limit() -> Weight;
+	fn set_fee_schedule() -> Weight;
+	fn erase_payment_data() -> Weight;
+	fn set_retry_mode() -> Weight;
+	fn resolve_screening_hold() -> Weight;
+	fn accept_fx_leg() -> Weight;
+	fn accept_payment() -> Weight;
+	fn reject_payment() -> Weight;
+	fn lock_payment_htlc() -> Weight;
+	fn set_encryption_key() -> Weight;
+	fn set_regulator_key(g: u32) -> Weight;
+	fn set_payment_envelope(r: u32, c: u32, g: u32) -> Weight;
+	fn request_recall() -> Weight;
+	fn accept_recall() -> Weight;
+	fn decline_recall() -> Weight;
+	fn return_payment() -> Weight;
+}
+
+/// Weights for nexus_api_final.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn set_status() -> Weight {
+		(24_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_dest_bank_data() -> Weight {
+		(31_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn set_source_bank_data() -> Weight {
+		(33_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn attest_creditor() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn final_payment_func() -> Weight {
+		(154_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(27 as Weight))
+			.saturating_add(T::DbWeight::get().writes(13 as Weight))
+	}
+	fn submit_payments(n: u32) -> Weight {
+		(25_000_000 as Weight)
+			.saturating_add((124_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads((29 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes((14 as Weight).saturating_mul(n as Weight)))
+	}
+	fn set_limit() -> Weight {
+		(29_000_000 as Weight).saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn set_fee_schedule() -> Weight {
+		(20_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn erase_payment_data() -> Weight {
+		(72_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(8 as Weight))
+	}
+	fn set_retry_mode() -> Weight {
+		(18_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn resolve_screening_hold() -> Weight {
+		(57_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(12 as Weight))
+			.saturating_add(T::DbWeight::get().writes(10 as Weight))
+	}
+	fn accept_fx_leg() -> Weight {
+		(52_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	fn accept_payment() -> Weight {
+		(98_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(9 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	fn reject_payment() -> Weight {
+		(59_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(12 as Weight))
+			.saturating_add(T::DbWeight::get().writes(10 as Weight))
+	}
+	fn lock_payment_htlc() -> Weight {
+		(78_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	fn set_encryption_key() -> Weight {
+		(18_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_regulator_key(g: u32) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((600_000 as Weight).saturating_mul(g as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(g as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_payment_envelope(r: u32, c: u32, g: u32) -> Weight {
+		(48_000_000 as Weight)
+			.saturating_add((400_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add((2_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add((1_900_000 as Weight).saturating_mul(g as Weight))
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(g as Weight)))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn request_recall() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	fn accept_recall() -> Weight {
+		(33_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn decline_recall() -> Weight {
+		(35_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	fn return_payment() -> Weight {
+		(94_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(15 as Weight))
+			.saturating_add(T::DbWeight::get().writes(13 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn set_status() -> Weight {
+		(24_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_dest_bank_data() -> Weight {
+		(31_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn set_source_bank_data() -> Weight {
+		(33_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	fn attest_creditor() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn final_payment_func() -> Weight {
+		(154_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(27 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(13 as Weight))
+	}
+	fn submit_payments(n: u32) -> Weight {
+		(25_000_000 as Weight)
+			.saturating_add((124_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads((29 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes((14 as Weight).saturating_mul(n as Weight)))
+	}
+	fn set_limit() -> Weight {
+		(29_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn set_fee_schedule() -> Weight {
+		(20_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn erase_payment_data() -> Weight {
+		(72_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(8 as Weight))
+	}
+	fn set_retry_mode() -> Weight {
+		(18_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn resolve_screening_hold() -> Weight {
+		(57_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(12 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(10 as Weight))
+	}
+	fn accept_fx_leg() -> Weight {
+		(52_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	fn accept_payment() -> Weight {
+		(98_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(9 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(6 as Weight))
+	}
+	fn reject_payment() -> Weight {
+		(59_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(12 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(10 as Weight))
+	}
+	fn lock_payment_htlc() -> Weight {
+		(78_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(7 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn set_encryption_key() -> Weight {
+		(18_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_regulator_key(g: u32) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((600_000 as Weight).saturating_mul(g as Weight))
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(g as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_payment_envelope(r: u32, c: u32, g: u32) -> Weight {
+		(48_000_000 as Weight)
+			.saturating_add((400_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add((2_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add((1_900_000 as Weight).saturating_mul(g as Weight))
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(g as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn request_recall() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	fn accept_recall() -> Weight {
+		(33_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn decline_recall() -> Weight {
+		(35_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	fn return_payment() -> Weight {
+		(94_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(15 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(13 as Weight))
+	}
+}
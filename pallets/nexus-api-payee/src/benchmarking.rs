@@ -0,0 +1,64 @@
+//! Benchmarks for nexus-api-payee.
+//!
+//! Every byte field is as long as `MaxFieldLength` allows, which is the most expensive input.
+
+use super::*;
+
+use frame_benchmarking::benchmarks;
+use frame_support::traits::EnsureOrigin;
+use frame_system::RawOrigin;
+use sp_std::vec;
+
+fn field<T: Config>() -> Vec<u8> {
+	vec![b'0'; T::MaxFieldLength::get() as usize]
+}
+
+/// Have `caller` look up a payee whose bank belongs to a destination PSP, and return that PSP.
+fn look_up<T: Config>(caller: &T::AccountId) -> Result<T::AccountId, &'static str> {
+	let dest_psp =
+		T::DestinationPspOrigin::ensure_origin(T::DestinationPspOrigin::successful_origin())?;
+	T::Directory::add_bank(&dest_psp, &field::<T>());
+	Pallet::<T>::confirmation_of_payee(
+		RawOrigin::Signed(caller.clone()).into(),
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+	)?;
+	Ok(dest_psp)
+}
+
+benchmarks! {
+	// The first lookup of a PSP also schedules its erasure.
+	confirmation_of_payee {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+	}: _(RawOrigin::Signed(caller.clone()), field::<T>(), field::<T>(), field::<T>(), field::<T>())
+	verify {
+		assert!(ErasureDue::<T>::contains_key(&caller, &field::<T>()));
+	}
+
+	confirm_subprocess {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let dest_psp = look_up::<T>(&caller)?;
+	}: _(RawOrigin::Signed(dest_psp), caller.clone(), field::<T>(), field::<T>(), field::<T>())
+	verify {
+		assert!(SubProcess::<T>::contains_key(&caller, &field::<T>()));
+	}
+
+	// Both halves of the lookup are stored and have to be hashed into the tombstone.
+	erase_payee_data {
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+		let dest_psp = look_up::<T>(&caller)?;
+		Pallet::<T>::confirm_subprocess(
+			RawOrigin::Signed(dest_psp).into(),
+			caller.clone(),
+			field::<T>(),
+			field::<T>(),
+			field::<T>(),
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), field::<T>())
+	verify {
+		assert!(!ConfirmPayee::<T>::contains_key(&caller, &field::<T>()));
+		assert!(!SubProcess::<T>::contains_key(&caller, &field::<T>()));
+	}
+}
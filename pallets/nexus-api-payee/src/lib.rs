@@ -1,15 +1,55 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Decode, Encode};
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult};
-use frame_system::ensure_signed;
+use frame_support::traits::Get;
+use nexus_directory::ParticipantDirectory;
+use nexus_support::ensure_field_lengths;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::traits::{Hash as HashT, One, Saturating};
 
 use sp_std::prelude::*;
 
-pub trait Config: frame_system::Config {
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migrations;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// Lets other pallets check that a payee has been confirmed before paying it.
+pub trait PayeeConfirmation<AccountId> {
+	/// Whether the payee `source_psp` looked up as `lookup_id` at `dest_bank_id` has been
+	/// confirmed by the destination PSP.
+	fn is_confirmed(source_psp: &AccountId, lookup_id: &[u8], dest_bank_id: &[u8]) -> bool;
+}
+
+impl<AccountId> PayeeConfirmation<AccountId> for () {
+	fn is_confirmed(_: &AccountId, _: &[u8], _: &[u8]) -> bool {
+		true
+	}
+}
+
+/// Storage layouts of this pallet, for migrations.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+enum Releases {
+	V1,
+	V2LookupIds,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
 pub struct Payee {
 	destination_country_id: Vec<u8>,
@@ -17,82 +57,405 @@ pub struct Payee {
 	destination_bank_account_number: Vec<u8>,
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
 pub struct DestinationPayee {
 	destination_bank_acc_holder_name: Vec<u8>,
 	destination_bank_acc_display_name: Vec<u8>,
 }
 
-decl_storage! {
-	trait Store for Module<T: Config> as NexusApiPayee {
-		ConfirmPayee get(fn confirm_payee): map hasher(blake2_128_concat) T::AccountId => Payee;
-		SubProcess get(fn sub_process): map hasher(blake2_128_concat) T::AccountId => DestinationPayee;
-	}
+/// A payee lookup of a PSP, as returned by the `NexusPayeeApi` runtime API.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct PayeeLookup {
+	pub payee: Option<Payee>,
+	pub destination_payee: Option<DestinationPayee>,
+	/// Whether the destination bank has answered with the account holder's name.
+	pub confirmed: bool,
 }
 
-decl_event!(
-	pub enum Event<T>
-	where
-		AccountId = <T as frame_system::Config>::AccountId,
-	{
-		/// SubProcess is done on behalf of the bank
-		SubProcessDone(AccountId, Vec<u8>, Vec<u8>),
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Who may look up a payee.
+		type SourcePspOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+		/// Who may confirm a payee at one of their banks.
+		type DestinationPspOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+		/// The banks of the participants.
+		type Directory: ParticipantDirectory<Self::AccountId>;
+
+		/// Number of blocks a payee lookup is kept before it is erased.
+		#[pallet::constant]
+		type RetentionPeriod: Get<Self::BlockNumber>;
+
+		/// The most payee lookups erased in one block. The rest are left for the next block.
+		#[pallet::constant]
+		type MaxErasuresPerBlock: Get<u32>;
+
+		/// The longest any byte field of a payee lookup may be.
+		#[pallet::constant]
+		type MaxFieldLength: Get<u32>;
 
-		/// Confirmation Is Done
-		PaymentConfirm(AccountId, Vec<u8>, Vec<u8>),
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
 	}
-);
 
-decl_error! {
-	pub enum Error for Module<T: Config> {
-		/// Invalid Payee
-		Invalid
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	pub(super) type StorageVersion<T> = StorageValue<_, Releases, ValueQuery>;
+
+	/// The payees looked up, by source PSP and lookup id.
+	#[pallet::storage]
+	#[pallet::getter(fn confirm_payee)]
+	pub(super) type ConfirmPayee<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		Vec<u8>,
+		Payee,
+		ValueQuery,
+	>;
+
+	/// The answers of the destination PSPs, by source PSP and lookup id.
+	#[pallet::storage]
+	#[pallet::getter(fn sub_process)]
+	pub(super) type SubProcess<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		Vec<u8>,
+		DestinationPayee,
+		ValueQuery,
+	>;
+
+	/// The answers carried over from before lookups had ids, which their source PSP gave itself.
+	/// They do not confirm the payee until the destination PSP answers the lookup again.
+	#[pallet::storage]
+	pub(super) type UnconfirmedAnswers<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, Vec<u8>, ()>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn erasure_queue)]
+	pub(super) type ErasureQueue<T: Config> =
+		StorageMap<_, Twox64Concat, T::BlockNumber, Vec<(T::AccountId, Vec<u8>)>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn erasure_due)]
+	pub(super) type ErasureDue<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		Vec<u8>,
+		T::BlockNumber,
+	>;
+
+	/// A hash of the payee lookups of a PSP erased in a block. Lookups erased in the same block
+	/// are folded into one hash in the order they were erased.
+	#[pallet::storage]
+	#[pallet::getter(fn tombstone)]
+	pub(super) type Tombstones<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, T::BlockNumber, T::Hash>;
+
+	#[pallet::genesis_config]
+	#[derive(Default)]
+	pub struct GenesisConfig {}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		/// A new chain starts out with the latest storage layout, so no migration runs on it.
+		fn build(&self) {
+			<StorageVersion<T>>::put(Releases::V2LookupIds);
+		}
 	}
-}
 
-decl_module! {
-	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", T::Hash = "Hash")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The destination PSP has answered a payee lookup with the account holder.
+		/// [source_psp, lookup_id, holder_name, display_name]
+		SubProcessDone(T::AccountId, Vec<u8>, Vec<u8>, Vec<u8>),
 
-		// Initialize errors
-		type Error = Error<T>;
+		/// A payee has been looked up and awaits the answer of the PSP of its bank.
+		/// [source_psp, lookup_id, destination_bank_identifier]
+		PaymentConfirm(T::AccountId, Vec<u8>, Vec<u8>),
 
-		// Initialize events
-		fn deposit_event() = default;
+		/// A payee lookup has been erased. [psp, lookup_id, tombstone]
+		PayeeDataErased(T::AccountId, Vec<u8>, T::Hash),
+	}
 
-		/// The request will be sent by the frontend
-		#[weight = 10_000]
-		fn confirm_subprocess(origin, 	destination_bank_acc_holder_name: Vec<u8>,
-			destination_bank_acc_display_name: Vec<u8>,) -> DispatchResult {
-			let user = ensure_signed(origin)?;
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Invalid Payee
+		Invalid,
 
-			let destination_payee = DestinationPayee {
-				destination_bank_acc_holder_name,
-				destination_bank_acc_display_name,
-			};
+		/// There is no payee lookup stored for the caller
+		NothingToErase,
 
-			let destination_payee_clone = destination_payee.clone();
+		/// A field is longer than `MaxFieldLength`
+		FieldTooLong,
 
-			<SubProcess<T>>::insert(&user, &destination_payee);
-			Self::deposit_event(RawEvent::SubProcessDone(user, destination_payee_clone.destination_bank_acc_holder_name, destination_payee_clone.destination_bank_acc_display_name));
-			Ok(())
+		/// The source PSP has not looked up a payee with this lookup id
+		LookupNotFound,
+
+		/// The payee's bank does not belong to the caller
+		NotDestinationBank,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Erase the payee lookups whose retention period ends in this block.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let mut due = <ErasureQueue<T>>::take(now);
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+			let max = T::MaxErasuresPerBlock::get() as usize;
+			if due.len() > max {
+				// Erase the rest first thing in the next block.
+				let mut rest = due.split_off(max);
+				<ErasureQueue<T>>::mutate(now.saturating_add(One::one()), |next| {
+					rest.append(next);
+					*next = rest;
+				});
+				weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			}
+
+			let count = due.len() as Weight;
+			for (psp, lookup_id) in due {
+				Self::erase(&psp, &lookup_id);
+			}
+
+			weight.saturating_add(T::DbWeight::get().reads_writes(3 * count, 5 * count))
 		}
 
-		/// Confirm the payment
-		#[weight = 10_000]
-		fn confirmation_of_payee(origin, destination_country_id: Vec<u8>, destination_bank_identifier: Vec<u8>, destination_bank_account_number: Vec<u8>) -> DispatchResult  {
-			let user = ensure_signed(origin)?;
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
 
-			// Check if the dest isp/bank has confirmed the payment
-			let confirmation = <SubProcess<T>>::get(&user);
+			if <StorageVersion<T>>::get() == Releases::V1 {
+				weight = weight.saturating_add(migrations::key_lookups_by_id::<T>());
+				<StorageVersion<T>>::put(Releases::V2LookupIds);
+			}
+
+			weight
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Look up a payee as `lookup_id`, for the PSP of its bank to confirm. Looking up again
+		/// under the same id drops the earlier answer.
+		#[pallet::weight(T::WeightInfo::confirmation_of_payee())]
+		pub fn confirmation_of_payee(
+			origin: OriginFor<T>,
+			lookup_id: Vec<u8>,
+			destination_country_id: Vec<u8>,
+			destination_bank_identifier: Vec<u8>,
+			destination_bank_account_number: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[
+					&lookup_id,
+					&destination_country_id,
+					&destination_bank_identifier,
+					&destination_bank_account_number,
+				],
+				Error::<T>::FieldTooLong,
+			)?;
 
 			let payee = Payee {
 				destination_country_id,
 				destination_bank_identifier,
 				destination_bank_account_number,
 			};
-			<ConfirmPayee<T>>::insert(&user, payee);
-			Self::deposit_event(RawEvent::PaymentConfirm(user, confirmation.destination_bank_acc_holder_name, confirmation.destination_bank_acc_display_name));
-			Ok(())
+			<ConfirmPayee<T>>::insert(&user, &lookup_id, &payee);
+			<SubProcess<T>>::remove(&user, &lookup_id);
+			<UnconfirmedAnswers<T>>::remove(&user, &lookup_id);
+			Self::schedule_erasure(&user, &lookup_id);
+			Self::deposit_event(Event::PaymentConfirm(
+				user,
+				lookup_id,
+				payee.destination_bank_identifier,
+			));
+			Ok(().into())
+		}
+
+		/// Answer the payee lookup `lookup_id` of `source_psp` with the account holder. The
+		/// payee's bank has to belong to the caller.
+		#[pallet::weight(T::WeightInfo::confirm_subprocess())]
+		pub fn confirm_subprocess(
+			origin: OriginFor<T>,
+			source_psp: T::AccountId,
+			lookup_id: Vec<u8>,
+			destination_bank_acc_holder_name: Vec<u8>,
+			destination_bank_acc_display_name: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::DestinationPspOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[
+					&destination_bank_acc_holder_name,
+					&destination_bank_acc_display_name,
+				],
+				Error::<T>::FieldTooLong,
+			)?;
+			ensure!(
+				<ConfirmPayee<T>>::contains_key(&source_psp, &lookup_id),
+				Error::<T>::LookupNotFound
+			);
+			ensure!(
+				T::Directory::is_bank_of(
+					&user,
+					&Self::confirm_payee(&source_psp, &lookup_id).destination_bank_identifier
+				),
+				Error::<T>::NotDestinationBank
+			);
+
+			let destination_payee = DestinationPayee {
+				destination_bank_acc_holder_name,
+				destination_bank_acc_display_name,
+			};
+			<SubProcess<T>>::insert(&source_psp, &lookup_id, &destination_payee);
+			<UnconfirmedAnswers<T>>::remove(&source_psp, &lookup_id);
+			Self::deposit_event(Event::SubProcessDone(
+				source_psp,
+				lookup_id,
+				destination_payee.destination_bank_acc_holder_name,
+				destination_payee.destination_bank_acc_display_name,
+			));
+			Ok(().into())
+		}
+
+		/// Erase the caller's payee lookup `lookup_id` before its retention period ends. A hash of
+		/// the erased data is kept.
+		#[pallet::weight(T::WeightInfo::erase_payee_data())]
+		pub fn erase_payee_data(
+			origin: OriginFor<T>,
+			lookup_id: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+
+			ensure!(Self::erase(&user, &lookup_id), Error::<T>::NothingToErase);
+
+			Ok(().into())
 		}
 	}
 }
+
+impl<T: Config> Pallet<T> {
+	/// The payee `psp` looked up as `lookup_id`, unless there is none or it has been erased.
+	pub fn payee_lookup(psp: &T::AccountId, lookup_id: &[u8]) -> Option<PayeeLookup> {
+		let payee = if <ConfirmPayee<T>>::contains_key(psp, lookup_id) {
+			Some(Self::confirm_payee(psp, lookup_id))
+		} else {
+			None
+		};
+		let destination_payee = if <SubProcess<T>>::contains_key(psp, lookup_id) {
+			Some(Self::sub_process(psp, lookup_id))
+		} else {
+			None
+		};
+		if payee.is_none() && destination_payee.is_none() {
+			return None;
+		}
+
+		let confirmed = !<UnconfirmedAnswers<T>>::contains_key(psp, lookup_id)
+			&& destination_payee
+				.as_ref()
+				.map_or(false, |destination_payee| {
+					!destination_payee
+						.destination_bank_acc_holder_name
+						.is_empty()
+				});
+		Some(PayeeLookup {
+			payee,
+			destination_payee,
+			confirmed,
+		})
+	}
+
+	/// Erase the payee lookup `lookup_id` of `psp` once its retention period is over, counted
+	/// from when it was first stored.
+	fn schedule_erasure(psp: &T::AccountId, lookup_id: &[u8]) {
+		if <ErasureDue<T>>::contains_key(psp, lookup_id) {
+			return;
+		}
+
+		let due =
+			<frame_system::Module<T>>::block_number().saturating_add(T::RetentionPeriod::get());
+		<ErasureDue<T>>::insert(psp, lookup_id, due);
+		<ErasureQueue<T>>::append(due, (psp, lookup_id));
+	}
+
+	/// Remove the payee lookup `lookup_id` of `psp` and keep a hash of it as a tombstone.
+	/// Returns whether there was anything to erase.
+	fn erase(psp: &T::AccountId, lookup_id: &[u8]) -> bool {
+		<ErasureDue<T>>::remove(psp, lookup_id);
+
+		let payee = if <ConfirmPayee<T>>::contains_key(psp, lookup_id) {
+			Some(<ConfirmPayee<T>>::take(psp, lookup_id))
+		} else {
+			None
+		};
+		let destination_payee = if <SubProcess<T>>::contains_key(psp, lookup_id) {
+			Some(<SubProcess<T>>::take(psp, lookup_id))
+		} else {
+			None
+		};
+		<UnconfirmedAnswers<T>>::remove(psp, lookup_id);
+
+		if payee.is_none() && destination_payee.is_none() {
+			return false;
+		}
+
+		let tombstone = T::Hashing::hash_of(&(lookup_id, payee, destination_payee));
+		Self::keep_tombstone(psp, tombstone);
+		Self::deposit_event(Event::PayeeDataErased(
+			psp.clone(),
+			lookup_id.to_vec(),
+			tombstone,
+		));
+
+		true
+	}
+
+	/// Keep `tombstone` for `psp` in this block, folded into any kept before it.
+	fn keep_tombstone(psp: &T::AccountId, tombstone: T::Hash) {
+		let now = <frame_system::Module<T>>::block_number();
+		let folded = match <Tombstones<T>>::get(psp, now) {
+			Some(earlier) => T::Hashing::hash_of(&(earlier, tombstone)),
+			None => tombstone,
+		};
+		<Tombstones<T>>::insert(psp, now, folded);
+	}
+}
+
+impl<T: Config> PayeeConfirmation<T::AccountId> for Pallet<T> {
+	/// A confirmation is positive once the PSP of the bank has answered with the account
+	/// holder's name.
+	fn is_confirmed(source_psp: &T::AccountId, lookup_id: &[u8], dest_bank_id: &[u8]) -> bool {
+		<ConfirmPayee<T>>::contains_key(source_psp, lookup_id)
+			&& !<UnconfirmedAnswers<T>>::contains_key(source_psp, lookup_id)
+			&& Self::confirm_payee(source_psp, lookup_id).destination_bank_identifier
+				== dest_bank_id
+			&& !Self::sub_process(source_psp, lookup_id)
+				.destination_bank_acc_holder_name
+				.is_empty()
+	}
+}
@@ -0,0 +1,73 @@
+//! Storage migrations of the payee pallet.
+
+use super::*;
+use frame_support::{
+	storage::{migration::storage_key_iter, StoragePrefixedMap},
+	traits::Get,
+	weights::Weight,
+	Blake2_128Concat,
+};
+
+/// The id the payee lookup a PSP stored before lookups had ids is kept under. A PSP had at most
+/// one such lookup, so the hash of the PSP tells it apart.
+pub fn legacy_lookup_id<T: Config>(psp: &T::AccountId) -> Vec<u8> {
+	T::Hashing::hash_of(psp).as_ref().to_vec()
+}
+
+/// Key the payee lookups stored before they had a lookup id by `legacy_lookup_id`, along with
+/// their erasure schedule.
+///
+/// Their source PSP answered them itself, so their answers are kept in `UnconfirmedAnswers`
+/// until the destination PSP answers again.
+pub fn key_lookups_by_id<T: Config>() -> Weight {
+	let payees = storage_key_iter::<T::AccountId, Payee, Blake2_128Concat>(
+		ConfirmPayee::<T>::module_prefix(),
+		ConfirmPayee::<T>::storage_prefix(),
+	)
+	.drain()
+	.collect::<Vec<_>>();
+	let answers = storage_key_iter::<T::AccountId, DestinationPayee, Blake2_128Concat>(
+		SubProcess::<T>::module_prefix(),
+		SubProcess::<T>::storage_prefix(),
+	)
+	.drain()
+	.collect::<Vec<_>>();
+	let erasures_due = storage_key_iter::<T::AccountId, T::BlockNumber, Blake2_128Concat>(
+		ErasureDue::<T>::module_prefix(),
+		ErasureDue::<T>::storage_prefix(),
+	)
+	.drain()
+	.collect::<Vec<_>>();
+
+	let mut reads = (payees.len() + answers.len() + erasures_due.len()) as Weight;
+	let mut writes = 0;
+	for (psp, payee) in payees {
+		<ConfirmPayee<T>>::insert(&psp, legacy_lookup_id::<T>(&psp), payee);
+		writes += 2;
+	}
+	for (psp, destination_payee) in answers {
+		let lookup_id = legacy_lookup_id::<T>(&psp);
+		<SubProcess<T>>::insert(&psp, &lookup_id, destination_payee);
+		<UnconfirmedAnswers<T>>::insert(&psp, &lookup_id, ());
+		writes += 3;
+	}
+	for (psp, due) in erasures_due {
+		<ErasureDue<T>>::insert(&psp, legacy_lookup_id::<T>(&psp), due);
+		writes += 2;
+	}
+
+	<ErasureQueue<T>>::translate::<Vec<T::AccountId>, _>(|_, psps| {
+		reads += 1;
+		writes += 1;
+		Some(
+			psps.into_iter()
+				.map(|psp| {
+					let lookup_id = legacy_lookup_id::<T>(&psp);
+					(psp, lookup_id)
+				})
+				.collect(),
+		)
+	});
+
+	T::DbWeight::get().reads_writes(reads, writes + 1)
+}
@@ -0,0 +1,109 @@
+use crate as nexus_api_payee;
+use frame_support::parameter_types;
+use frame_system::EnsureSigned;
+use nexus_directory::ParticipantDirectory;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		NexusPayee: nexus_api_payee::{Module, Call, Storage, Config, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+	pub const MaxFieldLength: u32 = 32;
+	pub const RetentionPeriod: u64 = 100;
+	pub const MaxErasuresPerBlock: u32 = 2;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+pub const SOURCE_PSP: u64 = 1;
+pub const DEST_PSP: u64 = 2;
+
+/// The payee bank `SBININBB` belongs to `DEST_PSP`.
+pub struct Directory;
+
+impl ParticipantDirectory<u64> for Directory {
+	fn is_bank_of(who: &u64, bank_id: &[u8]) -> bool {
+		bank_id == b"SBININBB" && *who == DEST_PSP
+	}
+
+	fn home_of(_: &u64) -> Option<(u64, Vec<u8>)> {
+		None
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn add_bank(_: &u64, _: &[u8]) {}
+}
+
+impl nexus_api_payee::Config for Test {
+	type Event = Event;
+	type SourcePspOrigin = EnsureSigned<u64>;
+	type DestinationPspOrigin = EnsureSigned<u64>;
+	type Directory = Directory;
+	type RetentionPeriod = RetentionPeriod;
+	type MaxErasuresPerBlock = MaxErasuresPerBlock;
+	type MaxFieldLength = MaxFieldLength;
+	type WeightInfo = ();
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap()
+		.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// The last event deposited by the payee pallet.
+pub fn last_event() -> nexus_api_payee::Event<Test> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::nexus_api_payee(event) => Some(event),
+			_ => None,
+		})
+		.last()
+		.expect("an event was deposited")
+}
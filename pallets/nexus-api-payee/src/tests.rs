@@ -0,0 +1,477 @@
+use crate::{
+	migrations, mock::*, DestinationPayee, Error, Event, Payee, PayeeConfirmation, PayeeLookup,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::put_storage_value,
+	traits::{GenesisBuild, Get, OnInitialize, OnRuntimeUpgrade},
+	Blake2_128Concat, StorageHasher, Twox64Concat,
+};
+use sp_runtime::traits::{BlakeTwo256, Hash};
+
+const LOOKUP_ID: &[u8] = b"payment-1";
+
+fn payee() -> Payee {
+	Payee {
+		destination_country_id: b"IN".to_vec(),
+		destination_bank_identifier: b"SBININBB".to_vec(),
+		destination_bank_account_number: b"00112233".to_vec(),
+	}
+}
+
+fn destination_payee() -> DestinationPayee {
+	DestinationPayee {
+		destination_bank_acc_holder_name: b"Asha Rao".to_vec(),
+		destination_bank_acc_display_name: b"A. Rao".to_vec(),
+	}
+}
+
+fn psp_key() -> Vec<u8> {
+	Blake2_128Concat::hash(&SOURCE_PSP.encode())
+}
+
+/// Write a lookup as it was stored before lookups had an id.
+fn populate_old_layout() {
+	put_storage_value(b"NexusPayee", b"ConfirmPayee", &psp_key(), payee());
+	put_storage_value(
+		b"NexusPayee",
+		b"SubProcess",
+		&psp_key(),
+		destination_payee(),
+	);
+	put_storage_value(b"NexusPayee", b"ErasureDue", &psp_key(), 101u64);
+	put_storage_value(
+		b"NexusPayee",
+		b"ErasureQueue",
+		&Twox64Concat::hash(&101u64.encode()),
+		vec![SOURCE_PSP],
+	);
+}
+
+#[test]
+fn migration_keeps_lookups_under_a_derived_id() {
+	new_test_ext().execute_with(|| {
+		populate_old_layout();
+
+		NexusPayee::on_runtime_upgrade();
+
+		let lookup_id = migrations::legacy_lookup_id::<Test>(&SOURCE_PSP);
+		assert_eq!(
+			NexusPayee::payee_lookup(&SOURCE_PSP, &lookup_id),
+			Some(PayeeLookup {
+				payee: Some(payee()),
+				destination_payee: Some(destination_payee()),
+				confirmed: false,
+			})
+		);
+		assert!(!NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			&lookup_id,
+			b"SBININBB"
+		));
+		assert_eq!(NexusPayee::erasure_due(SOURCE_PSP, &lookup_id), Some(101));
+		assert_eq!(
+			NexusPayee::erasure_queue(101),
+			vec![(SOURCE_PSP, lookup_id.clone())]
+		);
+		assert_eq!(NexusPayee::tombstone(SOURCE_PSP, 1), None);
+
+		// The destination PSP can still confirm the payee, until the lookup is erased on time.
+		let destination_payee = destination_payee();
+		assert_ok!(NexusPayee::confirm_subprocess(
+			Origin::signed(DEST_PSP),
+			SOURCE_PSP,
+			lookup_id.clone(),
+			destination_payee.destination_bank_acc_holder_name,
+			destination_payee.destination_bank_acc_display_name
+		));
+		assert!(NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			&lookup_id,
+			b"SBININBB"
+		));
+		NexusPayee::on_initialize(101);
+		assert_eq!(NexusPayee::payee_lookup(&SOURCE_PSP, &lookup_id), None);
+	});
+}
+
+#[test]
+fn migration_runs_once() {
+	new_test_ext().execute_with(|| {
+		populate_old_layout();
+
+		NexusPayee::on_runtime_upgrade();
+		look_up(LOOKUP_ID);
+		NexusPayee::on_runtime_upgrade();
+
+		let lookup_id = migrations::legacy_lookup_id::<Test>(&SOURCE_PSP);
+		assert_eq!(
+			NexusPayee::payee_lookup(&SOURCE_PSP, &lookup_id).map(|lookup| lookup.payee),
+			Some(Some(payee()))
+		);
+		assert_eq!(
+			NexusPayee::erasure_queue(101),
+			vec![(SOURCE_PSP, lookup_id), (SOURCE_PSP, LOOKUP_ID.to_vec())]
+		);
+		assert!(NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			LOOKUP_ID,
+			b"SBININBB"
+		));
+	});
+}
+
+#[test]
+fn a_new_chain_is_not_migrated() {
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	GenesisBuild::<Test>::assimilate_storage(&crate::GenesisConfig::default(), &mut storage)
+		.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		look_up(LOOKUP_ID);
+
+		NexusPayee::on_runtime_upgrade();
+
+		assert!(NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			LOOKUP_ID,
+			b"SBININBB"
+		));
+		assert_eq!(NexusPayee::tombstone(SOURCE_PSP, 1), None);
+	});
+}
+
+/// Have `SOURCE_PSP` look up the payee as `lookup_id` and `DEST_PSP` answer it.
+fn look_up(lookup_id: &[u8]) {
+	let payee = payee();
+	assert_ok!(NexusPayee::confirmation_of_payee(
+		Origin::signed(SOURCE_PSP),
+		lookup_id.to_vec(),
+		payee.destination_country_id,
+		payee.destination_bank_identifier,
+		payee.destination_bank_account_number
+	));
+	let destination_payee = destination_payee();
+	assert_ok!(NexusPayee::confirm_subprocess(
+		Origin::signed(DEST_PSP),
+		SOURCE_PSP,
+		lookup_id.to_vec(),
+		destination_payee.destination_bank_acc_holder_name,
+		destination_payee.destination_bank_acc_display_name
+	));
+}
+
+fn tombstone(lookup_id: &[u8]) -> sp_core::H256 {
+	BlakeTwo256::hash_of(&(lookup_id, Some(payee()), Some(destination_payee())))
+}
+
+#[test]
+fn confirmation_of_payee_stores_the_payee() {
+	new_test_ext().execute_with(|| {
+		let payee = payee();
+		assert_ok!(NexusPayee::confirmation_of_payee(
+			Origin::signed(SOURCE_PSP),
+			LOOKUP_ID.to_vec(),
+			payee.destination_country_id.clone(),
+			payee.destination_bank_identifier.clone(),
+			payee.destination_bank_account_number.clone()
+		));
+
+		assert_eq!(NexusPayee::confirm_payee(SOURCE_PSP, LOOKUP_ID), payee);
+		assert_eq!(
+			NexusPayee::erasure_due(SOURCE_PSP, LOOKUP_ID),
+			Some(1 + RetentionPeriod::get())
+		);
+		assert_eq!(
+			NexusPayee::erasure_queue(1 + RetentionPeriod::get()),
+			vec![(SOURCE_PSP, LOOKUP_ID.to_vec())]
+		);
+		assert_eq!(
+			last_event(),
+			Event::PaymentConfirm(SOURCE_PSP, LOOKUP_ID.to_vec(), b"SBININBB".to_vec())
+		);
+	});
+}
+
+#[test]
+fn confirm_subprocess_stores_the_account_holder() {
+	new_test_ext().execute_with(|| {
+		look_up(LOOKUP_ID);
+
+		assert_eq!(
+			NexusPayee::sub_process(SOURCE_PSP, LOOKUP_ID),
+			destination_payee()
+		);
+		assert_eq!(
+			last_event(),
+			Event::SubProcessDone(
+				SOURCE_PSP,
+				LOOKUP_ID.to_vec(),
+				b"Asha Rao".to_vec(),
+				b"A. Rao".to_vec()
+			)
+		);
+		// Both halves of the lookup are erased together.
+		assert_eq!(
+			NexusPayee::erasure_queue(1 + RetentionPeriod::get()),
+			vec![(SOURCE_PSP, LOOKUP_ID.to_vec())]
+		);
+	});
+}
+
+#[test]
+fn confirm_subprocess_needs_a_lookup() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusPayee::confirm_subprocess(
+				Origin::signed(DEST_PSP),
+				SOURCE_PSP,
+				LOOKUP_ID.to_vec(),
+				b"Asha Rao".to_vec(),
+				b"A. Rao".to_vec()
+			),
+			Error::<Test>::LookupNotFound
+		);
+	});
+}
+
+#[test]
+fn only_the_psp_of_the_payee_bank_can_confirm_it() {
+	new_test_ext().execute_with(|| {
+		let payee = payee();
+		assert_ok!(NexusPayee::confirmation_of_payee(
+			Origin::signed(SOURCE_PSP),
+			LOOKUP_ID.to_vec(),
+			payee.destination_country_id,
+			payee.destination_bank_identifier,
+			payee.destination_bank_account_number
+		));
+
+		assert_noop!(
+			NexusPayee::confirm_subprocess(
+				Origin::signed(SOURCE_PSP),
+				SOURCE_PSP,
+				LOOKUP_ID.to_vec(),
+				b"Asha Rao".to_vec(),
+				b"A. Rao".to_vec()
+			),
+			Error::<Test>::NotDestinationBank
+		);
+	});
+}
+
+#[test]
+fn lookups_reject_long_fields() {
+	new_test_ext().execute_with(|| {
+		let long = vec![b'x'; MaxFieldLength::get() as usize + 1];
+
+		assert_noop!(
+			NexusPayee::confirmation_of_payee(
+				Origin::signed(SOURCE_PSP),
+				LOOKUP_ID.to_vec(),
+				b"IN".to_vec(),
+				b"SBININBB".to_vec(),
+				long.clone()
+			),
+			Error::<Test>::FieldTooLong
+		);
+		assert_noop!(
+			NexusPayee::confirm_subprocess(
+				Origin::signed(DEST_PSP),
+				SOURCE_PSP,
+				LOOKUP_ID.to_vec(),
+				long,
+				Vec::new()
+			),
+			Error::<Test>::FieldTooLong
+		);
+	});
+}
+
+#[test]
+fn a_payee_is_confirmed_once_the_account_holder_is_known() {
+	new_test_ext().execute_with(|| {
+		assert!(!NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			LOOKUP_ID,
+			b"SBININBB"
+		));
+
+		let payee = payee();
+		assert_ok!(NexusPayee::confirmation_of_payee(
+			Origin::signed(SOURCE_PSP),
+			LOOKUP_ID.to_vec(),
+			payee.destination_country_id,
+			payee.destination_bank_identifier,
+			payee.destination_bank_account_number
+		));
+		assert!(!NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			LOOKUP_ID,
+			b"SBININBB"
+		));
+
+		look_up(LOOKUP_ID);
+		assert!(NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			LOOKUP_ID,
+			b"SBININBB"
+		));
+		assert!(!NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			LOOKUP_ID,
+			b"HDFCINBB"
+		));
+		assert!(!NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			b"payment-2",
+			b"SBININBB"
+		));
+		assert!(!NexusPayee::is_confirmed(&DEST_PSP, LOOKUP_ID, b"SBININBB"));
+	});
+}
+
+#[test]
+fn looking_up_again_drops_the_answer() {
+	new_test_ext().execute_with(|| {
+		look_up(LOOKUP_ID);
+
+		assert_ok!(NexusPayee::confirmation_of_payee(
+			Origin::signed(SOURCE_PSP),
+			LOOKUP_ID.to_vec(),
+			b"IN".to_vec(),
+			b"SBININBB".to_vec(),
+			b"99999999".to_vec()
+		));
+
+		assert!(!NexusPayee::is_confirmed(
+			&SOURCE_PSP,
+			LOOKUP_ID,
+			b"SBININBB"
+		));
+	});
+}
+
+#[test]
+fn erase_payee_data_keeps_a_tombstone() {
+	new_test_ext().execute_with(|| {
+		look_up(LOOKUP_ID);
+
+		assert_ok!(NexusPayee::erase_payee_data(
+			Origin::signed(SOURCE_PSP),
+			LOOKUP_ID.to_vec()
+		));
+
+		assert!(!crate::ConfirmPayee::<Test>::contains_key(
+			SOURCE_PSP, LOOKUP_ID
+		));
+		assert!(!crate::SubProcess::<Test>::contains_key(
+			SOURCE_PSP, LOOKUP_ID
+		));
+		assert_eq!(NexusPayee::erasure_due(SOURCE_PSP, LOOKUP_ID), None);
+		assert_eq!(
+			NexusPayee::tombstone(SOURCE_PSP, 1),
+			Some(tombstone(LOOKUP_ID))
+		);
+		assert_eq!(
+			last_event(),
+			Event::PayeeDataErased(SOURCE_PSP, LOOKUP_ID.to_vec(), tombstone(LOOKUP_ID))
+		);
+	});
+}
+
+#[test]
+fn erase_payee_data_fails_without_a_lookup() {
+	new_test_ext().execute_with(|| {
+		look_up(LOOKUP_ID);
+
+		assert_noop!(
+			NexusPayee::erase_payee_data(Origin::signed(SOURCE_PSP), b"payment-2".to_vec()),
+			Error::<Test>::NothingToErase
+		);
+	});
+}
+
+#[test]
+fn lookups_are_erased_after_the_retention_period() {
+	new_test_ext().execute_with(|| {
+		look_up(LOOKUP_ID);
+		look_up(b"payment-2");
+		let due = 1 + RetentionPeriod::get();
+
+		NexusPayee::on_initialize(due - 1);
+		assert_eq!(NexusPayee::confirm_payee(SOURCE_PSP, LOOKUP_ID), payee());
+
+		System::set_block_number(due);
+		NexusPayee::on_initialize(due);
+		assert!(!crate::ConfirmPayee::<Test>::contains_key(
+			SOURCE_PSP, LOOKUP_ID
+		));
+		assert!(!crate::ConfirmPayee::<Test>::contains_key(
+			SOURCE_PSP,
+			b"payment-2".to_vec()
+		));
+		// Both lookups are folded into the tombstone of the block.
+		assert_eq!(
+			NexusPayee::tombstone(SOURCE_PSP, due),
+			Some(BlakeTwo256::hash_of(&(
+				tombstone(LOOKUP_ID),
+				tombstone(b"payment-2")
+			)))
+		);
+		assert!(NexusPayee::erasure_queue(due).is_empty());
+	});
+}
+
+#[test]
+fn erasures_beyond_the_limit_are_left_for_the_next_block() {
+	new_test_ext().execute_with(|| {
+		look_up(LOOKUP_ID);
+		look_up(b"payment-2");
+		look_up(b"payment-3");
+		let looked_up =
+			|lookup_id: &[u8]| crate::ConfirmPayee::<Test>::contains_key(SOURCE_PSP, lookup_id);
+		let due = 1 + RetentionPeriod::get();
+
+		System::set_block_number(due);
+		NexusPayee::on_initialize(due);
+		assert!(!looked_up(LOOKUP_ID) && !looked_up(b"payment-2"));
+		assert!(looked_up(b"payment-3"));
+		assert_eq!(
+			NexusPayee::erasure_queue(due + 1),
+			vec![(SOURCE_PSP, b"payment-3".to_vec())]
+		);
+
+		System::set_block_number(due + 1);
+		NexusPayee::on_initialize(due + 1);
+		assert!(!looked_up(b"payment-3"));
+		assert!(NexusPayee::erasure_queue(due + 1).is_empty());
+	});
+}
+
+#[test]
+fn payee_lookup_reports_a_lookup_until_it_is_erased() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(NexusPayee::payee_lookup(&SOURCE_PSP, LOOKUP_ID), None);
+
+		look_up(LOOKUP_ID);
+		assert_eq!(
+			NexusPayee::payee_lookup(&SOURCE_PSP, LOOKUP_ID),
+			Some(PayeeLookup {
+				payee: Some(payee()),
+				destination_payee: Some(destination_payee()),
+				confirmed: true,
+			})
+		);
+
+		assert_ok!(NexusPayee::erase_payee_data(
+			Origin::signed(SOURCE_PSP),
+			LOOKUP_ID.to_vec()
+		));
+		assert_eq!(NexusPayee::payee_lookup(&SOURCE_PSP, LOOKUP_ID), None);
+	});
+}
@@ -0,0 +1,75 @@
+//! Benchmarks for nexus-api-quote.
+//!
+//! Every byte field is as long as `MaxFieldLength` allows, which is the most expensive input.
+
+use super::*;
+
+use frame_benchmarking::benchmarks;
+use frame_support::traits::EnsureOrigin;
+use frame_system::RawOrigin;
+use sp_std::vec;
+
+fn field<T: Config>(byte: u8) -> Vec<u8> {
+	vec![byte; T::MaxFieldLength::get() as usize]
+}
+
+fn store_quote<T: Config>(fxp: &T::AccountId) -> DispatchResult {
+	Pallet::<T>::provide_rate(
+		RawOrigin::Signed(fxp.clone()).into(),
+		field::<T>(b'S'),
+		field::<T>(b'D'),
+		field::<T>(b'Q'),
+		field::<T>(b'F'),
+		field::<T>(b'0'),
+		field::<T>(b'0'),
+		field::<T>(b'0'),
+		true,
+		field::<T>(b'0'),
+		field::<T>(b'0'),
+	)
+	.map(|_| ())
+	.map_err(|e| e.error)
+}
+
+benchmarks! {
+	provide_rate {
+		let caller = T::FxpOrigin::ensure_origin(T::FxpOrigin::successful_origin())?;
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		field::<T>(b'S'),
+		field::<T>(b'D'),
+		field::<T>(b'Q'),
+		field::<T>(b'F'),
+		field::<T>(b'0'),
+		field::<T>(b'0'),
+		field::<T>(b'0'),
+		true,
+		field::<T>(b'0'),
+		field::<T>(b'0')
+	)
+	verify {
+		assert!(Terms::<T>::contains_key(&caller, field::<T>(b'Q')));
+	}
+
+	get_rate {
+		let caller = T::FxpOrigin::ensure_origin(T::FxpOrigin::successful_origin())?;
+		store_quote::<T>(&caller)?;
+	}: _(RawOrigin::Signed(caller), field::<T>(b'S'), field::<T>(b'D'), field::<T>(b'Q'))
+
+	delete_rate {
+		let caller = T::FxpOrigin::ensure_origin(T::FxpOrigin::successful_origin())?;
+		store_quote::<T>(&caller)?;
+	}: _(RawOrigin::Signed(caller.clone()), field::<T>(b'S'), field::<T>(b'D'), field::<T>(b'Q'))
+	verify {
+		assert!(!Terms::<T>::contains_key(&caller, field::<T>(b'Q')));
+	}
+
+	lock_quote {
+		let fxp = T::FxpOrigin::ensure_origin(T::FxpOrigin::successful_origin())?;
+		store_quote::<T>(&fxp)?;
+		let caller = T::SourcePspOrigin::ensure_origin(T::SourcePspOrigin::successful_origin())?;
+	}: _(RawOrigin::Signed(caller.clone()), fxp.clone(), field::<T>(b'Q'))
+	verify {
+		assert_eq!(Pallet::<T>::quote_terms(&fxp, field::<T>(b'Q')).and_then(|terms| terms.locked_for), Some(caller));
+	}
+}
@@ -1,13 +1,106 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Decode, Encode};
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult};
-use frame_system::ensure_signed;
+use nexus_support::ensure_field_lengths;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::traits::Saturating;
 
 use sp_std::prelude::*;
 
-pub trait Config: frame_system::Config {
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migrations;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// Why a quote cannot be paid with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QuoteError {
+	/// There is no quote with the given id.
+	NotFound,
+	/// The quote is no longer valid.
+	Expired,
+	/// The quote has not been locked by the sender.
+	NotLocked,
+}
+
+/// The terms of a quote a sender has locked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockedQuote<AccountId> {
+	pub fxp: AccountId,
+	pub source_currency: Vec<u8>,
+	pub destination_currency: Vec<u8>,
+	pub rate: Vec<u8>,
+}
+
+impl<AccountId> LockedQuote<AccountId> {
+	/// `amount` converted at the quoted rate, rounded down. `None` if the rate is not a
+	/// decimal number such as `1.0825` or the result does not fit.
+	pub fn convert(&self, amount: u128) -> Option<u128> {
+		convert_at_rate(&self.rate, amount)
+	}
+}
+
+/// `amount` converted at `rate`, a decimal number such as `1.0825`, rounded down. `None` if the
+/// rate is not such a number or the result does not fit.
+pub fn convert_at_rate(rate: &[u8], amount: u128) -> Option<u128> {
+	if rate.is_empty() {
+		return None;
+	}
+
+	let mut mantissa: u128 = 0;
+	let mut scale: u128 = 1;
+	let mut seen_point = false;
+	for &byte in rate {
+		match byte {
+			b'0'..=b'9' => {
+				mantissa = mantissa
+					.checked_mul(10)?
+					.checked_add((byte - b'0') as u128)?;
+				if seen_point {
+					scale = scale.checked_mul(10)?;
+				}
+			}
+			b'.' if !seen_point => seen_point = true,
+			_ => return None,
+		}
+	}
+
+	amount.checked_mul(mantissa)?.checked_div(scale)
+}
+
+/// A quote anyone may lock, as returned by the `NexusQuoteApi` runtime API.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Debug, Eq, PartialEq)]
+pub struct QuoteInfo<AccountId, BlockNumber> {
+	pub quote_uuid: Vec<u8>,
+	pub fxp: AccountId,
+	pub source_currency: Vec<u8>,
+	pub destination_currency: Vec<u8>,
+	pub rate: Vec<u8>,
+	pub source_lp: Vec<u8>,
+	pub destination_lp: Vec<u8>,
+	pub expires_at: BlockNumber,
+	/// The amount asked about converted at the rate, if the rate can be read.
+	pub destination_amount: Option<u128>,
+}
+
+/// Lets other pallets check the quote a payment refers to.
+pub trait QuoteProvider<AccountId> {
+	/// The quote `quote_uuid` of `fxp` if it is still valid and locked by `sender`.
+	fn locked_quote(
+		fxp: &AccountId,
+		quote_uuid: &[u8],
+		sender: &AccountId,
+	) -> Result<LockedQuote<AccountId>, QuoteError>;
 }
 
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
@@ -22,97 +115,439 @@ pub struct Quote {
 	fxp_uuid: Vec<u8>,
 }
 
-decl_storage! {
-	trait Store for Module<T: Config> as NexusApiQuote {
-		ProvideRates get(fn update_api): map hasher(blake2_128_concat) (Vec<u8>, Vec<u8>, Vec<u8> ,T::AccountId) => Quote;
+/// Where a quote can be found and who may pay with it.
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct QuoteTerms<AccountId, BlockNumber> {
+	source_currency: Vec<u8>,
+	destination_currency: Vec<u8>,
+	fxp: AccountId,
+	expires_at: BlockNumber,
+	locked_for: Option<AccountId>,
+}
+
+/// Storage layouts of this pallet, for migrations.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+enum Releases {
+	V1,
+	V2TermsByFxp,
+	V3QuotesByPair,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
 	}
 }
 
-decl_event!(
-	pub enum Event<T>
-	where
-		AccountId = <T as frame_system::Config>::AccountId,
-	{
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Who may provide and delete quotes.
+		type FxpOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+		/// Who may lock a quote to pay with it.
+		type SourcePspOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+		/// Number of blocks a quote stays valid after it has been provided.
+		#[pallet::constant]
+		type QuoteLifetime: Get<Self::BlockNumber>;
+
+		/// The longest any byte field of a quote may be.
+		#[pallet::constant]
+		type MaxFieldLength: Get<u32>;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	#[pallet::getter(fn update_api)]
+	pub(super) type ProvideRates<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(Vec<u8>, Vec<u8>, Vec<u8>, T::AccountId),
+		Quote,
+		ValueQuery,
+	>;
+
+	/// The terms of the quotes, by FXP and quote id. Quote ids are only unique per FXP.
+	#[pallet::storage]
+	#[pallet::getter(fn quote_terms)]
+	pub(super) type Terms<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		Vec<u8>,
+		QuoteTerms<T::AccountId, T::BlockNumber>,
+	>;
+
+	/// The quotes of each currency pair, by FXP and quote id.
+	#[pallet::storage]
+	pub(super) type QuotesByPair<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(Vec<u8>, Vec<u8>),
+		Blake2_128Concat,
+		(T::AccountId, Vec<u8>),
+		(),
+	>;
+
+	#[pallet::storage]
+	pub(super) type StorageVersion<T> = StorageValue<_, Releases, ValueQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(Default)]
+	pub struct GenesisConfig {}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		/// A new chain starts out with the latest storage layout, so no migration runs on it.
+		fn build(&self) {
+			<StorageVersion<T>>::put(Releases::V3QuotesByPair);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", T::BlockNumber = "BlockNumber")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
 		/// FXP has submitted the quote for the given currencies.
-		RatesProvided(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>),
+		/// [source_currency, destination_currency, fxp, quote_uuid, rate]
+		RatesProvided(Vec<u8>, Vec<u8>, T::AccountId, Vec<u8>, Vec<u8>),
 
 		/// Source Bank is retriving the quote for the given currencies.
 		RatesRequested(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>),
 
 		/// FXP has deleted the quote for the given currencies.
-		RatesDeleted(Vec<u8>, Vec<u8>, AccountId, Vec<u8>),
-	}
-);
+		RatesDeleted(Vec<u8>, Vec<u8>, T::AccountId, Vec<u8>),
 
-decl_error! {
-	pub enum Error for Module<T: Config> {
+		/// Source PSP has locked a quote to pay with it. [fxp, quote_uuid, source_psp, expires_at]
+		QuoteLocked(T::AccountId, Vec<u8>, T::AccountId, T::BlockNumber),
+	}
 
+	#[pallet::error]
+	pub enum Error<T> {
 		/// The value cannot be incremented further because it has reached the maximum allowed value
 		MaxValueReached,
 
 		/// There is no quote for the given currencies
 		NoQuote,
+
+		/// The quote is no longer valid
+		QuoteExpired,
+
+		/// The quote has already been locked by another PSP
+		QuoteLocked,
+
+		/// A field is longer than `MaxFieldLength`
+		FieldTooLong,
+
+		/// The quote id belongs to a quote that is locked or has not expired
+		QuoteIdInUse,
 	}
-}
 
-decl_module! {
-	pub struct Module<T: Config> for enum Call where origin: T::Origin {
-
-		// Initialize errors
-		type Error = Error<T>;
-
-		// Initialize events
-		fn deposit_event() = default;
-
-		#[weight= 10_000_000]
-		fn provide_rate(origin, source_currency:Vec<u8>, destination_currency: Vec<u8>, quote_uuid: Vec<u8> , fxp_uuid: Vec<u8>, source_lp: Vec<u8>, destination_lp: Vec<u8>, rate: Vec<u8>, public: bool, timestamp: Vec<u8>, source_bank_id: Vec<u8>) -> DispatchResult {
-				let user = ensure_signed(origin)?;
-				let rate_clone = rate.clone();
-				let quote_uuid_clone = quote_uuid.clone();
-				let quote = Quote {
-					source_lp,
-					destination_lp,
-					rate,
-					public,
-					timestamp,
-					source_bank_id,
-					quote_uuid,
-					fxp_uuid,
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
+
+			if <StorageVersion<T>>::get() == Releases::V1 {
+				weight = weight.saturating_add(migrations::key_terms_by_fxp::<T>());
+				<StorageVersion<T>>::put(Releases::V2TermsByFxp);
+			}
+
+			if <StorageVersion<T>>::get() == Releases::V2TermsByFxp {
+				weight = weight.saturating_add(migrations::index_quotes_by_pair::<T>());
+				<StorageVersion<T>>::put(Releases::V3QuotesByPair);
+			}
+
+			weight
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Quote `rate` under `quote_uuid`. The id may only be reused once the quote it was given
+		/// to has expired without being locked.
+		#[pallet::weight(T::WeightInfo::provide_rate())]
+		pub fn provide_rate(
+			origin: OriginFor<T>,
+			source_currency: Vec<u8>,
+			destination_currency: Vec<u8>,
+			quote_uuid: Vec<u8>,
+			fxp_uuid: Vec<u8>,
+			source_lp: Vec<u8>,
+			destination_lp: Vec<u8>,
+			rate: Vec<u8>,
+			public: bool,
+			timestamp: Vec<u8>,
+			source_bank_id: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::FxpOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[
+					&source_currency,
+					&destination_currency,
+					&quote_uuid,
+					&fxp_uuid,
+					&source_lp,
+					&destination_lp,
+					&rate,
+					&timestamp,
+					&source_bank_id,
+				],
+				Error::<T>::FieldTooLong,
+			)?;
+			let now = <frame_system::Module<T>>::block_number();
+			let previous = Self::quote_terms(&user, &quote_uuid);
+			ensure!(
+				previous
+					.as_ref()
+					.map_or(true, |terms| terms.locked_for.is_none()
+						&& now >= terms.expires_at),
+				Error::<T>::QuoteIdInUse
+			);
+			let rate_clone = rate.clone();
+			let quote_uuid_clone = quote_uuid.clone();
+			let terms = QuoteTerms {
+				source_currency: source_currency.clone(),
+				destination_currency: destination_currency.clone(),
+				fxp: user.clone(),
+				expires_at: now.saturating_add(T::QuoteLifetime::get()),
+				locked_for: None,
+			};
+			let quote = Quote {
+				source_lp,
+				destination_lp,
+				rate,
+				public,
+				timestamp,
+				source_bank_id,
+				quote_uuid,
+				fxp_uuid,
 			};
 
-			<ProvideRates<T>>::insert((&source_currency, &destination_currency,&quote_uuid_clone ,&user), quote);
-			Self::deposit_event(RawEvent::RatesProvided(source_currency, destination_currency, quote_uuid_clone, rate_clone));
-			Ok(())
+			<ProvideRates<T>>::insert(
+				(
+					&source_currency,
+					&destination_currency,
+					&quote_uuid_clone,
+					&user,
+				),
+				quote,
+			);
+			if let Some(previous) = previous {
+				<QuotesByPair<T>>::remove(
+					(previous.source_currency, previous.destination_currency),
+					(&user, &quote_uuid_clone),
+				);
+			}
+			<QuotesByPair<T>>::insert(
+				(&source_currency, &destination_currency),
+				(&user, &quote_uuid_clone),
+				(),
+			);
+			<Terms<T>>::insert(&user, &quote_uuid_clone, terms);
+			Self::deposit_event(Event::RatesProvided(
+				source_currency,
+				destination_currency,
+				user,
+				quote_uuid_clone,
+				rate_clone,
+			));
+			Ok(().into())
+		}
+
+		#[pallet::weight(T::WeightInfo::get_rate())]
+		pub fn get_rate(
+			origin: OriginFor<T>,
+			source_currency: Vec<u8>,
+			destination_currency: Vec<u8>,
+			quote_uuid: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+
+			let (source_currency_clone, destination_currency_clone) =
+				(source_currency.clone(), destination_currency.clone());
+
+			let origin_account = (
+				source_currency,
+				destination_currency,
+				quote_uuid,
+				user.clone(),
+			);
+
+			let quote = <ProvideRates<T>>::get(&origin_account);
+
+			Self::deposit_event(Event::RatesRequested(
+				source_currency_clone,
+				destination_currency_clone,
+				quote.quote_uuid,
+				quote.fxp_uuid,
+				quote.rate,
+			));
+			Ok(().into())
 		}
 
-		#[weight= 10_000_000]
-		fn get_rate(origin, source_currency: Vec<u8>, destination_currency: Vec<u8>, quote_uuid:Vec<u8>) -> DispatchResult {
-				let user = ensure_signed(origin)?;
+		/// Withdraw the quote `quote_uuid`, unless a PSP has locked it and it has not expired.
+		#[pallet::weight(T::WeightInfo::delete_rate())]
+		pub fn delete_rate(
+			origin: OriginFor<T>,
+			source_currency: Vec<u8>,
+			destination_currency: Vec<u8>,
+			quote_uuid: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::FxpOrigin::ensure_origin(origin)?;
+			let now = <frame_system::Module<T>>::block_number();
+			ensure!(
+				Self::quote_terms(&user, &quote_uuid)
+					.map_or(true, |terms| terms.locked_for.is_none()
+						|| now >= terms.expires_at),
+				Error::<T>::QuoteLocked
+			);
 
-				let (source_currency_clone, destination_currency_clone) = (source_currency.clone(), destination_currency.clone());
+			let quote_uuid_clone = quote_uuid.clone();
 
-				let origin_account = (source_currency, destination_currency, quote_uuid, user.clone());
+			let (source_currency_clone, destination_currency_clone) =
+				(source_currency.clone(), destination_currency.clone());
 
-				let quote = <ProvideRates<T>>::get(&origin_account);
+			let origin_account = (
+				source_currency,
+				destination_currency,
+				quote_uuid,
+				user.clone(),
+			);
 
-				Self::deposit_event(RawEvent::RatesRequested(source_currency_clone,destination_currency_clone, quote.quote_uuid, quote.fxp_uuid, quote.rate));
-				Ok(())
+			<ProvideRates<T>>::take(&origin_account);
+			if let Some(terms) = <Terms<T>>::take(&user, &quote_uuid_clone) {
+				<QuotesByPair<T>>::remove(
+					(terms.source_currency, terms.destination_currency),
+					(&user, &quote_uuid_clone),
+				);
 			}
+			Self::deposit_event(Event::RatesDeleted(
+				source_currency_clone,
+				destination_currency_clone,
+				user,
+				quote_uuid_clone,
+			));
+			Ok(().into())
+		}
+
+		/// Lock the quote `quote_uuid` of `fxp` so that only the sender can pay with it until it
+		/// expires.
+		#[pallet::weight(T::WeightInfo::lock_quote())]
+		pub fn lock_quote(
+			origin: OriginFor<T>,
+			fxp: T::AccountId,
+			quote_uuid: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::SourcePspOrigin::ensure_origin(origin)?;
+
+			let mut terms = Self::quote_terms(&fxp, &quote_uuid).ok_or(Error::<T>::NoQuote)?;
+			ensure!(
+				<frame_system::Module<T>>::block_number() < terms.expires_at,
+				Error::<T>::QuoteExpired
+			);
+			ensure!(
+				terms.locked_for.as_ref().map_or(true, |psp| *psp == user),
+				Error::<T>::QuoteLocked
+			);
 
-		#[weight= 10_000_000]
-		fn delete_rate(origin, source_currency: Vec<u8>, destination_currency: Vec<u8>, quote_uuid: Vec<u8>) -> DispatchResult {
-				let user = ensure_signed(origin)?;
+			let expires_at = terms.expires_at;
+			terms.locked_for = Some(user.clone());
+			<Terms<T>>::insert(&fxp, &quote_uuid, terms);
 
-				let quote_uuid_clone = quote_uuid.clone();
+			Self::deposit_event(Event::QuoteLocked(fxp, quote_uuid, user, expires_at));
 
-				let (source_currency_clone, destination_currency_clone) = (source_currency.clone(), destination_currency.clone());
+			Ok(().into())
+		}
+	}
+}
 
-				let origin_account = (source_currency, destination_currency, quote_uuid,user.clone());
+impl<T: Config> Pallet<T> {
+	/// The public quotes from `source_currency` into `destination_currency` that are still valid
+	/// and not locked by anyone, with `amount` converted at each rate.
+	pub fn open_quotes(
+		source_currency: &[u8],
+		destination_currency: &[u8],
+		amount: u128,
+	) -> Vec<QuoteInfo<T::AccountId, T::BlockNumber>> {
+		let now = <frame_system::Module<T>>::block_number();
 
+		<QuotesByPair<T>>::iter_prefix((source_currency.to_vec(), destination_currency.to_vec()))
+			.filter_map(|((fxp, quote_uuid), _)| {
+				let terms = Self::quote_terms(&fxp, &quote_uuid)?;
+				if now >= terms.expires_at || terms.locked_for.is_some() {
+					return None;
+				}
 
-				<ProvideRates<T>>::take(&origin_account);
-			Self::deposit_event(RawEvent::RatesDeleted(source_currency_clone, destination_currency_clone, user, quote_uuid_clone));
-			Ok(())
+				let keys = (
+					terms.source_currency.clone(),
+					terms.destination_currency.clone(),
+					quote_uuid.clone(),
+					terms.fxp.clone(),
+				);
+				let quote = <ProvideRates<T>>::get(&keys);
+				if !quote.public {
+					return None;
+				}
+
+				Some(QuoteInfo {
+					destination_amount: convert_at_rate(&quote.rate, amount),
+					quote_uuid,
+					fxp: terms.fxp,
+					source_currency: terms.source_currency,
+					destination_currency: terms.destination_currency,
+					rate: quote.rate,
+					source_lp: quote.source_lp,
+					destination_lp: quote.destination_lp,
+					expires_at: terms.expires_at,
+				})
+			})
+			.collect()
 	}
 }
+
+impl<T: Config> QuoteProvider<T::AccountId> for Pallet<T> {
+	fn locked_quote(
+		fxp: &T::AccountId,
+		quote_uuid: &[u8],
+		sender: &T::AccountId,
+	) -> Result<LockedQuote<T::AccountId>, QuoteError> {
+		let terms = Self::quote_terms(fxp, quote_uuid).ok_or(QuoteError::NotFound)?;
+		if <frame_system::Module<T>>::block_number() >= terms.expires_at {
+			return Err(QuoteError::Expired);
+		}
+		if terms.locked_for.as_ref() != Some(sender) {
+			return Err(QuoteError::NotLocked);
+		}
+
+		let keys = (
+			terms.source_currency.clone(),
+			terms.destination_currency.clone(),
+			quote_uuid.to_vec(),
+			terms.fxp.clone(),
+		);
+		Ok(LockedQuote {
+			fxp: terms.fxp,
+			source_currency: terms.source_currency,
+			destination_currency: terms.destination_currency,
+			rate: <ProvideRates<T>>::get(&keys).rate,
+		})
+	}
 }
@@ -0,0 +1,43 @@
+//! Storage migrations of the quote pallet.
+
+use super::*;
+use frame_support::{
+	storage::{migration::storage_key_iter, StoragePrefixedMap},
+	traits::Get,
+	weights::Weight,
+	Blake2_128Concat,
+};
+
+/// Key the quote terms by FXP and quote instead of by quote alone, as quote ids are only unique
+/// per FXP. The FXP is taken from each record.
+pub fn key_terms_by_fxp<T: Config>() -> Weight {
+	let terms = storage_key_iter::<
+		Vec<u8>,
+		QuoteTerms<T::AccountId, T::BlockNumber>,
+		Blake2_128Concat,
+	>(Terms::<T>::module_prefix(), Terms::<T>::storage_prefix())
+	.drain()
+	.collect::<Vec<_>>();
+
+	let count = terms.len() as Weight;
+	for (quote_uuid, terms) in terms {
+		<Terms<T>>::insert(terms.fxp.clone(), quote_uuid, terms);
+	}
+
+	T::DbWeight::get().reads_writes(count, count * 2 + 1)
+}
+
+/// Index the quotes provided before `QuotesByPair` existed by their currency pair.
+pub fn index_quotes_by_pair<T: Config>() -> Weight {
+	let mut count = 0;
+	for (fxp, quote_uuid, terms) in <Terms<T>>::iter() {
+		<QuotesByPair<T>>::insert(
+			(terms.source_currency, terms.destination_currency),
+			(fxp, quote_uuid),
+			(),
+		);
+		count += 1;
+	}
+
+	T::DbWeight::get().reads_writes(count, count + 1)
+}
@@ -0,0 +1,489 @@
+use crate::{
+	mock::*, Error, Event, LockedQuote, Quote, QuoteError, QuoteInfo, QuoteProvider, QuoteTerms,
+	Releases,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::put_storage_value,
+	traits::{GenesisBuild, Get, OnRuntimeUpgrade},
+	Blake2_128Concat, StorageHasher,
+};
+
+const FXP: u64 = 1;
+const SOURCE_PSP: u64 = 2;
+
+fn quote() -> Quote {
+	Quote {
+		source_lp: b"LP-SG".to_vec(),
+		destination_lp: b"LP-IN".to_vec(),
+		rate: b"60.5".to_vec(),
+		public: true,
+		timestamp: b"2021-03-01T10:00:00Z".to_vec(),
+		source_bank_id: b"DBSSSGSG".to_vec(),
+		quote_uuid: b"q-1".to_vec(),
+		fxp_uuid: b"fxp-1".to_vec(),
+	}
+}
+
+fn rates_key() -> Vec<u8> {
+	Blake2_128Concat::hash(&(b"SGD".to_vec(), b"INR".to_vec(), b"q-1".to_vec(), FXP).encode())
+}
+
+/// Write a quote as it was stored before its terms were keyed by FXP.
+fn populate_old_layout() {
+	put_storage_value(b"NexusQuote", b"ProvideRates", &rates_key(), quote());
+	put_storage_value(
+		b"NexusQuote",
+		b"Terms",
+		&Blake2_128Concat::hash(&b"q-1".to_vec().encode()),
+		QuoteTerms::<u64, u64> {
+			source_currency: b"SGD".to_vec(),
+			destination_currency: b"INR".to_vec(),
+			fxp: FXP,
+			expires_at: 11,
+			locked_for: Some(SOURCE_PSP),
+		},
+	);
+}
+
+#[test]
+fn migration_keys_quote_terms_by_fxp() {
+	new_test_ext().execute_with(|| {
+		populate_old_layout();
+
+		NexusQuote::on_runtime_upgrade();
+
+		assert_eq!(
+			NexusQuote::update_api((b"SGD".to_vec(), b"INR".to_vec(), b"q-1".to_vec(), FXP)),
+			quote()
+		);
+		let locked = NexusQuote::locked_quote(&FXP, b"q-1", &SOURCE_PSP).unwrap();
+		assert_eq!(locked.fxp, FXP);
+		assert_eq!(locked.rate, b"60.5".to_vec());
+	});
+}
+
+#[test]
+fn migration_leaves_migrated_quotes_alone() {
+	new_test_ext().execute_with(|| {
+		populate_old_layout();
+
+		NexusQuote::on_runtime_upgrade();
+		NexusQuote::on_runtime_upgrade();
+
+		assert!(NexusQuote::quote_terms(FXP, b"q-1".to_vec()).is_some());
+		assert_eq!(
+			NexusQuote::update_api((b"SGD".to_vec(), b"INR".to_vec(), b"q-1".to_vec(), FXP)),
+			quote()
+		);
+	});
+}
+
+#[test]
+fn migration_indexes_quotes_by_pair() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+		crate::QuotesByPair::<Test>::remove(
+			(b"SGD".to_vec(), b"INR".to_vec()),
+			(FXP, b"q-1".to_vec()),
+		);
+		crate::StorageVersion::<Test>::put(Releases::V2TermsByFxp);
+		assert!(NexusQuote::open_quotes(b"SGD", b"INR", 1_000).is_empty());
+
+		NexusQuote::on_runtime_upgrade();
+
+		assert_eq!(NexusQuote::open_quotes(b"SGD", b"INR", 1_000).len(), 1);
+		assert_eq!(
+			crate::StorageVersion::<Test>::get(),
+			Releases::V3QuotesByPair
+		);
+	});
+}
+
+#[test]
+fn a_new_chain_is_not_migrated() {
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	GenesisBuild::<Test>::assimilate_storage(&crate::GenesisConfig::default(), &mut storage)
+		.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(provide(FXP, quote()));
+
+		NexusQuote::on_runtime_upgrade();
+
+		assert!(NexusQuote::quote_terms(FXP, b"q-1".to_vec()).is_some());
+	});
+}
+
+fn provide(fxp: u64, quote: Quote) -> frame_support::dispatch::DispatchResultWithPostInfo {
+	NexusQuote::provide_rate(
+		Origin::signed(fxp),
+		b"SGD".to_vec(),
+		b"INR".to_vec(),
+		quote.quote_uuid,
+		quote.fxp_uuid,
+		quote.source_lp,
+		quote.destination_lp,
+		quote.rate,
+		quote.public,
+		quote.timestamp,
+		quote.source_bank_id,
+	)
+}
+
+#[test]
+fn provide_rate_stores_the_quote_and_its_terms() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+
+		assert_eq!(
+			NexusQuote::update_api((b"SGD".to_vec(), b"INR".to_vec(), b"q-1".to_vec(), FXP)),
+			quote()
+		);
+		let terms = NexusQuote::quote_terms(FXP, b"q-1".to_vec()).unwrap();
+		assert_eq!(terms.fxp, FXP);
+		assert_eq!(terms.expires_at, 1 + QuoteLifetime::get());
+		assert_eq!(terms.locked_for, None);
+		assert_eq!(
+			last_event(),
+			Event::RatesProvided(
+				b"SGD".to_vec(),
+				b"INR".to_vec(),
+				FXP,
+				b"q-1".to_vec(),
+				b"60.5".to_vec()
+			)
+		);
+	});
+}
+
+#[test]
+fn provide_rate_reuses_a_quote_id_once_its_quote_expires() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+
+		let mut updated = quote();
+		updated.rate = b"61".to_vec();
+		assert_noop!(provide(FXP, updated.clone()), Error::<Test>::QuoteIdInUse);
+
+		System::set_block_number(1 + QuoteLifetime::get());
+		assert_ok!(provide(FXP, updated.clone()));
+		assert_eq!(
+			NexusQuote::update_api((b"SGD".to_vec(), b"INR".to_vec(), b"q-1".to_vec(), FXP)),
+			updated
+		);
+	});
+}
+
+#[test]
+fn locked_quotes_cannot_be_replaced_or_deleted() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+		assert_ok!(NexusQuote::lock_quote(
+			Origin::signed(SOURCE_PSP),
+			FXP,
+			b"q-1".to_vec()
+		));
+		let delete = || {
+			NexusQuote::delete_rate(
+				Origin::signed(FXP),
+				b"SGD".to_vec(),
+				b"INR".to_vec(),
+				b"q-1".to_vec(),
+			)
+		};
+
+		assert_noop!(provide(FXP, quote()), Error::<Test>::QuoteIdInUse);
+		assert_noop!(delete(), Error::<Test>::QuoteLocked);
+
+		System::set_block_number(1 + QuoteLifetime::get());
+		assert_noop!(provide(FXP, quote()), Error::<Test>::QuoteIdInUse);
+		assert_eq!(
+			NexusQuote::quote_terms(FXP, b"q-1".to_vec())
+				.unwrap()
+				.locked_for,
+			Some(SOURCE_PSP)
+		);
+		assert_ok!(delete());
+	});
+}
+
+#[test]
+fn quote_ids_are_only_unique_per_fxp() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+		assert_ok!(provide(
+			3,
+			Quote {
+				rate: b"61".to_vec(),
+				..quote()
+			}
+		));
+		assert_ok!(NexusQuote::lock_quote(
+			Origin::signed(SOURCE_PSP),
+			3,
+			b"q-1".to_vec()
+		));
+
+		assert_eq!(
+			NexusQuote::locked_quote(&FXP, b"q-1", &SOURCE_PSP),
+			Err(QuoteError::NotLocked)
+		);
+		assert_eq!(
+			NexusQuote::locked_quote(&3, b"q-1", &SOURCE_PSP)
+				.unwrap()
+				.rate,
+			b"61".to_vec()
+		);
+	});
+}
+
+#[test]
+fn provide_rate_rejects_long_fields() {
+	new_test_ext().execute_with(|| {
+		let mut long = quote();
+		long.rate = vec![b'1'; MaxFieldLength::get() as usize + 1];
+
+		assert_noop!(provide(FXP, long), Error::<Test>::FieldTooLong);
+	});
+}
+
+#[test]
+fn get_rate_reports_the_callers_quote() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+
+		assert_ok!(NexusQuote::get_rate(
+			Origin::signed(FXP),
+			b"SGD".to_vec(),
+			b"INR".to_vec(),
+			b"q-1".to_vec()
+		));
+
+		assert_eq!(
+			last_event(),
+			Event::RatesRequested(
+				b"SGD".to_vec(),
+				b"INR".to_vec(),
+				b"q-1".to_vec(),
+				b"fxp-1".to_vec(),
+				b"60.5".to_vec()
+			)
+		);
+	});
+}
+
+#[test]
+fn delete_rate_removes_the_quote_and_its_terms() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+
+		assert_ok!(NexusQuote::delete_rate(
+			Origin::signed(FXP),
+			b"SGD".to_vec(),
+			b"INR".to_vec(),
+			b"q-1".to_vec()
+		));
+
+		assert_eq!(
+			NexusQuote::update_api((b"SGD".to_vec(), b"INR".to_vec(), b"q-1".to_vec(), FXP)),
+			Quote::default()
+		);
+		assert!(NexusQuote::quote_terms(FXP, b"q-1".to_vec()).is_none());
+		assert_eq!(
+			last_event(),
+			Event::RatesDeleted(b"SGD".to_vec(), b"INR".to_vec(), FXP, b"q-1".to_vec())
+		);
+	});
+}
+
+#[test]
+fn delete_rate_keeps_another_fxps_terms() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+
+		assert_ok!(NexusQuote::delete_rate(
+			Origin::signed(3),
+			b"SGD".to_vec(),
+			b"INR".to_vec(),
+			b"q-1".to_vec()
+		));
+
+		assert!(NexusQuote::quote_terms(FXP, b"q-1".to_vec()).is_some());
+	});
+}
+
+#[test]
+fn lock_quote_reserves_the_quote_for_the_sender() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+
+		assert_ok!(NexusQuote::lock_quote(
+			Origin::signed(SOURCE_PSP),
+			FXP,
+			b"q-1".to_vec()
+		));
+		// Locking again is harmless.
+		assert_ok!(NexusQuote::lock_quote(
+			Origin::signed(SOURCE_PSP),
+			FXP,
+			b"q-1".to_vec()
+		));
+
+		assert_eq!(
+			NexusQuote::quote_terms(FXP, b"q-1".to_vec())
+				.unwrap()
+				.locked_for,
+			Some(SOURCE_PSP)
+		);
+		assert_eq!(
+			last_event(),
+			Event::QuoteLocked(FXP, b"q-1".to_vec(), SOURCE_PSP, 1 + QuoteLifetime::get())
+		);
+	});
+}
+
+#[test]
+fn lock_quote_fails_for_unknown_expired_and_locked_quotes() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusQuote::lock_quote(Origin::signed(SOURCE_PSP), FXP, b"q-1".to_vec()),
+			Error::<Test>::NoQuote
+		);
+
+		assert_ok!(provide(FXP, quote()));
+		assert_ok!(NexusQuote::lock_quote(
+			Origin::signed(SOURCE_PSP),
+			FXP,
+			b"q-1".to_vec()
+		));
+		assert_noop!(
+			NexusQuote::lock_quote(Origin::signed(3), FXP, b"q-1".to_vec()),
+			Error::<Test>::QuoteLocked
+		);
+
+		System::set_block_number(1 + QuoteLifetime::get());
+		assert_noop!(
+			NexusQuote::lock_quote(Origin::signed(SOURCE_PSP), FXP, b"q-1".to_vec()),
+			Error::<Test>::QuoteExpired
+		);
+	});
+}
+
+#[test]
+fn locked_quote_is_only_available_to_its_sender_until_expiry() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			NexusQuote::locked_quote(&FXP, b"q-1", &SOURCE_PSP),
+			Err(QuoteError::NotFound)
+		);
+
+		assert_ok!(provide(FXP, quote()));
+		assert_eq!(
+			NexusQuote::locked_quote(&FXP, b"q-1", &SOURCE_PSP),
+			Err(QuoteError::NotLocked)
+		);
+
+		assert_ok!(NexusQuote::lock_quote(
+			Origin::signed(SOURCE_PSP),
+			FXP,
+			b"q-1".to_vec()
+		));
+		let locked = NexusQuote::locked_quote(&FXP, b"q-1", &SOURCE_PSP).unwrap();
+		assert_eq!(locked.source_currency, b"SGD".to_vec());
+		assert_eq!(locked.destination_currency, b"INR".to_vec());
+		assert_eq!(
+			NexusQuote::locked_quote(&FXP, b"q-1", &3),
+			Err(QuoteError::NotLocked)
+		);
+
+		System::set_block_number(1 + QuoteLifetime::get());
+		assert_eq!(
+			NexusQuote::locked_quote(&FXP, b"q-1", &SOURCE_PSP),
+			Err(QuoteError::Expired)
+		);
+	});
+}
+
+#[test]
+fn locked_quotes_convert_at_their_rate() {
+	let quote = |rate: &[u8]| LockedQuote {
+		fxp: FXP,
+		source_currency: b"SGD".to_vec(),
+		destination_currency: b"INR".to_vec(),
+		rate: rate.to_vec(),
+	};
+
+	assert_eq!(quote(b"60.5").convert(1_000), Some(60_500));
+	assert_eq!(quote(b"2").convert(1_000), Some(2_000));
+	assert_eq!(quote(b"0.125").convert(1_000), Some(125));
+	assert_eq!(quote(b"").convert(1_000), None);
+	assert_eq!(quote(b"6,5").convert(1_000), None);
+	assert_eq!(quote(b"1.2.3").convert(1_000), None);
+}
+
+#[test]
+fn open_quotes_lists_public_quotes_anyone_may_lock() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+		assert_ok!(provide(
+			FXP,
+			Quote {
+				public: false,
+				quote_uuid: b"q-private".to_vec(),
+				..quote()
+			}
+		));
+		assert_ok!(provide(
+			FXP,
+			Quote {
+				quote_uuid: b"q-locked".to_vec(),
+				..quote()
+			}
+		));
+		assert_ok!(NexusQuote::lock_quote(
+			Origin::signed(SOURCE_PSP),
+			FXP,
+			b"q-locked".to_vec()
+		));
+
+		assert_eq!(
+			NexusQuote::open_quotes(b"SGD", b"INR", 1_000),
+			vec![QuoteInfo {
+				quote_uuid: b"q-1".to_vec(),
+				fxp: FXP,
+				source_currency: b"SGD".to_vec(),
+				destination_currency: b"INR".to_vec(),
+				rate: b"60.5".to_vec(),
+				source_lp: b"LP-SG".to_vec(),
+				destination_lp: b"LP-IN".to_vec(),
+				expires_at: 1 + QuoteLifetime::get(),
+				destination_amount: Some(60_500),
+			}]
+		);
+		assert!(NexusQuote::open_quotes(b"INR", b"SGD", 1_000).is_empty());
+
+		System::set_block_number(1 + QuoteLifetime::get());
+		assert!(NexusQuote::open_quotes(b"SGD", b"INR", 1_000).is_empty());
+	});
+}
+
+#[test]
+fn deleted_quotes_are_no_longer_open() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(provide(FXP, quote()));
+		assert_ok!(NexusQuote::delete_rate(
+			Origin::signed(FXP),
+			b"SGD".to_vec(),
+			b"INR".to_vec(),
+			b"q-1".to_vec()
+		));
+
+		assert!(NexusQuote::open_quotes(b"SGD", b"INR", 1_000).is_empty());
+		assert_eq!(crate::QuotesByPair::<Test>::iter().count(), 0);
+	});
+}
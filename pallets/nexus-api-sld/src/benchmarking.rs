@@ -0,0 +1,62 @@
+//! Benchmarks for nexus-api-sld.
+//!
+//! Every byte field is as long as `MaxFieldLength` allows, which is the most expensive input.
+
+use super::*;
+
+use frame_benchmarking::benchmarks;
+use frame_support::traits::EnsureOrigin;
+use frame_system::RawOrigin;
+use sp_std::vec;
+
+fn field<T: Config>() -> Vec<u8> {
+	vec![b'0'; T::MaxFieldLength::get() as usize]
+}
+
+fn store_sld<T: Config>(ips: &T::AccountId) -> DispatchResult {
+	Pallet::<T>::set_info(
+		RawOrigin::Signed(ips.clone()).into(),
+		true,
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+		true,
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+		true,
+		true,
+		field::<T>(),
+	)
+	.map(|_| ())
+	.map_err(|e| e.error)
+}
+
+benchmarks! {
+	set_info {
+		let caller = T::IpsOrigin::ensure_origin(T::IpsOrigin::successful_origin())?;
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		true,
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+		true,
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+		field::<T>(),
+		true,
+		true,
+		field::<T>()
+	)
+	verify {
+		assert!(UpdateSld::<T>::contains_key((caller, field::<T>())));
+	}
+
+	get_info {
+		let caller = T::IpsOrigin::ensure_origin(T::IpsOrigin::successful_origin())?;
+		store_sld::<T>(&caller)?;
+	}: _(RawOrigin::Signed(caller), field::<T>())
+}
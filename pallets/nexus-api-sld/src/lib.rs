@@ -1,17 +1,53 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Decode, Encode};
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult};
-use frame_system::ensure_signed;
+use nexus_support::ensure_field_lengths;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 
 use sp_std::prelude::*;
 
 use core::fmt::Debug;
 
-pub trait Config: frame_system::Config {
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migrations;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// Lets other pallets read the limits an IPS publishes in its SLD.
+pub trait MaxDestinationValue<AccountId> {
+	/// The most a single payment may credit in `country_id` through `ips`, if it publishes one.
+	fn max_destination_value(ips: &AccountId, country_id: &[u8]) -> Option<u128>;
+}
+
+impl<AccountId> MaxDestinationValue<AccountId> for () {
+	fn max_destination_value(_: &AccountId, _: &[u8]) -> Option<u128> {
+		None
+	}
+}
+
+/// Storage layouts of this pallet, for migrations.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+enum Releases {
+	V1,
+	V2SldsByCountry,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
 pub struct Sld {
 	iban: bool,
@@ -28,20 +64,65 @@ pub struct Sld {
 	ips_timeout: Vec<u8>,
 }
 
-decl_storage! {
-	trait Store for Module<T: Config> as NexusApiSLD {
-		UpdateSld get(fn update_sld): map hasher(blake2_128_concat) (T::AccountId, Vec<u8>) => Sld;
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Who may publish an SLD: the operator of an IPS.
+		type IpsOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+		/// The longest any byte field of an SLD may be.
+		#[pallet::constant]
+		type MaxFieldLength: Get<u32>;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
 	}
-}
 
-decl_event!(
-	pub enum Event<T>
-	where
-		AccountId = <T as frame_system::Config>::AccountId,
-	{
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	#[pallet::getter(fn update_sld)]
+	pub(super) type UpdateSld<T: Config> =
+		StorageMap<_, Blake2_128Concat, (T::AccountId, Vec<u8>), Sld, ValueQuery>;
+
+	/// The IPSs that have published an SLD for each country.
+	#[pallet::storage]
+	pub(super) type SldsByCountry<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, Vec<u8>, Blake2_128Concat, T::AccountId, ()>;
+
+	#[pallet::storage]
+	pub(super) type StorageVersion<T> = StorageValue<_, Releases, ValueQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(Default)]
+	pub struct GenesisConfig {}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		/// A new chain starts out with the latest storage layout, so no migration runs on it.
+		fn build(&self) {
+			<StorageVersion<T>>::put(Releases::V2SldsByCountry);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
 		/// IPS has input the details
 		InputSet(
-			AccountId,
+			T::AccountId,
 			bool,
 			Vec<u8>,
 			Vec<u8>,
@@ -57,7 +138,7 @@ decl_event!(
 		),
 
 		OutputSet(
-			AccountId,
+			T::AccountId,
 			bool,
 			Vec<u8>,
 			Vec<u8>,
@@ -72,25 +153,35 @@ decl_event!(
 			Vec<u8>,
 		),
 	}
-);
 
-decl_error! {
-	pub enum Error for Module<T: Config> {
+	#[pallet::error]
+	pub enum Error<T> {
 		InvalidAccountId,
+
+		/// A field is longer than `MaxFieldLength`
+		FieldTooLong,
 	}
-}
 
-decl_module! {
-	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
 
-		// Initialize errors
-		type Error = Error<T>;
+			if <StorageVersion<T>>::get() == Releases::V1 {
+				weight = weight.saturating_add(migrations::index_slds_by_country::<T>());
+				<StorageVersion<T>>::put(Releases::V2SldsByCountry);
+			}
 
-		// Initialize events
-		fn deposit_event() = default;
+			weight
+		}
+	}
 
-		#[weight = 10_000_000]
-		fn set_info(origin, iban: bool,
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::weight(T::WeightInfo::set_info())]
+		pub fn set_info(
+			origin: OriginFor<T>,
+			iban: bool,
 			country_id: Vec<u8>,
 			local_bank_number: Vec<u8>,
 			local_bank_id: Vec<u8>,
@@ -101,8 +192,23 @@ decl_module! {
 			max_destination_value: Vec<u8>,
 			account_validation_available: bool,
 			payee_type: bool,
-			ips_timeout: Vec<u8>,) -> DispatchResult {
-			let user = ensure_signed(origin)?;
+			ips_timeout: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let user = T::IpsOrigin::ensure_origin(origin)?;
+			ensure_field_lengths::<T::MaxFieldLength, _>(
+				&[
+					&country_id,
+					&local_bank_number,
+					&local_bank_id,
+					&alias_name,
+					&alias_format,
+					&alias_desc,
+					&max_destination_value,
+					&ips_timeout,
+				],
+				Error::<T>::FieldTooLong,
+			)?;
+
 			let local_bank_clone = local_bank_number.clone();
 			let local_bank_id_clone = local_bank_id.clone();
 			let alias_name_clone = alias_name.clone();
@@ -113,8 +219,7 @@ decl_module! {
 			let ips_timeout_clone = ips_timeout.clone();
 			let max_destination_value_clone = max_destination_value.clone();
 
-
-			let sld = Sld{
+			let sld = Sld {
 				iban,
 				country_id,
 				local_bank_number,
@@ -130,22 +235,81 @@ decl_module! {
 			};
 
 			<UpdateSld<T>>::insert((&user, &country_id_clone), sld);
+			<SldsByCountry<T>>::insert(&country_id_clone, &user, ());
 
-			Self::deposit_event(RawEvent::InputSet( user_clone, iban,country_id_clone, local_bank_clone, local_bank_id_clone, alias_conversion, alias_name_clone, alias_format_clone, alias_desc_clone, max_destination_value_clone, account_validation_available, payee_type, ips_timeout_clone));
-			Ok(())
-			}
+			Self::deposit_event(Event::InputSet(
+				user_clone,
+				iban,
+				country_id_clone,
+				local_bank_clone,
+				local_bank_id_clone,
+				alias_conversion,
+				alias_name_clone,
+				alias_format_clone,
+				alias_desc_clone,
+				max_destination_value_clone,
+				account_validation_available,
+				payee_type,
+				ips_timeout_clone,
+			));
+			Ok(().into())
+		}
 
-			#[weight = 10_000]
-			fn get_info(origin, country_id : Vec<u8>) -> DispatchResult {
-				let getter = ensure_signed(origin)?;
+		#[pallet::weight(T::WeightInfo::get_info())]
+		pub fn get_info(origin: OriginFor<T>, country_id: Vec<u8>) -> DispatchResultWithPostInfo {
+			let getter = ensure_signed(origin)?;
 
-				let keys  = (&getter, country_id);
+			let keys = (&getter, country_id);
 
-				let keys_clone = keys.clone();
+			let keys_clone = keys.clone();
 
-				let sld = <UpdateSld<T>>::get(keys_clone);
-				Self::deposit_event(RawEvent::OutputSet( getter,sld.iban,  sld.country_id , sld.local_bank_number, sld.local_bank_id, sld.alias_conversion, sld.alias_name, sld.alias_format, sld.alias_desc, sld.max_destination_value, sld.account_validation_available, sld.payee_type, sld.ips_timeout));
-				Ok(())
-			}
+			let sld = <UpdateSld<T>>::get(keys_clone);
+			Self::deposit_event(Event::OutputSet(
+				getter,
+				sld.iban,
+				sld.country_id,
+				sld.local_bank_number,
+				sld.local_bank_id,
+				sld.alias_conversion,
+				sld.alias_name,
+				sld.alias_format,
+				sld.alias_desc,
+				sld.max_destination_value,
+				sld.account_validation_available,
+				sld.payee_type,
+				sld.ips_timeout,
+			));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Every SLD published for `country_id`, with the IPS that published it.
+	pub fn slds_for_country(country_id: &[u8]) -> Vec<(T::AccountId, Sld)> {
+		<SldsByCountry<T>>::iter_prefix(country_id.to_vec())
+			.map(|(ips, _)| {
+				let sld = <UpdateSld<T>>::get((ips.clone(), country_id.to_vec()));
+				(ips, sld)
+			})
+			.collect()
+	}
+}
+
+impl<T: Config> MaxDestinationValue<T::AccountId> for Pallet<T> {
+	/// `max_destination_value` is read as a decimal amount in the smallest unit of the
+	/// destination currency. An empty or unreadable value means there is no limit.
+	fn max_destination_value(ips: &T::AccountId, country_id: &[u8]) -> Option<u128> {
+		let keys = (ips.clone(), country_id.to_vec());
+		if !<UpdateSld<T>>::contains_key(&keys) {
+			return None;
+		}
+
+		let sld = <UpdateSld<T>>::get(&keys);
+		core::str::from_utf8(&sld.max_destination_value)
+			.ok()?
+			.trim()
+			.parse()
+			.ok()
 	}
 }
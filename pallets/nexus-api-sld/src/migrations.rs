@@ -0,0 +1,15 @@
+//! Storage migrations of the SLD pallet.
+
+use super::*;
+use frame_support::{traits::Get, weights::Weight};
+
+/// Index the SLDs published before `SldsByCountry` existed by their country.
+pub fn index_slds_by_country<T: Config>() -> Weight {
+	let mut count = 0;
+	for ((ips, country_id), _) in <UpdateSld<T>>::iter() {
+		<SldsByCountry<T>>::insert(country_id, ips, ());
+		count += 1;
+	}
+
+	T::DbWeight::get().reads_writes(count, count + 1)
+}
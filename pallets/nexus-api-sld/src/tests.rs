@@ -0,0 +1,184 @@
+use crate::{mock::*, Error, Event, MaxDestinationValue, Releases, Sld};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{GenesisBuild, Get, OnRuntimeUpgrade},
+};
+
+fn sld(country_id: &[u8]) -> Sld {
+	Sld {
+		iban: true,
+		country_id: country_id.to_vec(),
+		local_bank_number: b"7171".to_vec(),
+		local_bank_id: b"DBSSSGSG".to_vec(),
+		alias_conversion: true,
+		alias_name: b"PayNow".to_vec(),
+		alias_format: b"MOBILE".to_vec(),
+		alias_desc: b"Mobile number".to_vec(),
+		max_destination_value: b"20000000".to_vec(),
+		account_validation_available: true,
+		payee_type: false,
+		ips_timeout: b"25".to_vec(),
+	}
+}
+
+fn set_info(ips: u64, sld: Sld) -> frame_support::dispatch::DispatchResultWithPostInfo {
+	NexusSld::set_info(
+		Origin::signed(ips),
+		sld.iban,
+		sld.country_id,
+		sld.local_bank_number,
+		sld.local_bank_id,
+		sld.alias_conversion,
+		sld.alias_name,
+		sld.alias_format,
+		sld.alias_desc,
+		sld.max_destination_value,
+		sld.account_validation_available,
+		sld.payee_type,
+		sld.ips_timeout,
+	)
+}
+
+fn input_set(ips: u64, sld: Sld) -> Event<Test> {
+	Event::InputSet(
+		ips,
+		sld.iban,
+		sld.country_id,
+		sld.local_bank_number,
+		sld.local_bank_id,
+		sld.alias_conversion,
+		sld.alias_name,
+		sld.alias_format,
+		sld.alias_desc,
+		sld.max_destination_value,
+		sld.account_validation_available,
+		sld.payee_type,
+		sld.ips_timeout,
+	)
+}
+
+fn output_set(ips: u64, sld: Sld) -> Event<Test> {
+	Event::OutputSet(
+		ips,
+		sld.iban,
+		sld.country_id,
+		sld.local_bank_number,
+		sld.local_bank_id,
+		sld.alias_conversion,
+		sld.alias_name,
+		sld.alias_format,
+		sld.alias_desc,
+		sld.max_destination_value,
+		sld.account_validation_available,
+		sld.payee_type,
+		sld.ips_timeout,
+	)
+}
+
+#[test]
+fn migration_indexes_slds_by_country() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_info(1, sld(b"SG")));
+		assert_ok!(set_info(2, sld(b"IN")));
+		crate::SldsByCountry::<Test>::remove(b"SG".to_vec(), 1);
+		crate::SldsByCountry::<Test>::remove(b"IN".to_vec(), 2);
+		assert!(NexusSld::slds_for_country(b"SG").is_empty());
+
+		NexusSld::on_runtime_upgrade();
+
+		assert_eq!(NexusSld::slds_for_country(b"SG"), vec![(1, sld(b"SG"))]);
+		assert_eq!(NexusSld::slds_for_country(b"IN"), vec![(2, sld(b"IN"))]);
+		assert_eq!(
+			crate::StorageVersion::<Test>::get(),
+			Releases::V2SldsByCountry
+		);
+	});
+}
+
+#[test]
+fn a_new_chain_is_not_migrated() {
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	GenesisBuild::<Test>::assimilate_storage(&crate::GenesisConfig::default(), &mut storage)
+		.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| {
+		crate::UpdateSld::<Test>::insert((1, b"SG".to_vec()), sld(b"SG"));
+
+		NexusSld::on_runtime_upgrade();
+
+		assert!(NexusSld::slds_for_country(b"SG").is_empty());
+	});
+}
+
+#[test]
+fn set_info_stores_the_sld() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_info(1, sld(b"SG")));
+
+		assert_eq!(NexusSld::update_sld((1, b"SG".to_vec())), sld(b"SG"));
+		assert_eq!(last_event(), input_set(1, sld(b"SG")));
+	});
+}
+
+#[test]
+fn set_info_rejects_long_fields() {
+	new_test_ext().execute_with(|| {
+		let mut long = sld(b"SG");
+		long.alias_desc = vec![b'x'; MaxFieldLength::get() as usize + 1];
+
+		assert_noop!(set_info(1, long), Error::<Test>::FieldTooLong);
+	});
+}
+
+#[test]
+fn get_info_reports_the_callers_sld() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_info(1, sld(b"SG")));
+
+		assert_ok!(NexusSld::get_info(Origin::signed(1), b"SG".to_vec()));
+
+		assert_eq!(last_event(), output_set(1, sld(b"SG")));
+	});
+}
+
+#[test]
+fn get_info_reports_an_empty_sld_when_none_is_stored() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusSld::get_info(Origin::signed(1), b"SG".to_vec()));
+
+		assert_eq!(last_event(), output_set(1, Sld::default()));
+	});
+}
+
+#[test]
+fn max_destination_value_reads_the_published_limit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(NexusSld::max_destination_value(&1, b"SG"), None);
+
+		assert_ok!(set_info(1, sld(b"SG")));
+		assert_eq!(NexusSld::max_destination_value(&1, b"SG"), Some(20_000_000));
+		assert_eq!(NexusSld::max_destination_value(&2, b"SG"), None);
+
+		let mut unlimited = sld(b"IN");
+		unlimited.max_destination_value = Vec::new();
+		assert_ok!(set_info(1, unlimited));
+		assert_eq!(NexusSld::max_destination_value(&1, b"IN"), None);
+	});
+}
+
+#[test]
+fn slds_for_country_lists_every_publishing_ips() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_info(1, sld(b"SG")));
+		assert_ok!(set_info(2, sld(b"SG")));
+		assert_ok!(set_info(2, sld(b"IN")));
+
+		let mut slds = NexusSld::slds_for_country(b"SG");
+		slds.sort_by_key(|(ips, _)| *ips);
+		assert_eq!(slds, vec![(1, sld(b"SG")), (2, sld(b"SG"))]);
+		assert_eq!(NexusSld::slds_for_country(b"IN"), vec![(2, sld(b"IN"))]);
+		assert!(NexusSld::slds_for_country(b"MY").is_empty());
+	});
+}
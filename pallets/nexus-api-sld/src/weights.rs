@@ -0,0 +1,37 @@
+//! Weights for nexus_api_sld.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for nexus_api_sld.
+pub trait WeightInfo {
+	fn set_info() -> Weight;
+	fn get_info() -> Weight;
+}
+
+/// Weights for nexus_api_sld.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn set_info() -> Weight {
+		(29_000_000 as Weight).saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	fn get_info() -> Weight {
+		(24_000_000 as Weight).saturating_add(T::DbWeight::get().reads(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn set_info() -> Weight {
+		(29_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	fn get_info() -> Weight {
+		(24_000_000 as Weight).saturating_add(RocksDbWeight::get().reads(1 as Weight))
+	}
+}
@@ -0,0 +1,51 @@
+//! Benchmarks for nexus-directory.
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks};
+
+const BIC: &[u8] = b"NEXSSGSGXXX";
+const LEI: &[u8] = b"NEXUSSGPSP0000000148";
+
+benchmarks! {
+	register_participant {
+		let who: T::AccountId = account("participant", 0, 0);
+		let home_ips: T::AccountId = account("ips", 0, 0);
+		let origin = T::AdminOrigin::successful_origin();
+	}: _(origin, who.clone(), BIC.to_vec(), LEI.to_vec(), b"SG".to_vec(), home_ips)
+	verify {
+		assert!(Participants::<T>::contains_key(&who));
+	}
+
+	set_participant_status {
+		let who: T::AccountId = account("participant", 0, 0);
+		Pallet::<T>::register_participant(
+			T::AdminOrigin::successful_origin(),
+			who.clone(),
+			BIC.to_vec(),
+			LEI.to_vec(),
+			b"SG".to_vec(),
+			account("ips", 0, 0),
+		)?;
+		let origin = T::AdminOrigin::successful_origin();
+	}: _(origin, who.clone(), ParticipantStatus::Suspended)
+	verify {
+		assert!(!Pallet::<T>::is_bank_of(&who, BIC));
+	}
+
+	remove_participant {
+		let who: T::AccountId = account("participant", 0, 0);
+		Pallet::<T>::register_participant(
+			T::AdminOrigin::successful_origin(),
+			who.clone(),
+			BIC.to_vec(),
+			LEI.to_vec(),
+			b"SG".to_vec(),
+			account("ips", 0, 0),
+		)?;
+		let origin = T::AdminOrigin::successful_origin();
+	}: _(origin, who.clone())
+	verify {
+		assert!(Pallet::<T>::lookup(LEI).is_none());
+	}
+}
@@ -0,0 +1,369 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::ensure;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+use sp_std::prelude::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// Whether a participant may currently take part in payments.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParticipantStatus {
+	Active,
+	Suspended,
+}
+
+impl Default for ParticipantStatus {
+	fn default() -> Self {
+		ParticipantStatus::Active
+	}
+}
+
+/// The identity of a PSP or FXP taking part in Nexus.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct Participant<AccountId> {
+	/// ISO 9362 business identifier code, 8 or 11 characters.
+	pub bic: Vec<u8>,
+	/// ISO 17442 legal entity identifier, 20 characters.
+	pub lei: Vec<u8>,
+	/// ISO 3166 alpha-2 code of the country the participant is licensed in.
+	pub country: Vec<u8>,
+	/// The operator of the IPS the participant settles in.
+	pub home_ips: AccountId,
+	pub status: ParticipantStatus,
+}
+
+/// Tells whose bank the identifiers in Nexus messages are.
+pub trait ParticipantDirectory<AccountId> {
+	/// Whether `bank_id`, a BIC or LEI, identifies `who` and `who` is active.
+	fn is_bank_of(who: &AccountId, bank_id: &[u8]) -> bool;
+
+	/// The IPS operator `who` settles in and the country it is licensed in, if it is registered.
+	fn home_of(who: &AccountId) -> Option<(AccountId, Vec<u8>)>;
+
+	/// Make `bank_id` identify `who`, so that benchmarks can use any bank id.
+	#[cfg(feature = "runtime-benchmarks")]
+	fn add_bank(who: &AccountId, bank_id: &[u8]);
+}
+
+impl<AccountId> ParticipantDirectory<AccountId> for () {
+	fn is_bank_of(_: &AccountId, _: &[u8]) -> bool {
+		true
+	}
+
+	fn home_of(_: &AccountId) -> Option<(AccountId, Vec<u8>)> {
+		None
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn add_bank(_: &AccountId, _: &[u8]) {}
+}
+
+/// Whether `code` is an ISO 3166 alpha-2 country code.
+pub fn is_valid_country(code: &[u8]) -> bool {
+	code.len() == 2 && code.iter().all(u8::is_ascii_uppercase)
+}
+
+/// Whether `bic` is a well-formed BIC: a 4 letter institution code, a country code, a 2
+/// character location code and an optional 3 character branch code.
+pub fn is_valid_bic(bic: &[u8]) -> bool {
+	let is_code = |c: &u8| c.is_ascii_uppercase() || c.is_ascii_digit();
+
+	(bic.len() == 8 || bic.len() == 11)
+		&& bic[..4].iter().all(u8::is_ascii_uppercase)
+		&& is_valid_country(&bic[4..6])
+		&& bic[6..].iter().all(is_code)
+}
+
+/// Whether `lei` is a well-formed LEI whose last two digits check out under ISO 7064 MOD 97-10.
+pub fn is_valid_lei(lei: &[u8]) -> bool {
+	if lei.len() != 20
+		|| !lei[..18]
+			.iter()
+			.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+		|| !lei[18..].iter().all(u8::is_ascii_digit)
+	{
+		return false;
+	}
+
+	// Letters count as the two digit numbers 10 to 35.
+	let remainder = lei.iter().fold(0u32, |remainder, &c| {
+		if c.is_ascii_digit() {
+			(remainder * 10 + (c - b'0') as u32) % 97
+		} else {
+			(remainder * 100 + (c - b'A') as u32 + 10) % 97
+		}
+	});
+	remainder == 1
+}
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Who may register, suspend and remove participants.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	#[pallet::getter(fn participant)]
+	pub(super) type Participants<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, Participant<T::AccountId>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn account_of_bic)]
+	pub(super) type Bics<T: Config> = StorageMap<_, Blake2_128Concat, Vec<u8>, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn account_of_lei)]
+	pub(super) type Leis<T: Config> = StorageMap<_, Blake2_128Concat, Vec<u8>, T::AccountId>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The participants registered at genesis.
+		pub participants: Vec<(T::AccountId, Participant<T::AccountId>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self {
+				participants: Vec::new(),
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (who, participant) in &self.participants {
+				Pallet::<T>::check_participant(who, participant)
+					.expect("genesis participants must be valid");
+				Pallet::<T>::insert_participant(who, participant.clone());
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A participant has been registered. [who, bic, lei]
+		ParticipantRegistered(T::AccountId, Vec<u8>, Vec<u8>),
+
+		/// A participant has been activated or suspended. [who, status]
+		ParticipantStatusSet(T::AccountId, ParticipantStatus),
+
+		/// A participant has been removed from the directory. [who]
+		ParticipantRemoved(T::AccountId),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The BIC is not well-formed
+		InvalidBic,
+
+		/// The LEI is not well-formed or its check digits are wrong
+		InvalidLei,
+
+		/// The country is not an ISO 3166 alpha-2 code
+		InvalidCountry,
+
+		/// The country of the BIC is not the country of the participant
+		BicCountryMismatch,
+
+		/// The account is already registered
+		AlreadyRegistered,
+
+		/// The BIC or LEI already identifies another participant
+		IdentifierInUse,
+
+		/// The account is not registered
+		NotRegistered,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register `who` as an active participant settling in the IPS run by `home_ips`.
+		#[pallet::weight(T::WeightInfo::register_participant())]
+		pub fn register_participant(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			bic: Vec<u8>,
+			lei: Vec<u8>,
+			country: Vec<u8>,
+			home_ips: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let participant = Participant {
+				bic,
+				lei,
+				country,
+				home_ips,
+				status: ParticipantStatus::Active,
+			};
+			Self::check_participant(&who, &participant)?;
+
+			let (bic, lei) = (participant.bic.clone(), participant.lei.clone());
+			Self::insert_participant(&who, participant);
+
+			Self::deposit_event(Event::ParticipantRegistered(who, bic, lei));
+
+			Ok(().into())
+		}
+
+		/// Activate or suspend `who`. The identifiers of a suspended participant are not accepted
+		/// in payments.
+		#[pallet::weight(T::WeightInfo::set_participant_status())]
+		pub fn set_participant_status(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			status: ParticipantStatus,
+		) -> DispatchResultWithPostInfo {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			<Participants<T>>::try_mutate(&who, |participant| -> DispatchResult {
+				let participant = participant.as_mut().ok_or(Error::<T>::NotRegistered)?;
+				participant.status = status;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::ParticipantStatusSet(who, status));
+
+			Ok(().into())
+		}
+
+		/// Remove `who` from the directory, freeing its BIC and LEI.
+		#[pallet::weight(T::WeightInfo::remove_participant())]
+		pub fn remove_participant(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let participant = <Participants<T>>::take(&who).ok_or(Error::<T>::NotRegistered)?;
+			<Bics<T>>::remove(&participant.bic);
+			<Leis<T>>::remove(&participant.lei);
+
+			Self::deposit_event(Event::ParticipantRemoved(who));
+
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The participant `bic` identifies.
+	pub fn participant_by_bic(bic: &[u8]) -> Option<(T::AccountId, Participant<T::AccountId>)> {
+		let who = Self::account_of_bic(bic)?;
+		Self::participant(&who).map(|participant| (who, participant))
+	}
+
+	/// The participant `lei` identifies.
+	pub fn participant_by_lei(lei: &[u8]) -> Option<(T::AccountId, Participant<T::AccountId>)> {
+		let who = Self::account_of_lei(lei)?;
+		Self::participant(&who).map(|participant| (who, participant))
+	}
+
+	/// The participant `identifier`, a BIC or LEI, identifies.
+	pub fn lookup(identifier: &[u8]) -> Option<(T::AccountId, Participant<T::AccountId>)> {
+		Self::participant_by_bic(identifier).or_else(|| Self::participant_by_lei(identifier))
+	}
+
+	/// Check that `participant` is well-formed and that neither `who` nor its identifiers are
+	/// registered yet.
+	fn check_participant(
+		who: &T::AccountId,
+		participant: &Participant<T::AccountId>,
+	) -> Result<(), Error<T>> {
+		ensure!(is_valid_bic(&participant.bic), Error::<T>::InvalidBic);
+		ensure!(is_valid_lei(&participant.lei), Error::<T>::InvalidLei);
+		ensure!(
+			is_valid_country(&participant.country),
+			Error::<T>::InvalidCountry
+		);
+		ensure!(
+			participant.bic[4..6] == participant.country[..],
+			Error::<T>::BicCountryMismatch
+		);
+		ensure!(
+			!<Participants<T>>::contains_key(who),
+			Error::<T>::AlreadyRegistered
+		);
+		ensure!(
+			!<Bics<T>>::contains_key(&participant.bic)
+				&& !<Leis<T>>::contains_key(&participant.lei),
+			Error::<T>::IdentifierInUse
+		);
+		Ok(())
+	}
+
+	fn insert_participant(who: &T::AccountId, participant: Participant<T::AccountId>) {
+		<Bics<T>>::insert(&participant.bic, who);
+		<Leis<T>>::insert(&participant.lei, who);
+		<Participants<T>>::insert(who, participant);
+	}
+}
+
+impl<T: Config> ParticipantDirectory<T::AccountId> for Pallet<T> {
+	fn is_bank_of(who: &T::AccountId, bank_id: &[u8]) -> bool {
+		let owner = Self::account_of_bic(bank_id).or_else(|| Self::account_of_lei(bank_id));
+		owner.as_ref() == Some(who)
+			&& Self::participant(who).map_or(false, |participant| {
+				participant.status == ParticipantStatus::Active
+			})
+	}
+
+	fn home_of(who: &T::AccountId) -> Option<(T::AccountId, Vec<u8>)> {
+		Self::participant(who).map(|participant| (participant.home_ips, participant.country))
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn add_bank(who: &T::AccountId, bank_id: &[u8]) {
+		<Bics<T>>::insert(bank_id, who);
+		if !<Participants<T>>::contains_key(who) {
+			let participant = Participant {
+				bic: bank_id.to_vec(),
+				lei: Vec::new(),
+				country: Vec::new(),
+				home_ips: who.clone(),
+				status: ParticipantStatus::Active,
+			};
+			<Participants<T>>::insert(who, participant);
+		}
+	}
+}
@@ -0,0 +1,104 @@
+use crate as nexus_directory;
+use crate::{Participant, ParticipantStatus};
+use frame_support::{parameter_types, traits::GenesisBuild};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const IPS: u64 = 1;
+pub const PSP: u64 = 2;
+pub const OTHER_PSP: u64 = 3;
+
+pub const BIC: &[u8] = b"NEXSSGSGXXX";
+pub const LEI: &[u8] = b"NEXUSSGPSP0000000148";
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		NexusDirectory: nexus_directory::{Module, Call, Storage, Config<T>, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+impl nexus_directory::Config for Test {
+	type Event = Event;
+	type AdminOrigin = EnsureRoot<u64>;
+	type WeightInfo = ();
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	nexus_directory::GenesisConfig::<Test> {
+		participants: vec![(
+			PSP,
+			Participant {
+				bic: BIC.to_vec(),
+				lei: LEI.to_vec(),
+				country: b"SG".to_vec(),
+				home_ips: IPS,
+				status: ParticipantStatus::Active,
+			},
+		)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// The last event deposited by the directory pallet.
+pub fn last_event() -> nexus_directory::Event<Test> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::nexus_directory(event) => Some(event),
+			_ => None,
+		})
+		.last()
+		.expect("an event was deposited")
+}
@@ -0,0 +1,192 @@
+use crate::{
+	is_valid_bic, is_valid_country, is_valid_lei, mock::*, Error, Event, ParticipantDirectory,
+	ParticipantStatus,
+};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+const OTHER_BIC: &[u8] = b"NEXSINBB";
+const OTHER_LEI: &[u8] = b"NEXUSINPSP0000000216";
+
+fn register_other() -> frame_support::dispatch::DispatchResultWithPostInfo {
+	NexusDirectory::register_participant(
+		Origin::root(),
+		OTHER_PSP,
+		OTHER_BIC.to_vec(),
+		OTHER_LEI.to_vec(),
+		b"IN".to_vec(),
+		IPS,
+	)
+}
+
+#[test]
+fn identifiers_are_validated() {
+	assert!(is_valid_bic(b"DEUTDEFF"));
+	assert!(is_valid_bic(b"DEUTDEFF500"));
+	assert!(!is_valid_bic(b"DEUTDEF"));
+	assert!(!is_valid_bic(b"DEU1DEFF"));
+	assert!(!is_valid_bic(b"deutdeff"));
+
+	assert!(is_valid_lei(b"5493001KJTIIGC8Y1R12"));
+	assert!(!is_valid_lei(b"5493001KJTIIGC8Y1R13"));
+	assert!(!is_valid_lei(b"5493001KJTIIGC8Y1R1"));
+	assert!(!is_valid_lei(b"5493001KJTIIGC8Y1RAB"));
+
+	assert!(is_valid_country(b"SG"));
+	assert!(!is_valid_country(b"SGP"));
+	assert!(!is_valid_country(b"sg"));
+}
+
+#[test]
+fn genesis_participants_are_registered() {
+	new_test_ext().execute_with(|| {
+		let participant = NexusDirectory::participant(&PSP).unwrap();
+		assert_eq!(participant.country, b"SG".to_vec());
+		assert_eq!(participant.home_ips, IPS);
+
+		assert_eq!(
+			NexusDirectory::participant_by_bic(BIC),
+			Some((PSP, participant.clone()))
+		);
+		assert_eq!(
+			NexusDirectory::participant_by_lei(LEI),
+			Some((PSP, participant.clone()))
+		);
+		assert_eq!(NexusDirectory::lookup(LEI), Some((PSP, participant)));
+		assert_eq!(NexusDirectory::lookup(OTHER_LEI), None);
+	});
+}
+
+#[test]
+fn register_participant_records_identifiers() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(register_other());
+
+		assert_eq!(
+			NexusDirectory::lookup(OTHER_BIC).map(|(who, _)| who),
+			Some(OTHER_PSP)
+		);
+		assert_eq!(
+			last_event(),
+			Event::ParticipantRegistered(OTHER_PSP, OTHER_BIC.to_vec(), OTHER_LEI.to_vec())
+		);
+		assert_noop!(register_other(), Error::<Test>::AlreadyRegistered);
+	});
+}
+
+#[test]
+fn register_participant_rejects_bad_identities() {
+	new_test_ext().execute_with(|| {
+		let register = |bic: &[u8], lei: &[u8], country: &[u8]| {
+			NexusDirectory::register_participant(
+				Origin::root(),
+				OTHER_PSP,
+				bic.to_vec(),
+				lei.to_vec(),
+				country.to_vec(),
+				IPS,
+			)
+		};
+
+		assert_noop!(
+			register(b"NEXS1NBB", OTHER_LEI, b"IN"),
+			Error::<Test>::InvalidBic
+		);
+		assert_noop!(
+			register(OTHER_BIC, b"NEXUSINPSP0000000217", b"IN"),
+			Error::<Test>::InvalidLei
+		);
+		assert_noop!(
+			register(OTHER_BIC, OTHER_LEI, b"IND"),
+			Error::<Test>::InvalidCountry
+		);
+		assert_noop!(
+			register(OTHER_BIC, OTHER_LEI, b"SG"),
+			Error::<Test>::BicCountryMismatch
+		);
+		assert_noop!(
+			register(OTHER_BIC, LEI, b"IN"),
+			Error::<Test>::IdentifierInUse
+		);
+		assert_noop!(
+			NexusDirectory::register_participant(
+				Origin::signed(IPS),
+				OTHER_PSP,
+				OTHER_BIC.to_vec(),
+				OTHER_LEI.to_vec(),
+				b"IN".to_vec(),
+				IPS,
+			),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn home_of_gives_the_ips_and_country() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(NexusDirectory::home_of(&PSP), Some((IPS, b"SG".to_vec())));
+		assert_eq!(NexusDirectory::home_of(&OTHER_PSP), None);
+	});
+}
+
+#[test]
+fn bank_ids_belong_to_active_participants() {
+	new_test_ext().execute_with(|| {
+		assert!(NexusDirectory::is_bank_of(&PSP, BIC));
+		assert!(NexusDirectory::is_bank_of(&PSP, LEI));
+		assert!(!NexusDirectory::is_bank_of(&OTHER_PSP, BIC));
+		assert!(!NexusDirectory::is_bank_of(&PSP, b"UNKNOWN"));
+
+		assert_ok!(NexusDirectory::set_participant_status(
+			Origin::root(),
+			PSP,
+			ParticipantStatus::Suspended
+		));
+		assert_eq!(
+			last_event(),
+			Event::ParticipantStatusSet(PSP, ParticipantStatus::Suspended)
+		);
+		assert!(!NexusDirectory::is_bank_of(&PSP, BIC));
+
+		assert_ok!(NexusDirectory::set_participant_status(
+			Origin::root(),
+			PSP,
+			ParticipantStatus::Active
+		));
+		assert!(NexusDirectory::is_bank_of(&PSP, BIC));
+		assert_noop!(
+			NexusDirectory::set_participant_status(
+				Origin::root(),
+				OTHER_PSP,
+				ParticipantStatus::Active
+			),
+			Error::<Test>::NotRegistered
+		);
+	});
+}
+
+#[test]
+fn remove_participant_frees_identifiers() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusDirectory::remove_participant(Origin::root(), PSP));
+
+		assert_eq!(NexusDirectory::participant(&PSP), None);
+		assert_eq!(NexusDirectory::lookup(BIC), None);
+		assert_eq!(last_event(), Event::ParticipantRemoved(PSP));
+		assert_noop!(
+			NexusDirectory::remove_participant(Origin::root(), PSP),
+			Error::<Test>::NotRegistered
+		);
+
+		assert_ok!(NexusDirectory::register_participant(
+			Origin::root(),
+			OTHER_PSP,
+			BIC.to_vec(),
+			LEI.to_vec(),
+			b"SG".to_vec(),
+			IPS,
+		));
+		assert!(NexusDirectory::is_bank_of(&OTHER_PSP, BIC));
+	});
+}
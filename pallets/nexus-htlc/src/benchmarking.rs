@@ -0,0 +1,94 @@
+//! Benchmarks for nexus-htlc.
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::traits::Get;
+use frame_system::RawOrigin;
+use sp_runtime::traits::One;
+use sp_std::vec;
+
+const SEED: u32 = 0;
+const CURRENCY: CurrencyCode = *b"SGD";
+
+/// Register the currency and give `sender` enough of it to lock.
+fn fund<T>(sender: &T::AccountId) -> Result<(), &'static str>
+where
+	T: Config<Assets = nexus_tokens::Pallet<T>> + nexus_tokens::Config,
+{
+	let issuer: T::AccountId = account("issuer", 0, SEED);
+	nexus_tokens::Pallet::<T>::create_asset(RawOrigin::Root.into(), CURRENCY, issuer.clone(), 2)?;
+	nexus_tokens::Pallet::<T>::mint(
+		RawOrigin::Signed(issuer).into(),
+		CURRENCY,
+		sender.clone(),
+		1_000u32.into(),
+	)?;
+	Ok(())
+}
+
+/// Lock funds of `sender` under the hash of `preimage` and return the lock and its expiry.
+fn lock_funds<T>(
+	sender: &T::AccountId,
+	preimage: &[u8],
+) -> Result<(HashLock, T::BlockNumber), &'static str>
+where
+	T: Config<Assets = nexus_tokens::Pallet<T>> + nexus_tokens::Config,
+{
+	let recipient: T::AccountId = account("recipient", 0, SEED);
+	let hashlock = sp_io::hashing::sha2_256(preimage);
+	let expires_at = <frame_system::Module<T>>::block_number() + T::MinLockPeriod::get();
+	Pallet::<T>::lock(
+		RawOrigin::Signed(sender.clone()).into(),
+		recipient,
+		CURRENCY,
+		1_000u32.into(),
+		hashlock,
+		expires_at,
+	)?;
+	Ok((hashlock, expires_at))
+}
+
+benchmarks! {
+	where_clause {
+		where
+			T: Config<Assets = nexus_tokens::Pallet<T>>,
+			T: nexus_tokens::Config,
+	}
+
+	lock {
+		let caller: T::AccountId = whitelisted_caller();
+		let recipient: T::AccountId = account("recipient", 0, SEED);
+		let hashlock = sp_io::hashing::sha2_256(b"preimage");
+		let expires_at = <frame_system::Module<T>>::block_number() + T::MinLockPeriod::get();
+		fund::<T>(&caller)?;
+	}: _(RawOrigin::Signed(caller), recipient, CURRENCY, 1_000u32.into(), hashlock, expires_at)
+	verify {
+		assert!(Htlcs::<T>::contains_key(hashlock));
+	}
+
+	// The preimage is stored with the claimed lock, so its length drives the cost.
+	claim {
+		let p in 1 .. 1_024;
+
+		let caller: T::AccountId = whitelisted_caller();
+		let sender: T::AccountId = account("sender", 0, SEED);
+		let preimage = vec![0u8; p as usize];
+		fund::<T>(&sender)?;
+		let (hashlock, _) = lock_funds::<T>(&sender, &preimage)?;
+	}: _(RawOrigin::Signed(caller), hashlock, preimage)
+	verify {
+		assert_eq!(Pallet::<T>::htlc(hashlock).map(|htlc| htlc.state), Some(HtlcState::Claimed));
+	}
+
+	refund {
+		let caller: T::AccountId = whitelisted_caller();
+		let sender: T::AccountId = account("sender", 0, SEED);
+		fund::<T>(&sender)?;
+		let (hashlock, expires_at) = lock_funds::<T>(&sender, b"preimage")?;
+		<frame_system::Module<T>>::set_block_number(expires_at + One::one());
+	}: _(RawOrigin::Signed(caller), hashlock)
+	verify {
+		assert_eq!(Pallet::<T>::htlc(hashlock).map(|htlc| htlc.state), Some(HtlcState::Refunded));
+	}
+}
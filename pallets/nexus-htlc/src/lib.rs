@@ -0,0 +1,296 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{dispatch::DispatchResult, ensure, transactional};
+use nexus_tokens::{CurrencyCode, MultiReservableCurrency};
+use sp_runtime::traits::{Saturating, Zero};
+
+use sp_std::prelude::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// SHA-256 digest of the secret preimage. SHA-256 is used so the same lock can be mirrored on
+/// ledgers that do not run Substrate.
+pub type HashLock = [u8; 32];
+
+type BalanceOf<T> = <<T as Config>::Assets as MultiReservableCurrency<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// Lets other pallets put funds under a hash-time-lock.
+pub trait HtlcLocker<AccountId, Balance, BlockNumber> {
+	/// Reserve `amount` of `currency` from `sender` until it is claimed by `recipient` with the
+	/// preimage of `hashlock` or refunded at `expires_at`.
+	fn lock(
+		sender: &AccountId,
+		recipient: &AccountId,
+		currency: CurrencyCode,
+		amount: Balance,
+		hashlock: HashLock,
+		expires_at: BlockNumber,
+	) -> DispatchResult;
+}
+
+/// Called once a hash-time-lock has been resolved.
+pub trait OnHtlcResolved {
+	/// The recipient has been paid.
+	fn on_claimed(hashlock: &HashLock);
+
+	/// The sender has been paid back.
+	fn on_refunded(hashlock: &HashLock);
+}
+
+impl OnHtlcResolved for () {
+	fn on_claimed(_: &HashLock) {}
+	fn on_refunded(_: &HashLock) {}
+}
+
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HtlcState {
+	/// The funds are reserved and can be claimed.
+	Locked,
+	/// The recipient claimed the funds with the preimage.
+	Claimed,
+	/// The lock expired and the sender got the funds back.
+	Refunded,
+}
+
+impl Default for HtlcState {
+	fn default() -> Self {
+		HtlcState::Locked
+	}
+}
+
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct Htlc<AccountId, Balance, BlockNumber> {
+	sender: AccountId,
+	recipient: AccountId,
+	currency: CurrencyCode,
+	amount: Balance,
+	expires_at: BlockNumber,
+	state: HtlcState,
+	preimage: Option<Vec<u8>>,
+}
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The CBDC ledger the locked funds are held in.
+		type Assets: MultiReservableCurrency<Self::AccountId>;
+
+		/// The shortest time a lock has to stay claimable.
+		#[pallet::constant]
+		type MinLockPeriod: Get<Self::BlockNumber>;
+
+		/// Told about every claim and refund.
+		type OnResolved: OnHtlcResolved;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	#[pallet::getter(fn htlc)]
+	pub(super) type Htlcs<T: Config> =
+		StorageMap<_, Blake2_128Concat, HashLock, Htlc<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", BalanceOf<T> = "Balance", T::BlockNumber = "BlockNumber")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Funds have been locked. [hashlock, sender, recipient, currency, amount, expires_at]
+		HtlcLocked(
+			HashLock,
+			T::AccountId,
+			T::AccountId,
+			CurrencyCode,
+			BalanceOf<T>,
+			T::BlockNumber,
+		),
+
+		/// The recipient has been paid and the preimage revealed. [hashlock, preimage]
+		HtlcClaimed(HashLock, Vec<u8>),
+
+		/// The sender has been paid back after expiry. [hashlock]
+		HtlcRefunded(HashLock),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// There is already a lock for the given hash
+		HtlcExists,
+
+		/// There is no lock for the given hash
+		UnknownHtlc,
+
+		/// The lock has already been claimed or refunded
+		AlreadyResolved,
+
+		/// The lock expires sooner than the minimum lock period
+		LockPeriodTooShort,
+
+		/// The preimage does not hash to the lock
+		InvalidPreimage,
+
+		/// The lock has expired and can only be refunded
+		HtlcExpired,
+
+		/// The lock has not expired yet
+		HtlcNotExpired,
+
+		/// The locked funds could not be paid out in full
+		InsufficientReservedBalance,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Lock funds for `recipient` under `hashlock` until `expires_at`.
+		#[pallet::weight(T::WeightInfo::lock())]
+		pub fn lock(
+			origin: OriginFor<T>,
+			recipient: T::AccountId,
+			currency: CurrencyCode,
+			amount: BalanceOf<T>,
+			hashlock: HashLock,
+			expires_at: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+
+			<Self as HtlcLocker<_, _, _>>::lock(
+				&user, &recipient, currency, amount, hashlock, expires_at,
+			)?;
+			Ok(().into())
+		}
+
+		/// Pay locked funds to their recipient. Anyone holding the preimage may call this.
+		#[pallet::weight(T::WeightInfo::claim(preimage.len() as u32))]
+		#[transactional]
+		pub fn claim(
+			origin: OriginFor<T>,
+			hashlock: HashLock,
+			preimage: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			let mut htlc = Self::htlc(&hashlock).ok_or(Error::<T>::UnknownHtlc)?;
+			ensure!(htlc.state == HtlcState::Locked, Error::<T>::AlreadyResolved);
+			ensure!(
+				<frame_system::Module<T>>::block_number() < htlc.expires_at,
+				Error::<T>::HtlcExpired
+			);
+			ensure!(
+				sp_io::hashing::sha2_256(&preimage) == hashlock,
+				Error::<T>::InvalidPreimage
+			);
+
+			let unpaid = T::Assets::repatriate_reserved(
+				htlc.currency,
+				&htlc.sender,
+				&htlc.recipient,
+				htlc.amount,
+			)?;
+			ensure!(unpaid.is_zero(), Error::<T>::InsufficientReservedBalance);
+
+			htlc.state = HtlcState::Claimed;
+			htlc.preimage = Some(preimage.clone());
+			<Htlcs<T>>::insert(&hashlock, htlc);
+
+			T::OnResolved::on_claimed(&hashlock);
+			Self::deposit_event(Event::HtlcClaimed(hashlock, preimage));
+
+			Ok(().into())
+		}
+
+		/// Give expired locked funds back to their sender. Anyone may call this.
+		#[pallet::weight(T::WeightInfo::refund())]
+		pub fn refund(origin: OriginFor<T>, hashlock: HashLock) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			let mut htlc = Self::htlc(&hashlock).ok_or(Error::<T>::UnknownHtlc)?;
+			ensure!(htlc.state == HtlcState::Locked, Error::<T>::AlreadyResolved);
+			ensure!(
+				<frame_system::Module<T>>::block_number() >= htlc.expires_at,
+				Error::<T>::HtlcNotExpired
+			);
+
+			T::Assets::unreserve(htlc.currency, &htlc.sender, htlc.amount);
+
+			htlc.state = HtlcState::Refunded;
+			<Htlcs<T>>::insert(&hashlock, htlc);
+
+			T::OnResolved::on_refunded(&hashlock);
+			Self::deposit_event(Event::HtlcRefunded(hashlock));
+
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> HtlcLocker<T::AccountId, BalanceOf<T>, T::BlockNumber> for Pallet<T> {
+	fn lock(
+		sender: &T::AccountId,
+		recipient: &T::AccountId,
+		currency: CurrencyCode,
+		amount: BalanceOf<T>,
+		hashlock: HashLock,
+		expires_at: T::BlockNumber,
+	) -> DispatchResult {
+		ensure!(!<Htlcs<T>>::contains_key(&hashlock), Error::<T>::HtlcExists);
+
+		let now = <frame_system::Module<T>>::block_number();
+		ensure!(
+			expires_at >= now.saturating_add(T::MinLockPeriod::get()),
+			Error::<T>::LockPeriodTooShort
+		);
+
+		T::Assets::reserve(currency, sender, amount)?;
+
+		let htlc = Htlc {
+			sender: sender.clone(),
+			recipient: recipient.clone(),
+			currency,
+			amount,
+			expires_at,
+			state: HtlcState::Locked,
+			preimage: None,
+		};
+		<Htlcs<T>>::insert(&hashlock, htlc);
+
+		Self::deposit_event(Event::HtlcLocked(
+			hashlock,
+			sender.clone(),
+			recipient.clone(),
+			currency,
+			amount,
+			expires_at,
+		));
+
+		Ok(())
+	}
+}
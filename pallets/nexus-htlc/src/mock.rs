@@ -0,0 +1,121 @@
+use crate as nexus_htlc;
+use frame_support::parameter_types;
+use nexus_tokens::CurrencyCode;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const CENTRAL_BANK: u64 = 1;
+pub const SENDER: u64 = 2;
+pub const RECIPIENT: u64 = 3;
+pub const SGD: CurrencyCode = *b"SGD";
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		NexusTokens: nexus_tokens::{Module, Call, Storage, Config<T>, Event<T>},
+		NexusHtlc: nexus_htlc::{Module, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+impl nexus_tokens::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type WeightInfo = ();
+}
+
+thread_local! {
+	/// Every resolved lock, with whether it was claimed rather than refunded.
+	pub static RESOLVED: RefCell<Vec<(nexus_htlc::HashLock, bool)>> = RefCell::new(Vec::new());
+}
+
+pub struct RecordResolved;
+impl nexus_htlc::OnHtlcResolved for RecordResolved {
+	fn on_claimed(hashlock: &nexus_htlc::HashLock) {
+		RESOLVED.with(|resolved| resolved.borrow_mut().push((*hashlock, true)));
+	}
+
+	fn on_refunded(hashlock: &nexus_htlc::HashLock) {
+		RESOLVED.with(|resolved| resolved.borrow_mut().push((*hashlock, false)));
+	}
+}
+
+parameter_types! {
+	pub const MinLockPeriod: u64 = 5;
+}
+
+impl nexus_htlc::Config for Test {
+	type Event = Event;
+	type Assets = NexusTokens;
+	type MinLockPeriod = MinLockPeriod;
+	type OnResolved = RecordResolved;
+	type WeightInfo = ();
+}
+
+// Build genesis storage according to the mock runtime, with 1_000 SGD held by `SENDER`.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap()
+		.into();
+	RESOLVED.with(|resolved| resolved.borrow_mut().clear());
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		NexusTokens::create_asset(Origin::root(), SGD, CENTRAL_BANK, 2).unwrap();
+		NexusTokens::mint(Origin::signed(CENTRAL_BANK), SGD, SENDER, 1_000).unwrap();
+	});
+	ext
+}
+
+/// The last event deposited by the HTLC pallet.
+pub fn last_event() -> nexus_htlc::Event<Test> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::nexus_htlc(event) => Some(event),
+			_ => None,
+		})
+		.last()
+		.expect("an event was deposited")
+}
@@ -0,0 +1,215 @@
+use crate::{mock::*, Error, Event, HashLock, HtlcLocker, HtlcState};
+use frame_support::{assert_noop, assert_ok};
+use nexus_tokens::MultiReservableCurrency;
+
+const PREIMAGE: &[u8] = b"secret";
+
+fn hashlock() -> HashLock {
+	sp_io::hashing::sha2_256(PREIMAGE)
+}
+
+fn lock(expires_at: u64) {
+	assert_ok!(NexusHtlc::lock(
+		Origin::signed(SENDER),
+		RECIPIENT,
+		SGD,
+		400,
+		hashlock(),
+		expires_at
+	));
+}
+
+fn resolved() -> Vec<(HashLock, bool)> {
+	RESOLVED.with(|resolved| resolved.borrow().clone())
+}
+
+#[test]
+fn lock_reserves_the_funds() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+
+		let htlc = NexusHtlc::htlc(hashlock()).unwrap();
+		assert_eq!(htlc.state, HtlcState::Locked);
+		assert_eq!(htlc.amount, 400);
+		assert_eq!(NexusTokens::reserved_balance(SGD, &SENDER), 400);
+		assert_eq!(NexusTokens::free_balance(SGD, &SENDER), 600);
+		assert_eq!(
+			last_event(),
+			Event::HtlcLocked(hashlock(), SENDER, RECIPIENT, SGD, 400, 6)
+		);
+	});
+}
+
+#[test]
+fn lock_fails_for_existing_locks() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+
+		assert_noop!(
+			NexusHtlc::lock(Origin::signed(SENDER), RECIPIENT, SGD, 100, hashlock(), 6),
+			Error::<Test>::HtlcExists
+		);
+	});
+}
+
+#[test]
+fn lock_fails_below_the_minimum_lock_period() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusHtlc::lock(Origin::signed(SENDER), RECIPIENT, SGD, 400, hashlock(), 5),
+			Error::<Test>::LockPeriodTooShort
+		);
+	});
+}
+
+#[test]
+fn lock_fails_without_enough_funds() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusHtlc::lock(Origin::signed(SENDER), RECIPIENT, SGD, 1_001, hashlock(), 6),
+			nexus_tokens::Error::<Test>::BalanceTooLow
+		);
+	});
+}
+
+#[test]
+fn lock_is_available_to_other_pallets() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(<NexusHtlc as HtlcLocker<_, _, _>>::lock(
+			&SENDER,
+			&RECIPIENT,
+			SGD,
+			400,
+			hashlock(),
+			6
+		));
+
+		assert_eq!(NexusHtlc::htlc(hashlock()).unwrap().sender, SENDER);
+	});
+}
+
+#[test]
+fn claim_pays_the_recipient() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+
+		assert_ok!(NexusHtlc::claim(
+			Origin::signed(RECIPIENT),
+			hashlock(),
+			PREIMAGE.to_vec()
+		));
+
+		let htlc = NexusHtlc::htlc(hashlock()).unwrap();
+		assert_eq!(htlc.state, HtlcState::Claimed);
+		assert_eq!(htlc.preimage, Some(PREIMAGE.to_vec()));
+		assert_eq!(NexusTokens::reserved_balance(SGD, &SENDER), 0);
+		assert_eq!(NexusTokens::free_balance(SGD, &RECIPIENT), 400);
+		assert_eq!(resolved(), vec![(hashlock(), true)]);
+		assert_eq!(
+			last_event(),
+			Event::HtlcClaimed(hashlock(), PREIMAGE.to_vec())
+		);
+	});
+}
+
+#[test]
+fn claim_fails_for_unknown_locks() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusHtlc::claim(Origin::signed(RECIPIENT), hashlock(), PREIMAGE.to_vec()),
+			Error::<Test>::UnknownHtlc
+		);
+	});
+}
+
+#[test]
+fn claim_fails_with_the_wrong_preimage() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+
+		assert_noop!(
+			NexusHtlc::claim(Origin::signed(RECIPIENT), hashlock(), b"guess".to_vec()),
+			Error::<Test>::InvalidPreimage
+		);
+	});
+}
+
+#[test]
+fn claim_fails_after_expiry() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+		System::set_block_number(6);
+
+		assert_noop!(
+			NexusHtlc::claim(Origin::signed(RECIPIENT), hashlock(), PREIMAGE.to_vec()),
+			Error::<Test>::HtlcExpired
+		);
+	});
+}
+
+#[test]
+fn claim_fails_once_resolved() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+		assert_ok!(NexusHtlc::claim(
+			Origin::signed(RECIPIENT),
+			hashlock(),
+			PREIMAGE.to_vec()
+		));
+
+		assert_noop!(
+			NexusHtlc::claim(Origin::signed(RECIPIENT), hashlock(), PREIMAGE.to_vec()),
+			Error::<Test>::AlreadyResolved
+		);
+	});
+}
+
+#[test]
+fn refund_pays_the_sender_back() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+		System::set_block_number(6);
+
+		assert_ok!(NexusHtlc::refund(Origin::signed(RECIPIENT), hashlock()));
+
+		assert_eq!(
+			NexusHtlc::htlc(hashlock()).unwrap().state,
+			HtlcState::Refunded
+		);
+		assert_eq!(NexusTokens::reserved_balance(SGD, &SENDER), 0);
+		assert_eq!(NexusTokens::free_balance(SGD, &SENDER), 1_000);
+		assert_eq!(resolved(), vec![(hashlock(), false)]);
+		assert_eq!(last_event(), Event::HtlcRefunded(hashlock()));
+	});
+}
+
+#[test]
+fn refund_fails_before_expiry() {
+	new_test_ext().execute_with(|| {
+		lock(6);
+
+		assert_noop!(
+			NexusHtlc::refund(Origin::signed(SENDER), hashlock()),
+			Error::<Test>::HtlcNotExpired
+		);
+	});
+}
+
+#[test]
+fn refund_fails_for_unknown_and_resolved_locks() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusHtlc::refund(Origin::signed(SENDER), hashlock()),
+			Error::<Test>::UnknownHtlc
+		);
+
+		lock(6);
+		System::set_block_number(6);
+		assert_ok!(NexusHtlc::refund(Origin::signed(SENDER), hashlock()));
+
+		assert_noop!(
+			NexusHtlc::refund(Origin::signed(SENDER), hashlock()),
+			Error::<Test>::AlreadyResolved
+		);
+	});
+}
@@ -0,0 +1,28 @@
+//! Benchmarks for nexus-roles.
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks};
+
+benchmarks! {
+	grant_role {
+		let who: T::AccountId = account("participant", 0, 0);
+		let origin = T::AdminOrigin::successful_origin();
+	}: _(origin, who.clone(), Role::SourcePsp)
+	verify {
+		assert!(Pallet::<T>::has_role(&who, Role::SourcePsp));
+	}
+
+	revoke_role {
+		let who: T::AccountId = account("participant", 0, 0);
+		Pallet::<T>::grant_role(
+			T::AdminOrigin::successful_origin(),
+			who.clone(),
+			Role::SourcePsp,
+		)?;
+		let origin = T::AdminOrigin::successful_origin();
+	}: _(origin, who.clone(), Role::SourcePsp)
+	verify {
+		assert!(!Pallet::<T>::has_role(&who, Role::SourcePsp));
+	}
+}
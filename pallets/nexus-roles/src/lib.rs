@@ -0,0 +1,203 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	parameter_types,
+	traits::{EnsureOrigin, Get},
+};
+use frame_system::RawOrigin;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+use sp_std::{marker::PhantomData, prelude::*};
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// What a participant may do in Nexus.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+	/// Operates an instant payment system and publishes its SLD.
+	IpsOperator,
+	/// Foreign exchange provider quoting rates and settling conversions.
+	Fxp,
+	/// PSP sending payments on behalf of debtors.
+	SourcePsp,
+	/// PSP receiving payments on behalf of creditors.
+	DestinationPsp,
+	/// Oversees payments, screening and limits.
+	Regulator,
+	/// Connects an IPS to Nexus and relays messages for its participants.
+	NexusGateway,
+}
+
+parameter_types! {
+	pub const IpsOperatorRole: Role = Role::IpsOperator;
+	pub const FxpRole: Role = Role::Fxp;
+	pub const SourcePspRole: Role = Role::SourcePsp;
+	pub const DestinationPspRole: Role = Role::DestinationPsp;
+	pub const RegulatorRole: Role = Role::Regulator;
+	pub const NexusGatewayRole: Role = Role::NexusGateway;
+}
+
+/// Ensures the origin is signed by an account holding the role `R`, and yields that account.
+pub struct EnsureRole<T, R>(PhantomData<(T, R)>);
+
+impl<T: Config, R: Get<Role>> EnsureOrigin<T::Origin> for EnsureRole<T, R> {
+	type Success = T::AccountId;
+
+	fn try_origin(o: T::Origin) -> Result<Self::Success, T::Origin> {
+		o.into().and_then(|o| match o {
+			RawOrigin::Signed(who) if Pallet::<T>::has_role(&who, R::get()) => Ok(who),
+			r => Err(T::Origin::from(r)),
+		})
+	}
+
+	/// An account of its own for every role, which is granted the role.
+	#[cfg(feature = "runtime-benchmarks")]
+	fn successful_origin() -> T::Origin {
+		let role = R::get();
+		let who: T::AccountId = frame_benchmarking::account("role", role as u32, 0);
+		<Roles<T>>::insert(&who, role, ());
+		T::Origin::from(RawOrigin::Signed(who))
+	}
+}
+
+pub type EnsureIpsOperator<T> = EnsureRole<T, IpsOperatorRole>;
+pub type EnsureFxp<T> = EnsureRole<T, FxpRole>;
+pub type EnsureSourcePsp<T> = EnsureRole<T, SourcePspRole>;
+pub type EnsureDestinationPsp<T> = EnsureRole<T, DestinationPspRole>;
+pub type EnsureRegulator<T> = EnsureRole<T, RegulatorRole>;
+pub type EnsureNexusGateway<T> = EnsureRole<T, NexusGatewayRole>;
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Who may grant and revoke roles.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	pub(super) type Roles<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, Role, ()>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The roles held at genesis.
+		pub roles: Vec<(T::AccountId, Role)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { roles: Vec::new() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (who, role) in &self.roles {
+				<Roles<T>>::insert(who, role, ());
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An account has been given a role. [who, role]
+		RoleGranted(T::AccountId, Role),
+
+		/// A role has been taken away from an account. [who, role]
+		RoleRevoked(T::AccountId, Role),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account already holds the role
+		RoleAlreadyGranted,
+
+		/// The account does not hold the role
+		RoleNotGranted,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Give `who` the role `role`.
+		#[pallet::weight(T::WeightInfo::grant_role())]
+		pub fn grant_role(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			role: Role,
+		) -> DispatchResultWithPostInfo {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(!Self::has_role(&who, role), Error::<T>::RoleAlreadyGranted);
+
+			<Roles<T>>::insert(&who, role, ());
+
+			Self::deposit_event(Event::RoleGranted(who, role));
+
+			Ok(().into())
+		}
+
+		/// Take the role `role` away from `who`.
+		#[pallet::weight(T::WeightInfo::revoke_role())]
+		pub fn revoke_role(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			role: Role,
+		) -> DispatchResultWithPostInfo {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(Self::has_role(&who, role), Error::<T>::RoleNotGranted);
+
+			<Roles<T>>::remove(&who, role);
+
+			Self::deposit_event(Event::RoleRevoked(who, role));
+
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether `who` holds the role `role`.
+	pub fn has_role(who: &T::AccountId, role: Role) -> bool {
+		<Roles<T>>::contains_key(who, role)
+	}
+
+	/// Every role `who` holds.
+	pub fn roles_of(who: &T::AccountId) -> Vec<Role> {
+		<Roles<T>>::iter_prefix(who).map(|(role, _)| role).collect()
+	}
+}
@@ -0,0 +1,78 @@
+use crate::{mock::*, EnsureIpsOperator, EnsureSourcePsp, Error, Event, Role};
+use frame_support::{assert_noop, assert_ok, traits::EnsureOrigin};
+use sp_runtime::DispatchError;
+
+#[test]
+fn genesis_roles_are_granted() {
+	new_test_ext().execute_with(|| {
+		assert!(NexusRoles::has_role(&IPS, Role::IpsOperator));
+		assert_eq!(NexusRoles::roles_of(&IPS), vec![Role::IpsOperator]);
+		assert!(NexusRoles::roles_of(&PSP).is_empty());
+	});
+}
+
+#[test]
+fn grant_role_gives_the_role() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusRoles::grant_role(Origin::root(), PSP, Role::SourcePsp));
+
+		assert!(NexusRoles::has_role(&PSP, Role::SourcePsp));
+		assert!(!NexusRoles::has_role(&PSP, Role::DestinationPsp));
+		assert_eq!(last_event(), Event::RoleGranted(PSP, Role::SourcePsp));
+		assert_noop!(
+			NexusRoles::grant_role(Origin::root(), PSP, Role::SourcePsp),
+			Error::<Test>::RoleAlreadyGranted
+		);
+	});
+}
+
+#[test]
+fn revoke_role_takes_the_role_away() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusRoles::revoke_role(
+			Origin::root(),
+			IPS,
+			Role::IpsOperator
+		));
+
+		assert!(!NexusRoles::has_role(&IPS, Role::IpsOperator));
+		assert_eq!(last_event(), Event::RoleRevoked(IPS, Role::IpsOperator));
+		assert_noop!(
+			NexusRoles::revoke_role(Origin::root(), IPS, Role::IpsOperator),
+			Error::<Test>::RoleNotGranted
+		);
+	});
+}
+
+#[test]
+fn roles_are_managed_by_the_admin_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusRoles::grant_role(Origin::signed(IPS), PSP, Role::Fxp),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			NexusRoles::revoke_role(Origin::signed(IPS), IPS, Role::IpsOperator),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn ensure_role_checks_the_signer() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			EnsureIpsOperator::<Test>::try_origin(Origin::signed(IPS)).ok(),
+			Some(IPS)
+		);
+		assert!(EnsureIpsOperator::<Test>::try_origin(Origin::signed(PSP)).is_err());
+		assert!(EnsureIpsOperator::<Test>::try_origin(Origin::root()).is_err());
+		assert!(EnsureSourcePsp::<Test>::try_origin(Origin::signed(IPS)).is_err());
+
+		assert_ok!(NexusRoles::grant_role(Origin::root(), PSP, Role::SourcePsp));
+		assert_eq!(
+			EnsureSourcePsp::<Test>::try_origin(Origin::signed(PSP)).ok(),
+			Some(PSP)
+		);
+	});
+}
@@ -0,0 +1,16 @@
+//! Benchmarks for nexus-screening.
+
+use super::*;
+
+use frame_benchmarking::benchmarks;
+use frame_support::traits::EnsureOrigin;
+
+benchmarks! {
+	set_listing {
+		let origin = T::ListOrigin::successful_origin();
+		let identifier = T::Hash::default();
+	}: _(origin, identifier, ScreeningOutcome::Hold)
+	verify {
+		assert_eq!(Pallet::<T>::watchlist(identifier), Some(ScreeningOutcome::Hold));
+	}
+}
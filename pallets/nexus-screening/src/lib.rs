@@ -0,0 +1,118 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+
+use sp_std::prelude::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// What should happen to a payment after screening its debtor and creditor.
+#[derive(Encode, Decode, Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum ScreeningOutcome {
+	/// Nothing was found and the payment may go ahead.
+	Pass,
+	/// A possible match was found and a compliance officer has to release the payment.
+	Hold,
+	/// A match was found and the payment must not go ahead.
+	Reject,
+}
+
+impl Default for ScreeningOutcome {
+	fn default() -> Self {
+		ScreeningOutcome::Pass
+	}
+}
+
+/// Screens the parties of a payment before it is accepted.
+pub trait ScreeningProvider<AccountId, Hash> {
+	/// Screen a payment sent by `source_psp`. `debtor` and `creditor` are the hashed identifiers
+	/// of the two parties.
+	fn screen(source_psp: &AccountId, debtor: &Hash, creditor: &Hash) -> ScreeningOutcome;
+}
+
+impl<AccountId, Hash> ScreeningProvider<AccountId, Hash> for () {
+	fn screen(_: &AccountId, _: &Hash, _: &Hash) -> ScreeningOutcome {
+		ScreeningOutcome::Pass
+	}
+}
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Who may change the watchlist.
+		type ListOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	#[pallet::getter(fn watchlist)]
+	pub(super) type Watchlist<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::Hash, ScreeningOutcome>;
+
+	#[pallet::event]
+	#[pallet::metadata(T::Hash = "Hash")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A hashed identifier has been listed or delisted. [identifier, outcome]
+		ListingSet(T::Hash, ScreeningOutcome),
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Put a hashed identifier on the watchlist. `Pass` takes it off again.
+		#[pallet::weight(T::WeightInfo::set_listing())]
+		pub fn set_listing(
+			origin: OriginFor<T>,
+			identifier: T::Hash,
+			outcome: ScreeningOutcome,
+		) -> DispatchResultWithPostInfo {
+			T::ListOrigin::ensure_origin(origin)?;
+
+			match outcome {
+				ScreeningOutcome::Pass => <Watchlist<T>>::remove(&identifier),
+				_ => <Watchlist<T>>::insert(&identifier, outcome),
+			}
+
+			Self::deposit_event(Event::ListingSet(identifier, outcome));
+
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> ScreeningProvider<T::AccountId, T::Hash> for Pallet<T> {
+	fn screen(_: &T::AccountId, debtor: &T::Hash, creditor: &T::Hash) -> ScreeningOutcome {
+		let debtor = Self::watchlist(debtor).unwrap_or_default();
+		let creditor = Self::watchlist(creditor).unwrap_or_default();
+
+		debtor.max(creditor)
+	}
+}
@@ -0,0 +1,76 @@
+use crate::{mock::*, Event, ScreeningOutcome, ScreeningProvider};
+use frame_support::{assert_noop, assert_ok};
+use sp_core::H256;
+
+const PSP: u64 = 1;
+
+fn id(n: u8) -> H256 {
+	H256::repeat_byte(n)
+}
+
+#[test]
+fn set_listing_lists_and_delists() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusScreening::set_listing(
+			Origin::root(),
+			id(1),
+			ScreeningOutcome::Hold
+		));
+		assert_eq!(NexusScreening::watchlist(id(1)), Some(ScreeningOutcome::Hold));
+		assert_eq!(
+			last_event(),
+			Event::ListingSet(id(1), ScreeningOutcome::Hold)
+		);
+
+		assert_ok!(NexusScreening::set_listing(
+			Origin::root(),
+			id(1),
+			ScreeningOutcome::Pass
+		));
+		assert_eq!(NexusScreening::watchlist(id(1)), None);
+		assert_eq!(
+			last_event(),
+			Event::ListingSet(id(1), ScreeningOutcome::Pass)
+		);
+	});
+}
+
+#[test]
+fn set_listing_requires_the_list_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusScreening::set_listing(Origin::signed(PSP), id(1), ScreeningOutcome::Reject),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn screen_returns_the_strictest_outcome_of_both_parties() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			NexusScreening::screen(&PSP, &id(1), &id(2)),
+			ScreeningOutcome::Pass
+		);
+
+		assert_ok!(NexusScreening::set_listing(
+			Origin::root(),
+			id(2),
+			ScreeningOutcome::Hold
+		));
+		assert_eq!(
+			NexusScreening::screen(&PSP, &id(1), &id(2)),
+			ScreeningOutcome::Hold
+		);
+
+		assert_ok!(NexusScreening::set_listing(
+			Origin::root(),
+			id(1),
+			ScreeningOutcome::Reject
+		));
+		assert_eq!(
+			NexusScreening::screen(&PSP, &id(1), &id(2)),
+			ScreeningOutcome::Reject
+		);
+	});
+}
@@ -0,0 +1,30 @@
+//! Weights for nexus_screening.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for nexus_screening.
+pub trait WeightInfo {
+	fn set_listing() -> Weight;
+}
+
+/// Weights for nexus_screening.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn set_listing() -> Weight {
+		(17_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn set_listing() -> Weight {
+		(17_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+}
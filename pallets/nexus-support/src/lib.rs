@@ -0,0 +1,37 @@
+//! Checks and migration helpers shared by the Nexus pallets.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{storage::unhashed, traits::Get};
+use sp_io::hashing::twox_128;
+
+#[cfg(test)]
+mod tests;
+
+/// Fail with `error` if any of `fields` is longer than `Max` bytes.
+pub fn ensure_field_lengths<Max: Get<u32>, E>(fields: &[&[u8]], error: E) -> Result<(), E> {
+	let max = Max::get() as usize;
+	if fields.iter().all(|field| field.len() <= max) {
+		Ok(())
+	} else {
+		Err(error)
+	}
+}
+
+/// Remove every value of the storage item `item` of the pallet stored under `module`, and return
+/// how many there were.
+pub fn remove_storage_item(module: &[u8], item: &[u8]) -> u64 {
+	let prefix = [twox_128(module), twox_128(item)].concat();
+	let mut removed = 0;
+	let mut previous_key = prefix.clone();
+	while let Some(key) =
+		sp_io::storage::next_key(&previous_key).filter(|key| key.starts_with(&prefix))
+	{
+		unhashed::kill(&key);
+
+		removed += 1;
+		previous_key = key;
+	}
+
+	removed
+}
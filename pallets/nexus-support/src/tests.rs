@@ -0,0 +1,38 @@
+use crate::{ensure_field_lengths, remove_storage_item};
+use frame_support::{
+	parameter_types,
+	storage::migration::{get_storage_value, put_storage_value},
+};
+
+parameter_types! {
+	pub const MaxFieldLength: u32 = 4;
+}
+
+#[test]
+fn fields_up_to_the_limit_pass() {
+	assert_eq!(
+		ensure_field_lengths::<MaxFieldLength, _>(&[&b""[..], b"SGD", b"INR1"], "too long"),
+		Ok(())
+	);
+}
+
+#[test]
+fn any_longer_field_fails() {
+	assert_eq!(
+		ensure_field_lengths::<MaxFieldLength, _>(&[&b"SGD"[..], b"INR12"], "too long"),
+		Err("too long")
+	);
+}
+
+#[test]
+fn removing_a_storage_item_leaves_the_others() {
+	sp_io::TestExternalities::default().execute_with(|| {
+		for &(item, key) in [(&b"Gone"[..], &b"a"[..]), (b"Gone", b"b"), (b"Kept", b"a")].iter() {
+			put_storage_value(b"Pallet", item, key, 1u32);
+		}
+
+		assert_eq!(remove_storage_item(b"Pallet", b"Gone"), 2);
+		assert_eq!(get_storage_value::<u32>(b"Pallet", b"Gone", b"a"), None);
+		assert_eq!(get_storage_value::<u32>(b"Pallet", b"Kept", b"a"), Some(1));
+	});
+}
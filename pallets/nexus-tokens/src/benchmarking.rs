@@ -0,0 +1,60 @@
+//! Benchmarks for nexus-tokens.
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+const CURRENCY: CurrencyCode = *b"SGD";
+
+benchmarks! {
+	create_asset {
+		let issuer: T::AccountId = account("issuer", 0, SEED);
+	}: _(RawOrigin::Root, CURRENCY, issuer, 2)
+	verify {
+		assert!(Assets::<T>::contains_key(CURRENCY));
+	}
+
+	set_issuer {
+		let issuer: T::AccountId = account("issuer", 0, SEED);
+		let new_issuer: T::AccountId = account("issuer", 1, SEED);
+		Pallet::<T>::create_asset(RawOrigin::Root.into(), CURRENCY, issuer.clone(), 2)?;
+	}: _(RawOrigin::Root, CURRENCY, new_issuer.clone())
+	verify {
+		assert_eq!(Pallet::<T>::asset(CURRENCY).map(|details| details.issuer), Some(new_issuer));
+	}
+
+	// Minting into an account that does not hold the currency yet.
+	mint {
+		let issuer: T::AccountId = whitelisted_caller();
+		let beneficiary: T::AccountId = account("beneficiary", 0, SEED);
+		Pallet::<T>::create_asset(RawOrigin::Root.into(), CURRENCY, issuer.clone(), 2)?;
+	}: _(RawOrigin::Signed(issuer), CURRENCY, beneficiary.clone(), 1_000u32.into())
+	verify {
+		assert_eq!(Pallet::<T>::account(CURRENCY, &beneficiary).free, 1_000u32.into());
+	}
+
+	// Burning the whole balance, which removes the account.
+	burn {
+		let issuer: T::AccountId = whitelisted_caller();
+		let who: T::AccountId = account("who", 0, SEED);
+		Pallet::<T>::create_asset(RawOrigin::Root.into(), CURRENCY, issuer.clone(), 2)?;
+		Pallet::<T>::mint(RawOrigin::Signed(issuer.clone()).into(), CURRENCY, who.clone(), 1_000u32.into())?;
+	}: _(RawOrigin::Signed(issuer), CURRENCY, who.clone(), 1_000u32.into())
+	verify {
+		assert_eq!(Pallet::<T>::account(CURRENCY, &who), Default::default());
+	}
+
+	// Transferring the whole balance to an account that does not hold the currency yet.
+	transfer {
+		let issuer: T::AccountId = account("issuer", 0, SEED);
+		let caller: T::AccountId = whitelisted_caller();
+		let dest: T::AccountId = account("dest", 0, SEED);
+		Pallet::<T>::create_asset(RawOrigin::Root.into(), CURRENCY, issuer.clone(), 2)?;
+		Pallet::<T>::mint(RawOrigin::Signed(issuer).into(), CURRENCY, caller.clone(), 1_000u32.into())?;
+	}: _(RawOrigin::Signed(caller.clone()), CURRENCY, dest.clone(), 1_000u32.into())
+	verify {
+		assert_eq!(Pallet::<T>::account(CURRENCY, &dest).free, 1_000u32.into());
+	}
+}
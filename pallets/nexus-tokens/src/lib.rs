@@ -0,0 +1,448 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::{DispatchError, DispatchResult},
+	ensure, Parameter,
+};
+use sp_runtime::traits::{
+	AtLeast32BitUnsigned, CheckedAdd, CheckedSub, MaybeSerializeDeserialize, Member, Saturating,
+	Zero,
+};
+
+use sp_std::prelude::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub use weights::WeightInfo;
+
+/// ISO 4217 alphabetic currency code, e.g. `*b"EUR"`.
+pub type CurrencyCode = [u8; 3];
+
+/// Reservable balances held in several currencies, keyed by ISO 4217 code.
+pub trait MultiReservableCurrency<AccountId> {
+	/// The balance of an account in any one currency.
+	type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+
+	/// The free balance of `who` in `currency`.
+	fn free_balance(currency: CurrencyCode, who: &AccountId) -> Self::Balance;
+
+	/// The reserved balance of `who` in `currency`.
+	fn reserved_balance(currency: CurrencyCode, who: &AccountId) -> Self::Balance;
+
+	/// Move `amount` of free balance from `from` to `to`.
+	fn transfer(
+		currency: CurrencyCode,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult;
+
+	/// Move `amount` of free balance of `who` to its reserved balance.
+	fn reserve(currency: CurrencyCode, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+	/// Move up to `amount` of reserved balance of `who` back to its free balance.
+	/// Returns the part of `amount` that could not be unreserved.
+	fn unreserve(currency: CurrencyCode, who: &AccountId, amount: Self::Balance) -> Self::Balance;
+
+	/// Move up to `amount` of reserved balance of `slashed` to the free balance of `beneficiary`.
+	/// Returns the part of `amount` that could not be moved.
+	fn repatriate_reserved(
+		currency: CurrencyCode,
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		amount: Self::Balance,
+	) -> Result<Self::Balance, DispatchError>;
+}
+
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct AssetDetails<AccountId, Balance> {
+	issuer: AccountId,
+	supply: Balance,
+	decimals: u8,
+}
+
+#[derive(Encode, Decode, Default, Clone, Debug, Eq, PartialEq)]
+pub struct AccountData<Balance> {
+	free: Balance,
+	reserved: Balance,
+}
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The balance of an account in any one currency.
+		type Balance: Parameter
+			+ Member
+			+ AtLeast32BitUnsigned
+			+ Default
+			+ Copy
+			+ MaybeSerializeDeserialize;
+
+		/// Weights of the calls of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::storage]
+	#[pallet::getter(fn asset)]
+	pub(super) type Assets<T: Config> =
+		StorageMap<_, Blake2_128Concat, CurrencyCode, AssetDetails<T::AccountId, T::Balance>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn account)]
+	pub(super) type Accounts<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		CurrencyCode,
+		Blake2_128Concat,
+		T::AccountId,
+		AccountData<T::Balance>,
+		ValueQuery,
+	>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The CBDCs that exist at genesis: `(code, issuing central bank, decimals)`.
+		pub assets: Vec<(CurrencyCode, T::AccountId, u8)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { assets: Vec::new() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (code, issuer, decimals) in &self.assets {
+				assert!(
+					Pallet::<T>::is_valid_code(code),
+					"invalid ISO 4217 currency code in genesis"
+				);
+				let details = AssetDetails {
+					issuer: issuer.clone(),
+					supply: Zero::zero(),
+					decimals: *decimals,
+				};
+				<Assets<T>>::insert(code, details);
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", T::Balance = "Balance")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A new CBDC has been registered. [currency, issuer]
+		AssetCreated(CurrencyCode, T::AccountId),
+
+		/// The issuing central bank of a CBDC has changed. [currency, issuer]
+		IssuerChanged(CurrencyCode, T::AccountId),
+
+		/// The issuer has created new units. [currency, beneficiary, amount]
+		Minted(CurrencyCode, T::AccountId, T::Balance),
+
+		/// The issuer has destroyed units. [currency, who, amount]
+		Burned(CurrencyCode, T::AccountId, T::Balance),
+
+		/// Units have been moved between accounts. [currency, from, to, amount]
+		Transferred(CurrencyCode, T::AccountId, T::AccountId, T::Balance),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The currency code is not three upper case ASCII letters
+		InvalidCurrencyCode,
+
+		/// There is already an asset for the given currency
+		AssetExists,
+
+		/// There is no asset for the given currency
+		UnknownAsset,
+
+		/// Only the issuing central bank may mint or burn this currency
+		NotIssuer,
+
+		/// The account does not hold enough of the currency
+		BalanceTooLow,
+
+		/// The operation would overflow a balance or the total supply
+		Overflow,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register a new CBDC and the central bank allowed to issue it.
+		#[pallet::weight(T::WeightInfo::create_asset())]
+		pub fn create_asset(
+			origin: OriginFor<T>,
+			currency: CurrencyCode,
+			issuer: T::AccountId,
+			decimals: u8,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			ensure!(
+				Self::is_valid_code(&currency),
+				Error::<T>::InvalidCurrencyCode
+			);
+			ensure!(
+				!<Assets<T>>::contains_key(&currency),
+				Error::<T>::AssetExists
+			);
+
+			let details = AssetDetails {
+				issuer: issuer.clone(),
+				supply: Zero::zero(),
+				decimals,
+			};
+			<Assets<T>>::insert(&currency, details);
+
+			Self::deposit_event(Event::AssetCreated(currency, issuer));
+			Ok(().into())
+		}
+
+		/// Hand the issuance of a CBDC to another central bank account.
+		#[pallet::weight(T::WeightInfo::set_issuer())]
+		pub fn set_issuer(
+			origin: OriginFor<T>,
+			currency: CurrencyCode,
+			issuer: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			<Assets<T>>::try_mutate(&currency, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::UnknownAsset)?;
+				details.issuer = issuer.clone();
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::IssuerChanged(currency, issuer));
+			Ok(().into())
+		}
+
+		/// Create new units of a CBDC. Only callable by its issuing central bank.
+		#[pallet::weight(T::WeightInfo::mint())]
+		pub fn mint(
+			origin: OriginFor<T>,
+			currency: CurrencyCode,
+			beneficiary: T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+			let mut details = Self::asset(&currency).ok_or(Error::<T>::UnknownAsset)?;
+			ensure!(details.issuer == user, Error::<T>::NotIssuer);
+
+			details.supply = details
+				.supply
+				.checked_add(&amount)
+				.ok_or(Error::<T>::Overflow)?;
+
+			let mut account = Self::account(&currency, &beneficiary);
+			account.free = account
+				.free
+				.checked_add(&amount)
+				.ok_or(Error::<T>::Overflow)?;
+
+			<Assets<T>>::insert(&currency, details);
+			Self::set_account(currency, &beneficiary, account);
+
+			Self::deposit_event(Event::Minted(currency, beneficiary, amount));
+			Ok(().into())
+		}
+
+		/// Destroy units of a CBDC held by `who`. Only callable by its issuing central bank.
+		#[pallet::weight(T::WeightInfo::burn())]
+		pub fn burn(
+			origin: OriginFor<T>,
+			currency: CurrencyCode,
+			who: T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+			let mut details = Self::asset(&currency).ok_or(Error::<T>::UnknownAsset)?;
+			ensure!(details.issuer == user, Error::<T>::NotIssuer);
+
+			let mut account = Self::account(&currency, &who);
+			account.free = account
+				.free
+				.checked_sub(&amount)
+				.ok_or(Error::<T>::BalanceTooLow)?;
+			details.supply = details.supply.saturating_sub(amount);
+
+			<Assets<T>>::insert(&currency, details);
+			Self::set_account(currency, &who, account);
+
+			Self::deposit_event(Event::Burned(currency, who, amount));
+			Ok(().into())
+		}
+
+		/// Send units of a CBDC to another account.
+		#[pallet::weight(T::WeightInfo::transfer())]
+		pub fn transfer(
+			origin: OriginFor<T>,
+			currency: CurrencyCode,
+			dest: T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResultWithPostInfo {
+			let user = ensure_signed(origin)?;
+
+			<Self as MultiReservableCurrency<T::AccountId>>::transfer(
+				currency, &user, &dest, amount,
+			)?;
+
+			Self::deposit_event(Event::Transferred(currency, user, dest, amount));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether `code` looks like an ISO 4217 alphabetic code.
+	pub fn is_valid_code(code: &CurrencyCode) -> bool {
+		code.iter().all(|c| c.is_ascii_uppercase())
+	}
+
+	fn ensure_asset(currency: &CurrencyCode) -> DispatchResult {
+		ensure!(
+			<Assets<T>>::contains_key(currency),
+			Error::<T>::UnknownAsset
+		);
+		Ok(())
+	}
+
+	/// Write an account back, dropping it once it holds nothing.
+	fn set_account(currency: CurrencyCode, who: &T::AccountId, account: AccountData<T::Balance>) {
+		if account.free.is_zero() && account.reserved.is_zero() {
+			<Accounts<T>>::remove(&currency, who);
+		} else {
+			<Accounts<T>>::insert(&currency, who, account);
+		}
+	}
+}
+
+impl<T: Config> MultiReservableCurrency<T::AccountId> for Pallet<T> {
+	type Balance = T::Balance;
+
+	fn free_balance(currency: CurrencyCode, who: &T::AccountId) -> Self::Balance {
+		Self::account(&currency, who).free
+	}
+
+	fn reserved_balance(currency: CurrencyCode, who: &T::AccountId) -> Self::Balance {
+		Self::account(&currency, who).reserved
+	}
+
+	fn transfer(
+		currency: CurrencyCode,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		Self::ensure_asset(&currency)?;
+		if amount.is_zero() || from == to {
+			return Ok(());
+		}
+
+		let mut from_account = Self::account(&currency, from);
+		from_account.free = from_account
+			.free
+			.checked_sub(&amount)
+			.ok_or(Error::<T>::BalanceTooLow)?;
+
+		let mut to_account = Self::account(&currency, to);
+		to_account.free = to_account
+			.free
+			.checked_add(&amount)
+			.ok_or(Error::<T>::Overflow)?;
+
+		Self::set_account(currency, from, from_account);
+		Self::set_account(currency, to, to_account);
+		Ok(())
+	}
+
+	fn reserve(
+		currency: CurrencyCode,
+		who: &T::AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		Self::ensure_asset(&currency)?;
+
+		let mut account = Self::account(&currency, who);
+		account.free = account
+			.free
+			.checked_sub(&amount)
+			.ok_or(Error::<T>::BalanceTooLow)?;
+		account.reserved = account
+			.reserved
+			.checked_add(&amount)
+			.ok_or(Error::<T>::Overflow)?;
+
+		Self::set_account(currency, who, account);
+		Ok(())
+	}
+
+	fn unreserve(
+		currency: CurrencyCode,
+		who: &T::AccountId,
+		amount: Self::Balance,
+	) -> Self::Balance {
+		let mut account = Self::account(&currency, who);
+		let actual = amount.min(account.reserved);
+		account.reserved -= actual;
+		account.free = account.free.saturating_add(actual);
+
+		Self::set_account(currency, who, account);
+		amount - actual
+	}
+
+	fn repatriate_reserved(
+		currency: CurrencyCode,
+		slashed: &T::AccountId,
+		beneficiary: &T::AccountId,
+		amount: Self::Balance,
+	) -> Result<Self::Balance, DispatchError> {
+		Self::ensure_asset(&currency)?;
+		if slashed == beneficiary {
+			return Ok(Self::unreserve(currency, slashed, amount));
+		}
+
+		let mut from_account = Self::account(&currency, slashed);
+		let actual = amount.min(from_account.reserved);
+		from_account.reserved -= actual;
+
+		let mut to_account = Self::account(&currency, beneficiary);
+		to_account.free = to_account
+			.free
+			.checked_add(&actual)
+			.ok_or(Error::<T>::Overflow)?;
+
+		Self::set_account(currency, slashed, from_account);
+		Self::set_account(currency, beneficiary, to_account);
+		Ok(amount - actual)
+	}
+}
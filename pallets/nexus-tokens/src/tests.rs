@@ -0,0 +1,246 @@
+use crate::{mock::*, Error, Event, MultiReservableCurrency};
+use frame_support::{assert_noop, assert_ok};
+use frame_system::RawOrigin;
+
+const INR: [u8; 3] = *b"INR";
+
+fn mint(who: u64, amount: u64) {
+	assert_ok!(NexusTokens::mint(
+		Origin::signed(CENTRAL_BANK),
+		SGD,
+		who,
+		amount
+	));
+}
+
+#[test]
+fn genesis_registers_assets() {
+	new_test_ext().execute_with(|| {
+		let details = NexusTokens::asset(SGD).unwrap();
+		assert_eq!(details.issuer, CENTRAL_BANK);
+		assert_eq!(details.supply, 0);
+		assert_eq!(details.decimals, 2);
+	});
+}
+
+#[test]
+fn create_asset_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusTokens::create_asset(
+			RawOrigin::Root.into(),
+			INR,
+			CENTRAL_BANK,
+			2
+		));
+
+		assert_eq!(NexusTokens::asset(INR).unwrap().issuer, CENTRAL_BANK);
+		assert_eq!(last_event(), Event::AssetCreated(INR, CENTRAL_BANK));
+	});
+}
+
+#[test]
+fn create_asset_requires_root() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusTokens::create_asset(Origin::signed(ALICE), INR, ALICE, 2),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn create_asset_rejects_invalid_codes() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusTokens::create_asset(RawOrigin::Root.into(), *b"inr", CENTRAL_BANK, 2),
+			Error::<Test>::InvalidCurrencyCode
+		);
+	});
+}
+
+#[test]
+fn create_asset_rejects_existing_assets() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusTokens::create_asset(RawOrigin::Root.into(), SGD, ALICE, 2),
+			Error::<Test>::AssetExists
+		);
+	});
+}
+
+#[test]
+fn set_issuer_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(NexusTokens::set_issuer(RawOrigin::Root.into(), SGD, ALICE));
+
+		assert_eq!(NexusTokens::asset(SGD).unwrap().issuer, ALICE);
+		assert_eq!(last_event(), Event::IssuerChanged(SGD, ALICE));
+		assert_noop!(
+			NexusTokens::mint(Origin::signed(CENTRAL_BANK), SGD, BOB, 100),
+			Error::<Test>::NotIssuer
+		);
+	});
+}
+
+#[test]
+fn set_issuer_fails_for_unknown_assets() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusTokens::set_issuer(RawOrigin::Root.into(), INR, ALICE),
+			Error::<Test>::UnknownAsset
+		);
+		assert_noop!(
+			NexusTokens::set_issuer(Origin::signed(CENTRAL_BANK), SGD, ALICE),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn mint_works() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+
+		assert_eq!(NexusTokens::free_balance(SGD, &ALICE), 1_000);
+		assert_eq!(NexusTokens::asset(SGD).unwrap().supply, 1_000);
+		assert_eq!(last_event(), Event::Minted(SGD, ALICE, 1_000));
+	});
+}
+
+#[test]
+fn mint_fails_for_non_issuers_unknown_assets_and_overflow() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NexusTokens::mint(Origin::signed(ALICE), SGD, ALICE, 1_000),
+			Error::<Test>::NotIssuer
+		);
+		assert_noop!(
+			NexusTokens::mint(Origin::signed(CENTRAL_BANK), INR, ALICE, 1_000),
+			Error::<Test>::UnknownAsset
+		);
+
+		mint(ALICE, u64::max_value());
+		assert_noop!(
+			NexusTokens::mint(Origin::signed(CENTRAL_BANK), SGD, BOB, 1),
+			Error::<Test>::Overflow
+		);
+	});
+}
+
+#[test]
+fn burn_works() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+
+		assert_ok!(NexusTokens::burn(
+			Origin::signed(CENTRAL_BANK),
+			SGD,
+			ALICE,
+			400
+		));
+
+		assert_eq!(NexusTokens::free_balance(SGD, &ALICE), 600);
+		assert_eq!(NexusTokens::asset(SGD).unwrap().supply, 600);
+		assert_eq!(last_event(), Event::Burned(SGD, ALICE, 400));
+	});
+}
+
+#[test]
+fn burning_everything_removes_the_account() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+
+		assert_ok!(NexusTokens::burn(
+			Origin::signed(CENTRAL_BANK),
+			SGD,
+			ALICE,
+			1_000
+		));
+
+		assert!(!crate::Accounts::<Test>::contains_key(SGD, ALICE));
+	});
+}
+
+#[test]
+fn burn_fails_for_non_issuers_unknown_assets_and_low_balances() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+
+		assert_noop!(
+			NexusTokens::burn(Origin::signed(ALICE), SGD, ALICE, 100),
+			Error::<Test>::NotIssuer
+		);
+		assert_noop!(
+			NexusTokens::burn(Origin::signed(CENTRAL_BANK), INR, ALICE, 100),
+			Error::<Test>::UnknownAsset
+		);
+		assert_noop!(
+			NexusTokens::burn(Origin::signed(CENTRAL_BANK), SGD, ALICE, 1_001),
+			Error::<Test>::BalanceTooLow
+		);
+	});
+}
+
+#[test]
+fn transfer_works() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+
+		assert_ok!(NexusTokens::transfer(Origin::signed(ALICE), SGD, BOB, 300));
+
+		assert_eq!(NexusTokens::free_balance(SGD, &ALICE), 700);
+		assert_eq!(NexusTokens::free_balance(SGD, &BOB), 300);
+		assert_eq!(last_event(), Event::Transferred(SGD, ALICE, BOB, 300));
+	});
+}
+
+#[test]
+fn transfer_fails_for_unknown_assets_and_low_balances() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+
+		assert_noop!(
+			NexusTokens::transfer(Origin::signed(ALICE), INR, BOB, 300),
+			Error::<Test>::UnknownAsset
+		);
+		assert_noop!(
+			NexusTokens::transfer(Origin::signed(ALICE), SGD, BOB, 1_001),
+			Error::<Test>::BalanceTooLow
+		);
+	});
+}
+
+#[test]
+fn reserve_and_unreserve_move_between_free_and_reserved() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+
+		assert_ok!(NexusTokens::reserve(SGD, &ALICE, 600));
+		assert_eq!(NexusTokens::free_balance(SGD, &ALICE), 400);
+		assert_eq!(NexusTokens::reserved_balance(SGD, &ALICE), 600);
+		assert_eq!(
+			NexusTokens::reserve(SGD, &ALICE, 401),
+			Err(Error::<Test>::BalanceTooLow.into())
+		);
+
+		assert_eq!(NexusTokens::unreserve(SGD, &ALICE, 700), 100);
+		assert_eq!(NexusTokens::free_balance(SGD, &ALICE), 1_000);
+		assert_eq!(NexusTokens::reserved_balance(SGD, &ALICE), 0);
+	});
+}
+
+#[test]
+fn repatriate_reserved_pays_the_beneficiary() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE, 1_000);
+		assert_ok!(NexusTokens::reserve(SGD, &ALICE, 600));
+
+		assert_eq!(
+			NexusTokens::repatriate_reserved(SGD, &ALICE, &BOB, 700),
+			Ok(100)
+		);
+		assert_eq!(NexusTokens::reserved_balance(SGD, &ALICE), 0);
+		assert_eq!(NexusTokens::free_balance(SGD, &ALICE), 400);
+		assert_eq!(NexusTokens::free_balance(SGD, &BOB), 600);
+	});
+}
@@ -0,0 +1,94 @@
+//! Runtime APIs for reading the participants, SLDs, quotes, payee lookups and payments of the
+//! Nexus pallets.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+pub use nexus_api_final::{
+	ChargeBearer, CostDisclosure, PaymentInfo, PaymentTransition, SettlementStruct,
+};
+pub use nexus_api_payee::{DestinationPayee, Payee, PayeeLookup};
+pub use nexus_api_quote::QuoteInfo;
+pub use nexus_api_sld::Sld;
+pub use nexus_directory::{Participant, ParticipantStatus};
+
+sp_api::decl_runtime_apis! {
+	/// The participants registered in the directory.
+	pub trait NexusDirectoryApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// The participant registered for the given account.
+		fn participant(who: AccountId) -> Option<Participant<AccountId>>;
+
+		/// The participant with the given BIC.
+		fn participant_by_bic(bic: Vec<u8>) -> Option<(AccountId, Participant<AccountId>)>;
+
+		/// The participant with the given LEI.
+		fn participant_by_lei(lei: Vec<u8>) -> Option<(AccountId, Participant<AccountId>)>;
+
+		/// The participant with the given BIC or LEI.
+		fn lookup(identifier: Vec<u8>) -> Option<(AccountId, Participant<AccountId>)>;
+	}
+
+	/// The SLDs published by the IPSs.
+	pub trait NexusSldApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// Every SLD published for the given country, with the IPS that published it.
+		fn slds(country_id: Vec<u8>) -> Vec<(AccountId, Sld)>;
+	}
+
+	/// The quotes provided by the FXPs.
+	pub trait NexusQuoteApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The public quotes between two currencies that anyone may still lock, with `amount`
+		/// converted at each rate.
+		fn quotes(
+			source_currency: Vec<u8>,
+			destination_currency: Vec<u8>,
+			amount: u128,
+		) -> Vec<QuoteInfo<AccountId, BlockNumber>>;
+	}
+
+	/// The payee lookups of the source PSPs.
+	pub trait NexusPayeeApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// The payee the given PSP looked up under `lookup_id`.
+		fn payee_lookup(source_psp: AccountId, lookup_id: Vec<u8>) -> Option<PayeeLookup>;
+	}
+
+	/// The final payments.
+	pub trait NexusPaymentApi<AccountId, Balance, Hash, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		Hash: Codec,
+		BlockNumber: Codec,
+	{
+		/// The payment with the given id, whoever sent it.
+		fn payment(payment_uuid: Vec<u8>) -> Option<PaymentInfo<AccountId, Balance, Hash, BlockNumber>>;
+
+		/// What a payment would cost before it is sent.
+		fn cost_disclosure(
+			source_psp: AccountId,
+			settlement: SettlementStruct<AccountId, Balance>,
+			charge_bearer: ChargeBearer,
+		) -> CostDisclosure<Balance>;
+
+		/// The ids of the payments the given PSP has sent or is the destination PSP of.
+		fn payments_by_psp(psp: AccountId) -> Vec<Vec<u8>>;
+	}
+
+	/// The status changes of the final payments.
+	pub trait NexusPaymentTransitionApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The payments whose status changed in the block, with the status they ended it in.
+		fn payment_transitions() -> Vec<PaymentTransition<AccountId, BlockNumber>>;
+	}
+}
@@ -46,6 +46,11 @@ pub use nexus_api_final;
 pub use nexus_api_payee;
 pub use nexus_api_quote;
 pub use nexus_api_sld;
+pub use nexus_directory;
+pub use nexus_htlc;
+pub use nexus_roles;
+pub use nexus_screening;
+pub use nexus_tokens;
 /// -----Import the nexus pallets-------
 
 /// An index to a block.
@@ -102,10 +107,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// This determines the average expected block time that we are targeting.
@@ -265,20 +270,123 @@ impl pallet_sudo::Config for Runtime {
 }
 
 /// Configure the nexus pallets.
+impl nexus_roles::Config for Runtime {
+	type Event = Event;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type WeightInfo = nexus_roles::weights::SubstrateWeight<Runtime>;
+}
+
+impl nexus_directory::Config for Runtime {
+	type Event = Event;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type WeightInfo = nexus_directory::weights::SubstrateWeight<Runtime>;
+}
+
+impl nexus_tokens::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type WeightInfo = nexus_tokens::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const MinLockPeriod: BlockNumber = 10 * MINUTES;
+}
+
+impl nexus_htlc::Config for Runtime {
+	type Event = Event;
+	type Assets = NexusTokens;
+	type MinLockPeriod = MinLockPeriod;
+	type OnResolved = NexusApiFinal;
+	type WeightInfo = nexus_htlc::weights::SubstrateWeight<Runtime>;
+}
+
+impl nexus_screening::Config for Runtime {
+	type Event = Event;
+	type ListOrigin = nexus_roles::EnsureRegulator<Runtime>;
+	type WeightInfo = nexus_screening::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	/// ISO 20022 free text is at most 140 characters, and every other Nexus field is shorter.
+	pub const NexusMaxFieldLength: u32 = 140;
+}
+
 impl nexus_api_sld::Config for Runtime {
 	type Event = Event;
+	type IpsOrigin = nexus_roles::EnsureIpsOperator<Runtime>;
+	type MaxFieldLength = NexusMaxFieldLength;
+	type WeightInfo = nexus_api_sld::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const QuoteLifetime: BlockNumber = 10 * MINUTES;
 }
 
 impl nexus_api_quote::Config for Runtime {
 	type Event = Event;
+	type FxpOrigin = nexus_roles::EnsureFxp<Runtime>;
+	type SourcePspOrigin = nexus_roles::EnsureSourcePsp<Runtime>;
+	type QuoteLifetime = QuoteLifetime;
+	type MaxFieldLength = NexusMaxFieldLength;
+	type WeightInfo = nexus_api_quote::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const PayeeRetentionPeriod: BlockNumber = 90 * DAYS;
+	pub const PayeeMaxErasuresPerBlock: u32 = 100;
 }
 
 impl nexus_api_payee::Config for Runtime {
 	type Event = Event;
+	type SourcePspOrigin = nexus_roles::EnsureSourcePsp<Runtime>;
+	type DestinationPspOrigin = nexus_roles::EnsureDestinationPsp<Runtime>;
+	type Directory = NexusDirectory;
+	type RetentionPeriod = PayeeRetentionPeriod;
+	type MaxErasuresPerBlock = PayeeMaxErasuresPerBlock;
+	type MaxFieldLength = NexusMaxFieldLength;
+	type WeightInfo = nexus_api_payee::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const RecallWindow: BlockNumber = 10 * DAYS;
+	pub const RecallResponseWindow: BlockNumber = 15 * DAYS;
+	pub const ReturnWindow: BlockNumber = 2 * DAYS;
+	pub const LimitDayLength: BlockNumber = DAYS;
+	pub const LimitMonthLength: BlockNumber = 30 * DAYS;
+	pub const MaxBatchSize: u32 = 500;
+	pub const PaymentRetentionPeriod: BlockNumber = 5 * 365 * DAYS;
+	pub const PaymentMaxErasuresPerBlock: u32 = 50;
+	pub const MaxEnvelopeLength: u32 = 64 * 1024;
+	pub const MaxEnvelopeRecipients: u32 = 16;
+	pub const MaxRegulators: u32 = 8;
 }
 
 impl nexus_api_final::Config for Runtime {
 	type Event = Event;
+	type Assets = NexusTokens;
+	type Htlc = NexusHtlc;
+	type SourcePspOrigin = nexus_roles::EnsureSourcePsp<Runtime>;
+	type DestinationPspOrigin = nexus_roles::EnsureDestinationPsp<Runtime>;
+	type Directory = NexusDirectory;
+	type Screening = NexusScreening;
+	type ScreeningOrigin = nexus_roles::EnsureRegulator<Runtime>;
+	type Quotes = NexusApiQuote;
+	type Payees = NexusApiPayee;
+	type Sld = NexusApiSLD;
+	type LimitOrigin = nexus_roles::EnsureRegulator<Runtime>;
+	type DayLength = LimitDayLength;
+	type MonthLength = LimitMonthLength;
+	type RecallWindow = RecallWindow;
+	type RecallResponseWindow = RecallResponseWindow;
+	type ReturnWindow = ReturnWindow;
+	type RetentionPeriod = PaymentRetentionPeriod;
+	type MaxErasuresPerBlock = PaymentMaxErasuresPerBlock;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxFieldLength = NexusMaxFieldLength;
+	type MaxEnvelopeLength = MaxEnvelopeLength;
+	type MaxEnvelopeRecipients = MaxEnvelopeRecipients;
+	type MaxRegulators = MaxRegulators;
+	type WeightInfo = nexus_api_final::weights::SubstrateWeight<Runtime>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -296,11 +404,19 @@ construct_runtime!(
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Module, Storage},
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
-		// Include the custom logic from the template pallet in the runtime.
-		NexusApiSLD: nexus_api_sld::{Module, Call, Storage, Event<T>},
-		NexusApiPayee: nexus_api_payee::{Module, Call, Storage, Event<T>},
-		NexusApiQuote: nexus_api_quote::{Module, Call, Storage, Event<T>},
-		NexusApiFinal: nexus_api_final::{Module, Call, Storage, Event<T>},
+		// Include the custom logic from the template pallet in the runtime. The names of these
+		// four are the prefixes `decl_storage!` kept their items under, which the pallet macro
+		// takes from here, so renaming one would leave its storage behind.
+		NexusApiSLD: nexus_api_sld::{Module, Call, Storage, Config, Event<T>},
+		NexusApiPayee: nexus_api_payee::{Module, Call, Storage, Config, Event<T>},
+		NexusApiQuote: nexus_api_quote::{Module, Call, Storage, Config, Event<T>},
+		NexusApiFinal: nexus_api_final::{Module, Call, Storage, Config, Event<T>},
+		// Pallets added later go last, so the indices of the ones above stay the same.
+		NexusTokens: nexus_tokens::{Module, Call, Storage, Config<T>, Event<T>},
+		NexusHtlc: nexus_htlc::{Module, Call, Storage, Event<T>},
+		NexusScreening: nexus_screening::{Module, Call, Storage, Event<T>},
+		NexusRoles: nexus_roles::{Module, Call, Storage, Config<T>, Event<T>},
+		NexusDirectory: nexus_directory::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 
@@ -469,6 +585,85 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl nexus_runtime_api::NexusPaymentApi<Block, AccountId, Balance, Hash, BlockNumber>
+		for Runtime {
+		fn payment(
+			payment_uuid: Vec<u8>,
+		) -> Option<nexus_api_final::PaymentInfo<AccountId, Balance, Hash, BlockNumber>> {
+			NexusApiFinal::payment_info(payment_uuid)
+		}
+
+		fn cost_disclosure(
+			source_psp: AccountId,
+			settlement: nexus_api_final::SettlementStruct<AccountId, Balance>,
+			charge_bearer: nexus_api_final::ChargeBearer,
+		) -> nexus_api_final::CostDisclosure<Balance> {
+			NexusApiFinal::cost_disclosure(&source_psp, &settlement, charge_bearer)
+		}
+
+		fn payments_by_psp(psp: AccountId) -> Vec<Vec<u8>> {
+			NexusApiFinal::payments_by_psp(&psp)
+		}
+	}
+
+	impl nexus_runtime_api::NexusPaymentTransitionApi<Block, AccountId, BlockNumber> for Runtime {
+		fn payment_transitions() -> Vec<nexus_api_final::PaymentTransition<AccountId, BlockNumber>> {
+			NexusApiFinal::payment_transitions(System::events().into_iter().filter_map(|record| {
+				match record.event {
+					Event::nexus_api_final(event) => Some(event),
+					_ => None,
+				}
+			}))
+		}
+	}
+
+	impl nexus_runtime_api::NexusSldApi<Block, AccountId> for Runtime {
+		fn slds(country_id: Vec<u8>) -> Vec<(AccountId, nexus_api_sld::Sld)> {
+			NexusApiSLD::slds_for_country(&country_id)
+		}
+	}
+
+	impl nexus_runtime_api::NexusQuoteApi<Block, AccountId, BlockNumber> for Runtime {
+		fn quotes(
+			source_currency: Vec<u8>,
+			destination_currency: Vec<u8>,
+			amount: u128,
+		) -> Vec<nexus_api_quote::QuoteInfo<AccountId, BlockNumber>> {
+			NexusApiQuote::open_quotes(&source_currency, &destination_currency, amount)
+		}
+	}
+
+	impl nexus_runtime_api::NexusPayeeApi<Block, AccountId> for Runtime {
+		fn payee_lookup(
+			source_psp: AccountId,
+			lookup_id: Vec<u8>,
+		) -> Option<nexus_api_payee::PayeeLookup> {
+			NexusApiPayee::payee_lookup(&source_psp, &lookup_id)
+		}
+	}
+
+	impl nexus_runtime_api::NexusDirectoryApi<Block, AccountId> for Runtime {
+		fn participant(who: AccountId) -> Option<nexus_directory::Participant<AccountId>> {
+			NexusDirectory::participant(&who)
+		}
+
+		fn participant_by_bic(
+			bic: Vec<u8>,
+		) -> Option<(AccountId, nexus_directory::Participant<AccountId>)> {
+			NexusDirectory::participant_by_bic(&bic)
+		}
+
+		fn participant_by_lei(
+			lei: Vec<u8>,
+		) -> Option<(AccountId, nexus_directory::Participant<AccountId>)> {
+			NexusDirectory::participant_by_lei(&lei)
+		}
+
+		fn lookup(identifier: Vec<u8>) -> Option<(AccountId, nexus_directory::Participant<AccountId>)> {
+			NexusDirectory::lookup(&identifier)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(
@@ -503,6 +698,15 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+			add_benchmark!(params, batches, nexus_roles, NexusRoles);
+			add_benchmark!(params, batches, nexus_directory, NexusDirectory);
+			add_benchmark!(params, batches, nexus_tokens, NexusTokens);
+			add_benchmark!(params, batches, nexus_htlc, NexusHtlc);
+			add_benchmark!(params, batches, nexus_screening, NexusScreening);
+			add_benchmark!(params, batches, nexus_api_sld, NexusApiSLD);
+			add_benchmark!(params, batches, nexus_api_quote, NexusApiQuote);
+			add_benchmark!(params, batches, nexus_api_payee, NexusApiPayee);
+			add_benchmark!(params, batches, nexus_api_final, NexusApiFinal);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)